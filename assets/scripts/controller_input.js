@@ -40,6 +40,33 @@ window.controller_state_raw = {
 };
 window.controller_state = JSON.stringify(window.controller_state_raw);
 
+// Tilt sensor cartridges (MBC7), each key tilts fully along one axis
+const tilt_keybindings = {
+    i: ["y", -1],
+    k: ["y", 1],
+    j: ["x", -1],
+    l: ["x", 1],
+};
+
+window.tilt_state_raw = { x: 0, y: 0 };
+window.tilt_state = JSON.stringify(window.tilt_state_raw);
+
+function update_tilt_state(key, pressed) {
+    const binding = tilt_keybindings[key];
+    if (!binding) {
+        return false;
+    }
+
+    const [axis, direction] = binding;
+    if (pressed) {
+        window.tilt_state_raw[axis] = direction;
+    } else if (window.tilt_state_raw[axis] == direction) {
+        window.tilt_state_raw[axis] = 0;
+    }
+    window.tilt_state = JSON.stringify(window.tilt_state_raw);
+    return true;
+}
+
 export function button_up(button) {
     if (button in window.controller_state_raw) {
         window.controller_state_raw[button] = false;
@@ -132,16 +159,27 @@ export function configure_keybindings() {
     document.querySelector("#keybinding_dialog > form").addEventListener("submit", onsubmit);
 }
 
+// Hold to rewind
+const rewind_key = "backspace";
+window.rewind_held = false;
+
 function initialize_input() {
     const update_button_state = (event, state) => {
         let fn = state ? button_down : button_up;
 
+        if (event.key.toLowerCase() == rewind_key) {
+            window.rewind_held = state;
+            return;
+        }
+
         for (let button of Object.keys(current_keybindings)) {
             if (current_keybindings[button] == event.key.toLowerCase()) {
                 fn(button)
                 return;
             }
         }
+
+        update_tilt_state(event.key.toLowerCase(), state);
     }
 
     addEventListener("keydown", (event) => update_button_state(event, true));
@@ -4,6 +4,7 @@ await init();
 import AudioContext from "./audio_context.js";
 
 const ctx = document.querySelector("#screen").getContext("2d");
+const cpu_status = document.querySelector("#cpu_status");
 const app = new Application();
 
 let audio = new AudioContext();
@@ -18,6 +19,7 @@ function run(time) {
 
   audio.pushSamples(samples);
   ctx.putImageData(screen_image, 0, 0);
+  cpu_status.innerText = app.cpu_lock() ?? "";
 }
 
 // const loop = (time) => {
@@ -26,7 +26,10 @@ const menu_content = document.querySelector("#menu_content");
 
 let save = document.createElement("button");
 save.innerText = "Save";
-save.onclick = () => wasm.save_save_state(0);
+save.onclick = () => {
+  wasm.save_save_state(0);
+  render_save_slots();
+};
 menu_content.appendChild(save);
 
 let load = document.createElement("button");
@@ -34,6 +37,49 @@ load.innerText = "Load";
 load.onclick = () => wasm.load_save_state(0);
 menu_content.appendChild(load);
 
+// Slot picker with a preview of every save state
+const save_slots = 10;
+const save_states_container = document.querySelector("#save_states_container");
+
+function render_save_slots() {
+  save_states_container.replaceChildren();
+
+  for (let slot = 0; slot < save_slots; slot++) {
+    let row = document.createElement("div");
+    row.className = "save_slot";
+
+    let preview = document.createElement("canvas");
+    preview.width = 80;
+    preview.height = 72;
+    let thumbnail = wasm.save_state_thumbnail(slot);
+    if (thumbnail) {
+      preview.getContext("2d").putImageData(thumbnail, 0, 0);
+    }
+    row.appendChild(preview);
+
+    let label = document.createElement("span");
+    label.innerText = wasm.save_state_label(slot) ?? `Slot ${slot}: empty`;
+    row.appendChild(label);
+
+    let save_slot = document.createElement("button");
+    save_slot.innerText = "Save";
+    save_slot.onclick = () => {
+      wasm.save_save_state(slot);
+      render_save_slots();
+    };
+    row.appendChild(save_slot);
+
+    let load_slot = document.createElement("button");
+    load_slot.innerText = "Load";
+    load_slot.onclick = () => wasm.load_save_state(slot);
+    row.appendChild(load_slot);
+
+    save_states_container.appendChild(row);
+  }
+}
+
+render_save_slots();
+
 let toggle_vsync = document.createElement("input");
 toggle_vsync.type = "checkbox";
 toggle_vsync.id = "toggle_vsync";
@@ -39,11 +39,40 @@ async function load_rom({ name, path }) {
   await load_rom_internal({ name, path });
 }
 
+// Patch applied to the next rom that is loaded
+let selected_patch = null;
+
 async function load_rom_internal({ name, path }) {
   let rom_data = new Uint8Array(await (await fetch(path)).arrayBuffer());
-  app.load_rom(rom_data, path);
+  load_rom_data(name, rom_data, path);
+}
+
+function load_rom_data(name, rom_data, path) {
+  try {
+    app.load_rom(rom_data, path, selected_patch);
+  } catch (error) {
+    console.error(`Failed to load ${name}: ${error}`);
+  }
 }
 
+const read_file = (file) =>
+  file.arrayBuffer().then((buffer) => new Uint8Array(buffer));
+
+document.getElementById("rom_upload_input").onchange = async (event) => {
+  const file = event.target.files[0];
+  if (file) {
+    load_rom_data(file.name, await read_file(file), undefined);
+  }
+};
+
+document.getElementById("patch_upload_input").onchange = async (event) => {
+  const file = event.target.files[0];
+  selected_patch = file ? await read_file(file) : null;
+  document.getElementById("patch_upload_name").innerText = file
+    ? `Patch: ${file.name}`
+    : "Apply a patch (IPS, UPS, BPS)";
+};
+
 get_available_roms().then((roms) => {
   document
     .getElementById("roms_container")
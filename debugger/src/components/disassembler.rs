@@ -4,7 +4,7 @@ use egui::style::Spacing;
 use egui::{Align, Button, Color32, Rgba, Stroke, Style, Ui, Vec2};
 use egui_extras::{Column, TableBuilder};
 use gameboy::Gameboy;
-use sm83::instruction::Fetch;
+use sm83::disassembler::disassemble;
 use sm83::memory_mapper::MemoryMapper;
 use sm83::registers::{Addressable, CPURegister16};
 use sm83::Instruction;
@@ -25,37 +25,20 @@ pub struct Disassembler {
 }
 
 pub fn generate_instructions(gb: &Gameboy) -> Vec<DisassembledInstruction> {
-	let mut gb = gb.clone();
-
-	let mut instructions = vec![];
-
-	gb.cpu_state.write(CPURegister16::PC, 0);
-
-	loop {
-		let pc = gb.cpu_state.read(CPURegister16::PC);
-		let instruction = gb.fetch();
-		let new_pc = gb.cpu_state.read(CPURegister16::PC);
-
-		if new_pc <= pc {
-			// Detect looping back around to the start of memory
-			// This is likely to cause an infinite loop if not avoided
-			break;
-		}
-
-		let mut bytes = vec![];
-		for addr in pc..new_pc {
-			bytes.push(format!("{:02X}", gb.read(addr)));
-		}
-		let bytes = bytes.join(", ");
-
-		instructions.push(DisassembledInstruction {
-			addr: pc,
-			instruction,
-			bytes,
-		});
-	}
-
-	instructions
+	let memory: Vec<u8> = (0..=0xFFFF).map(|addr| gb.read(addr)).collect();
+
+	disassemble(&memory, 0)
+		.map(|instruction| DisassembledInstruction {
+			addr: instruction.address,
+			instruction: instruction.instruction,
+			bytes: instruction
+				.bytes
+				.iter()
+				.map(|byte| format!("{byte:02X}"))
+				.collect::<Vec<_>>()
+				.join(", "),
+		})
+		.collect()
 }
 
 impl Disassembler {
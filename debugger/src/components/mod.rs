@@ -6,6 +6,7 @@ mod memory_image;
 mod memory_view;
 mod rom_loader;
 pub mod run_controller;
+mod save_states;
 mod screen;
 mod system_info;
 mod timeline;
@@ -20,6 +21,7 @@ pub use joypad::JoypadInput;
 pub use memory_image::MemoryImage;
 pub use memory_view::MemoryView;
 pub use rom_loader::RomLoader;
+pub use save_states::SaveStates;
 pub use screen::Screen;
 pub use system_info::show_system_info;
 pub use timeline::{CheckpointManager, TStates};
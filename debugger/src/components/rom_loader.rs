@@ -65,15 +65,20 @@ impl RomLoader {
 		});
 	}
 
-	pub fn draw(&mut self, ui: &mut Ui, gameboy: &mut Gameboy) {
+	/// Returns the replaced emulator when a new rom was loaded
+	pub fn draw(&mut self, ui: &mut Ui, gameboy: &mut Gameboy) -> Option<Gameboy> {
+		let mut replaced = None;
 		if recursive_dir(ui, &mut self.url, &ROMS.with(|r| r.clone())) {
 			self.load_rom(ui);
 		}
 
 		match LOAD_RESULT.with(|r| r.borrow_mut().take()) {
 			Some(Ok(resource)) => {
-				*gameboy = Gameboy::cgb();
-				gameboy.load_rom(&resource.response.bytes, None);
+				let mut new_gameboy = Gameboy::cgb();
+				match new_gameboy.load_rom(&resource.response.bytes, None) {
+					Ok(()) => replaced = Some(std::mem::replace(gameboy, new_gameboy)),
+					Err(error) => self.error_msg = Some(error.to_string()),
+				}
 			}
 			Some(Err(error)) => {
 				let msg = if error.is_empty() { "Error" } else { &error };
@@ -85,5 +90,7 @@ impl RomLoader {
 		if let Some(error) = &self.error_msg {
 			ui.label(error);
 		}
+
+		replaced
 	}
 }
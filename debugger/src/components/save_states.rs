@@ -0,0 +1,169 @@
+use egui::{load::SizedTexture, Color32, ColorImage, Image, TextureHandle, TextureOptions, Ui};
+#[cfg(not(target_arch = "wasm32"))]
+use gameboy::save_state::file_save_manager::FileSaveManager;
+#[cfg(target_arch = "wasm32")]
+use gameboy::save_state::MemorySaveManager;
+use gameboy::{
+	cartridge::header::RomFingerprint,
+	save_state::{
+		thumbnail::{THUMBNAIL_HEIGHT, THUMBNAIL_WIDTH},
+		SaveManager, SaveState, SaveStateEntry, SAVE_SLOTS,
+	},
+	Gameboy,
+};
+
+// Native builds keep the saves of each rom on disk, the web build only has memory
+#[cfg(not(target_arch = "wasm32"))]
+type Manager = FileSaveManager;
+#[cfg(target_arch = "wasm32")]
+type Manager = MemorySaveManager;
+
+// Save state slots with a preview of each state
+#[derive(Default)]
+pub struct SaveStates {
+	manager: Option<Manager>,
+	// Rom the manager belongs to
+	rom: Option<RomFingerprint>,
+	entries: Vec<Option<SaveStateEntry>>,
+	thumbnails: Vec<Option<TextureHandle>>,
+	error_msg: Option<String>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SaveStates {
+	/// Switches to the saves of the loaded rom, loading its battery save,
+	/// and flushes battery backed memory periodically. Called every frame.
+	pub fn update(&mut self, gameboy: &mut Gameboy) {
+		let Some(info) = gameboy.cartridge_state.as_ref().map(|cart| &cart.info) else {
+			return;
+		};
+
+		if self.rom != Some(info.fingerprint) {
+			let manager = FileSaveManager::new(FileSaveManager::default_directory(), info);
+			self.rom = Some(info.fingerprint);
+			self.entries.clear();
+			self.error_msg = manager
+				.load_battery(gameboy)
+				.err()
+				.map(|err| format!("{err:?}"));
+			self.manager = Some(manager);
+		}
+
+		if let Some(manager) = &mut self.manager {
+			if let Err(err) = manager.flush_battery_periodically(gameboy) {
+				self.error_msg = Some(format!("{err:?}"));
+			}
+		}
+	}
+
+	/// Writes battery backed memory of the rom, when it is replaced or the debugger exits
+	pub fn flush_battery(&mut self, gameboy: &mut Gameboy) {
+		let same_rom = gameboy
+			.cartridge_state
+			.as_ref()
+			.is_some_and(|cart| Some(cart.info.fingerprint) == self.rom);
+
+		if let (Some(manager), true) = (&mut self.manager, same_rom) {
+			if let Err(err) = manager.flush_battery(gameboy) {
+				self.error_msg = Some(format!("{err:?}"));
+			}
+		}
+	}
+}
+
+// Battery saves are not persisted on the web
+#[cfg(target_arch = "wasm32")]
+impl SaveStates {
+	pub fn update(&mut self, gameboy: &mut Gameboy) {
+		let rom = gameboy
+			.cartridge_state
+			.as_ref()
+			.map(|cart| cart.info.fingerprint);
+		if rom.is_some() && self.rom != rom {
+			self.rom = rom;
+			self.entries.clear();
+			self.manager = Some(MemorySaveManager::default());
+		}
+	}
+
+	pub fn flush_battery(&mut self, _gameboy: &mut Gameboy) {}
+}
+
+impl SaveStates {
+	fn refresh(&mut self, ui: &Ui) {
+		self.entries = match &self.manager {
+			Some(manager) => manager.get_save_states(),
+			None => vec![None; SAVE_SLOTS],
+		};
+		self.thumbnails = self
+			.entries
+			.iter()
+			.enumerate()
+			.map(|(slot, entry)| {
+				let thumbnail = entry.as_ref()?.thumbnail.as_ref()?;
+				let pixels = thumbnail
+					.to_rgba()
+					.chunks_exact(4)
+					.map(|color| Color32::from_rgb(color[0], color[1], color[2]))
+					.collect();
+
+				let image = ColorImage {
+					size: [THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT],
+					pixels,
+				};
+				Some(ui.ctx().load_texture(
+					format!("save_state_{slot}"),
+					image,
+					TextureOptions::NEAREST,
+				))
+			})
+			.collect();
+	}
+
+	pub fn draw(&mut self, gameboy: &mut Gameboy, ui: &mut Ui) {
+		if self.entries.len() != SAVE_SLOTS {
+			self.refresh(ui);
+		}
+
+		let Some(manager) = &mut self.manager else {
+			ui.label("No rom loaded");
+			return;
+		};
+
+		for slot in 0..SAVE_SLOTS {
+			ui.horizontal(|ui| {
+				let size = egui::vec2(THUMBNAIL_WIDTH as f32, THUMBNAIL_HEIGHT as f32);
+				match &self.thumbnails[slot] {
+					Some(texture) => ui.add(Image::new(SizedTexture::new(texture, size))),
+					None => ui.allocate_exact_size(size, egui::Sense::hover()).1,
+				};
+
+				match &self.entries[slot] {
+					Some(entry) => ui.label(entry.to_string()),
+					None => ui.label(format!("Slot {slot}: empty")),
+				};
+
+				if ui.button("Save").clicked() {
+					let result = SaveState::try_from(&*gameboy)
+						.and_then(|save| manager.save_save_state(save, slot));
+					self.error_msg = result.err().map(|err| format!("{err:?}"));
+					self.entries.clear();
+				}
+
+				if ui.button("Load").clicked() {
+					let result = manager
+						.load_save_state(slot)
+						.and_then(|save| gameboy.try_load_save_state(&save));
+					match result {
+						Ok(state) => *gameboy = state,
+						Err(err) => self.error_msg = Some(format!("{err:?}")),
+					}
+				}
+			});
+		}
+
+		if let Some(error) = &self.error_msg {
+			ui.label(error);
+		}
+	}
+}
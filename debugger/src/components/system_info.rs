@@ -1,4 +1,4 @@
-use egui::Ui;
+use egui::{Color32, Ui};
 use egui_extras::{Column, TableBuilder};
 use gameboy::{
 	io_registers::{IE, IF},
@@ -63,6 +63,10 @@ fn cpu_info(gb: &Gameboy, ui: &mut Ui) {
 	use sm83::registers::{CPURegister16::*, CPURegister8::*};
 	ui.label(format!("Speed: {:?}", gb.mode.get_speed()));
 	ui.label(bool!("Halted:{}", cpu.halted));
+	ui.label(bool!("Locked:{}", cpu.locked.is_some()));
+	if let Some(lock) = cpu.locked {
+		ui.colored_label(Color32::RED, lock.to_string());
+	}
 	ui.label(bool!("Booting:{}", gb.booting));
 
 	ui.horizontal(|ui| {
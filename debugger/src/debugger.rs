@@ -1,7 +1,7 @@
 use crate::components::{
 	run_controller::{self, RunController},
 	show_system_info, AudioVisualizer, CheckpointManager, Disassembler, JoypadInput, MemoryImage,
-	MemoryView, RomLoader, Screen, VramView,
+	MemoryView, RomLoader, SaveStates, Screen, VramView,
 };
 use egui::{CentralPanel, SidePanel, Style, TextStyle, TopBottomPanel, Window};
 
@@ -33,6 +33,9 @@ pub struct Debugger {
 
 	audio_visualizer: AudioVisualizer,
 	audio_visualizer_enabled: bool,
+
+	save_states: SaveStates,
+	save_states_enabled: bool,
 }
 
 impl Debugger {
@@ -40,9 +43,14 @@ impl Debugger {
 		Default::default()
 	}
 
-	// Returns true if breakpoint was hit
+	// Returns true if breakpoint was hit or the CPU just locked up
 	fn step_gb(&mut self) -> bool {
+		let was_locked = self.gameboy.cpu_state.locked.is_some();
 		self.gameboy.step();
+		if !was_locked && self.gameboy.cpu_state.locked.is_some() {
+			self.run_controller.state = run_controller::RunningState::Broke;
+			return true;
+		}
 		self.disassembler.should_break(&self.gameboy)
 	}
 }
@@ -79,8 +87,10 @@ impl eframe::App for Debugger {
 							}
 						}
 						run_controller::Action::NextInterrupt => {
+							// A locked CPU never services interrupts
 							while !(self.gameboy.cpu_state.interrupt_pending()
 								&& (self.gameboy.cpu_state.ime() || self.gameboy.cpu_state.halted))
+								&& self.gameboy.cpu_state.locked.is_none()
 							{
 								if self.step_gb() {
 									break;
@@ -109,7 +119,9 @@ impl eframe::App for Debugger {
 					}
 				}
 
-				self.rom_loader.draw(ui, &mut self.gameboy);
+				if let Some(mut previous) = self.rom_loader.draw(ui, &mut self.gameboy) {
+					self.save_states.flush_battery(&mut previous);
+				}
 
 				ui.checkbox(&mut self.checkpoint_manager_enabled, "Checkpoint Manager");
 				ui.checkbox(&mut self.memory_image_enabled, "Memory Image");
@@ -117,9 +129,12 @@ impl eframe::App for Debugger {
 				ui.checkbox(&mut self.memory_view_enabled, "Memory View");
 				ui.checkbox(&mut self.disassembler_enabled, "Instruction View");
 				ui.checkbox(&mut self.audio_visualizer_enabled, "Audio Visualizer");
+				ui.checkbox(&mut self.save_states_enabled, "Save States");
 			});
 		});
 
+		self.save_states.update(&mut self.gameboy);
+
 		let screen_buffer = self.gameboy.ppu.lcd.front_buffer();
 
 		SidePanel::right("right").show(ctx, |ui| show_system_info(&self.gameboy, ui));
@@ -152,6 +167,14 @@ impl eframe::App for Debugger {
 				.show(ctx, |ui| self.audio_visualizer.draw(&mut self.gameboy, ui));
 		}
 
+		if self.save_states_enabled {
+			Window::new("Save States").show(ctx, |ui| self.save_states.draw(&mut self.gameboy, ui));
+		}
+
 		ctx.request_repaint();
 	}
+
+	fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+		self.save_states.flush_battery(&mut self.gameboy);
+	}
 }
@@ -1,51 +1,211 @@
+use camera::{ImageProvider, ImageSource, PocketCameraState};
 use cartridge_data::CartridgeData;
-use header::{CartridgeInfo, CartridgeParseError, RawCartridgeHeader};
+use header::{
+	CartridgeInfo, CartridgeParseError, RawCartridgeHeader, RomFingerprint, NINTENDO_LOGO,
+};
+use huc1::HuC1State;
+use huc3::HuC3State;
 use mbc1::MBC1State;
 use mbc2::MBC2State;
 use mbc3::MBC3State;
 use mbc5::MBC5State;
+use mbc6::MBC6State;
+use mbc7::MBC7State;
 use memory_bank_controller::Mbc;
+use mmm01::MMM01State;
+use rtc::{Rtc, RtcClock};
 use serde::{Deserialize, Serialize};
 
-use crate::save_state::RomSource;
+use crate::{cheats::GameGenieCode, joypad::TiltState, save_state::RomSource};
 
+mod battery;
+pub mod camera;
 mod cartridge_data;
-mod header;
+pub mod header;
+mod huc1;
+mod huc3;
+mod licensee;
 mod mbc1;
 mod mbc2;
 mod mbc3;
 mod mbc5;
+mod mbc6;
+mod mbc7;
 pub mod memory_bank_controller;
+mod mmm01;
+pub mod rtc;
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Cartridge {
 	pub data: CartridgeData,
 	pub mbc: Mbc,
 	pub info: CartridgeInfo,
+
+	// Set when battery backed memory may have changed since the last export
+	#[serde(skip)]
+	save_dirty: bool,
+
+	// Enabled Game Genie codes, applied to rom reads
+	#[serde(skip)]
+	game_genie_codes: Vec<GameGenieCode>,
 }
 
 impl Cartridge {
 	pub fn try_new(value: &[u8], source: Option<RomSource>) -> Result<Self, CartridgeParseError> {
-		let raw_header = RawCartridgeHeader::new(value, source);
+		let raw_header = RawCartridgeHeader::new(value, source)?;
 		use Mbc::*;
-		let info = raw_header.parse()?;
+		let info = CartridgeInfo {
+			fingerprint: RomFingerprint::new(value),
+			..raw_header.parse()?
+		};
 		let data = CartridgeData::new(value, info.rom_banks, info.ram_banks);
 
 		let mbc = match raw_header.cartridge_type {
 			0x00 => Ok(ROM),
+			0x01..=0x03 if Self::is_mbc1_multicart(value) => Ok(MBC1(MBC1State::multicart())),
 			0x01..=0x03 => Ok(MBC1(MBC1State::default())),
 			0x05 | 0x06 => Ok(MBC2(MBC2State::default())),
 			0x08 | 0x09 => Ok(ROM),
 			0x0F..=0x13 => Ok(MBC3(MBC3State::default())),
-			0x0B..=0x0D => Ok(MMM01),
+			0x0B..=0x0D => Ok(MMM01(MMM01State::default())),
 			0x19..=0x1E => Ok(MBC5(MBC5State::default())),
-			0x20 => Ok(MBC6),
-			0x22 => Ok(MBC7),
-			0xFE => Ok(HUC3),
-			0xFF => Ok(HUC1),
-			_ => Err(CartridgeParseError::MBCType),
+			0x20 => Ok(MBC6(MBC6State::default())),
+			0x22 => Ok(MBC7(MBC7State::default())),
+			0xFC => Ok(CAMERA(PocketCameraState::default())),
+			0xFE => Ok(HUC3(HuC3State::default())),
+			0xFF => Ok(HUC1(HuC1State::default())),
+			_ => Err(CartridgeParseError::MBCType(raw_header.cartridge_type)),
 		}?;
 
-		Ok(Cartridge { data, mbc, info })
+		Ok(Cartridge {
+			data,
+			mbc,
+			info,
+			save_dirty: false,
+			game_genie_codes: vec![],
+		})
+	}
+
+	// MBC1M carts are 1MiB and have a game, with its own header, every 16 banks
+	fn is_mbc1_multicart(rom: &[u8]) -> bool {
+		if rom.len() != 0x100000 {
+			return false;
+		}
+
+		[0x10, 0x20, 0x30].iter().any(|bank| {
+			let offset = bank * 0x4000 + 0x0104;
+			rom[offset..offset + NINTENDO_LOGO.len()] == NINTENDO_LOGO
+		})
+	}
+
+	/// State stored in save states. The info is derived from the rom and rebuilt
+	/// when a save is loaded, so older saves don't break when it changes.
+	pub(crate) fn saved_state(&self) -> (&CartridgeData, &Mbc) {
+		(&self.data, &self.mbc)
+	}
+
+	/// Restores a cartridge from its saved state, the rom and info must be restored by the caller
+	pub(crate) fn from_saved_state(data: CartridgeData, mbc: Mbc) -> Self {
+		Cartridge {
+			data,
+			mbc,
+			info: CartridgeInfo::default(),
+			save_dirty: false,
+			game_genie_codes: vec![],
+		}
+	}
+
+	pub fn set_game_genie_codes(&mut self, codes: Vec<GameGenieCode>) {
+		self.game_genie_codes = codes;
+	}
+
+	/// Writes external RAM at `addr` in $A000-$BFFF directly, without going through the MBC.
+	/// `bank` wraps around the number of RAM banks, carts without RAM ignore the write.
+	pub fn write_ram_bank(&mut self, bank: u8, addr: u16, value: u8) {
+		let offset = (addr as usize - 0xA000) % 0x2000;
+		let (target, value) = match &mut self.mbc {
+			// Built in RAM of 512 half bytes, mirrored through the area
+			Mbc::MBC2(state) => (&mut state.ram_data[offset % 0x200], value | 0xF0),
+			_ if self.data.ram_banks.is_empty() => return,
+			_ => {
+				let banks = self.data.ram_banks.len();
+				(
+					&mut self.data.ram_banks[bank as usize % banks][offset],
+					value,
+				)
+			}
+		};
+
+		if *target != value {
+			*target = value;
+			self.save_dirty |= self.info.has_battery;
+		}
+	}
+
+	/// Brings the real-time clock, if any, up to date with the emulated time
+	pub fn sync_clock(&mut self, t_states: u64) {
+		match &mut self.mbc {
+			Mbc::MBC3(state) => state.sync_clock(t_states),
+			Mbc::HUC3(state) => state.clock.sync(t_states),
+			_ => {}
+		}
+	}
+
+	pub fn set_rtc_clock(&mut self, clock: RtcClock) {
+		match &mut self.mbc {
+			Mbc::MBC3(state) => state.set_rtc_clock(clock),
+			Mbc::HUC3(state) => state.clock.set_clock(clock),
+			_ => {}
+		}
+	}
+
+	/// Advances cartridge hardware that runs independently of bus accesses
+	pub fn tick(&mut self, t_states: u32) {
+		if let Mbc::CAMERA(state) = &mut self.mbc {
+			state.tick(&mut self.data, t_states);
+		}
+	}
+
+	/// Sets where the camera sensor gets its images from
+	pub fn set_image_provider(&mut self, provider: impl ImageProvider + Send + 'static) {
+		if let Mbc::CAMERA(state) = &mut self.mbc {
+			state.image_source = ImageSource::new(provider);
+		}
+	}
+
+	/// Keeps host provided devices when replacing the cartridge state, they aren't serialized
+	pub(crate) fn inherit_devices(&mut self, previous: &Cartridge) {
+		if let (Mbc::CAMERA(state), Mbc::CAMERA(previous)) = (&mut self.mbc, &previous.mbc) {
+			state.image_source = previous.image_source.clone();
+		}
+	}
+
+	/// Returns the tone requested from the cartridge speaker since the last call
+	pub fn take_speaker_tone(&mut self) -> Option<u8> {
+		match &mut self.mbc {
+			Mbc::HUC3(state) => state.take_speaker_tone(),
+			_ => None,
+		}
+	}
+
+	/// Feeds the accelerometer of cartridges with a tilt sensor
+	pub fn set_tilt(&mut self, tilt: &TiltState) {
+		if let Mbc::MBC7(state) = &mut self.mbc {
+			state.set_tilt(tilt);
+		}
+	}
+
+	pub fn rtc(&self) -> Option<&Rtc> {
+		match &self.mbc {
+			Mbc::MBC3(state) => Some(&state.rtc),
+			_ => None,
+		}
+	}
+
+	pub fn rtc_mut(&mut self) -> Option<&mut Rtc> {
+		match &mut self.mbc {
+			Mbc::MBC3(state) => Some(&mut state.rtc),
+			_ => None,
+		}
 	}
 }
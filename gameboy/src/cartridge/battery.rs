@@ -0,0 +1,82 @@
+use crate::save_state::SaveError;
+
+use super::{memory_bank_controller::Mbc, rtc::RTC_FOOTER_SIZE, Cartridge};
+
+// Battery backed memory in the `.sav` format shared with other emulators,
+// RAM is stored as is, followed by the RTC footer on MBC3 carts with a timer
+impl Cartridge {
+	fn has_rtc_footer(&self) -> bool {
+		self.info.has_rtc && matches!(self.mbc, Mbc::MBC3(_))
+	}
+
+	// Size of the save file, excluding the RTC footer
+	fn save_size(&self) -> usize {
+		match &self.mbc {
+			Mbc::MBC2(state) => state.ram_data.len(),
+			Mbc::MBC6(state) => self.data.ram_size() + state.flash.data.len(),
+			Mbc::MBC7(state) => state.eeprom.to_bytes().len(),
+			_ => self.data.ram_size(),
+		}
+	}
+
+	/// True when battery backed memory was written since the last import or export
+	pub fn is_save_dirty(&self) -> bool {
+		self.save_dirty
+	}
+
+	/// Exports battery backed memory as a `.sav` image and clears the dirty flag
+	pub fn export_save(&mut self) -> Vec<u8> {
+		self.save_dirty = false;
+
+		let mut save = match &self.mbc {
+			// Only the lower nibble of MBC2 RAM is stored
+			Mbc::MBC2(state) => state.ram_data.iter().map(|value| value & 0x0F).collect(),
+			Mbc::MBC6(state) => {
+				let mut save = self.data.ram_bytes();
+				save.extend_from_slice(&state.flash.data);
+				save
+			}
+			Mbc::MBC7(state) => state.eeprom.to_bytes(),
+			_ => self.data.ram_bytes(),
+		};
+
+		if let (Mbc::MBC3(state), true) = (&self.mbc, self.has_rtc_footer()) {
+			save.extend(state.rtc.to_save_footer());
+		}
+
+		save
+	}
+
+	/// Imports a `.sav` image, the RTC footer is optional
+	pub fn import_save(&mut self, save: &[u8]) -> Result<(), SaveError> {
+		let size = self.save_size();
+		if save.len() < size {
+			return Err(SaveError::Deserialization);
+		}
+
+		let (memory, footer) = save.split_at(size);
+		match &mut self.mbc {
+			Mbc::MBC2(state) => {
+				for (value, saved) in state.ram_data.iter_mut().zip(memory) {
+					*value = saved | 0xF0;
+				}
+			}
+			Mbc::MBC6(state) => {
+				let (ram, flash) = memory.split_at(self.data.ram_size());
+				self.data.load_ram_bytes(ram);
+				state.flash.data.copy_from_slice(flash);
+			}
+			Mbc::MBC7(state) => state.eeprom.load_bytes(memory),
+			_ => self.data.load_ram_bytes(memory),
+		}
+
+		if !footer.is_empty() && footer.len() <= RTC_FOOTER_SIZE && self.has_rtc_footer() {
+			if let Some(rtc) = self.rtc_mut() {
+				rtc.load_save_footer(footer);
+			}
+		}
+
+		self.save_dirty = false;
+		Ok(())
+	}
+}
@@ -0,0 +1,360 @@
+// https://gbdev.io/pandocs/Gameboy_Camera.html
+
+use std::{
+	fmt::Debug,
+	sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::util::bits::{BIT_0, BIT_3, BIT_7};
+
+use super::cartridge_data::CartridgeData;
+
+/// Dimensions of the image captured by the sensor, in pixels
+pub const SENSOR_WIDTH: usize = 128;
+pub const SENSOR_HEIGHT: usize = 112;
+
+/// Captured image is written to the first RAM bank as 2bpp tiles at this offset
+const IMAGE_OFFSET: usize = 0x100;
+const IMAGE_SIZE: usize = SENSOR_WIDTH * SENSOR_HEIGHT / 4;
+
+/// Exposure time at which the sensor reports the source image unchanged
+const EXPOSURE_REFERENCE: u32 = 0x0800;
+
+/// Edge enhancement ratios selected by bits 4-6 of register 4, in quarters
+const EDGE_RATIOS: [i32; 8] = [2, 3, 4, 5, 8, 12, 16, 20];
+
+/// Supplies the images seen by the camera sensor
+pub trait ImageProvider {
+	/// Returns a `SENSOR_WIDTH` x `SENSOR_HEIGHT` greyscale image, row major,
+	/// 0 is black and 255 is white
+	fn capture(&mut self) -> Vec<u8>;
+}
+
+/// Frontends can provide images from a closure, for example a webcam
+impl<F: FnMut() -> Vec<u8>> ImageProvider for F {
+	fn capture(&mut self) -> Vec<u8> {
+		self()
+	}
+}
+
+/// A fixed image, scaled to the size of the sensor
+#[derive(Clone, Debug)]
+pub struct StaticImage {
+	pixels: Vec<u8>,
+}
+
+impl StaticImage {
+	/// Creates an image from a row major greyscale buffer of the given size
+	pub fn from_greyscale(width: usize, height: usize, pixels: &[u8]) -> Self {
+		let mut scaled = vec![0; SENSOR_WIDTH * SENSOR_HEIGHT];
+		if width == 0 || height == 0 || pixels.len() < width * height {
+			return Self { pixels: scaled };
+		}
+
+		for y in 0..SENSOR_HEIGHT {
+			for x in 0..SENSOR_WIDTH {
+				let src_x = x * width / SENSOR_WIDTH;
+				let src_y = y * height / SENSOR_HEIGHT;
+				scaled[y * SENSOR_WIDTH + x] = pixels[src_y * width + src_x];
+			}
+		}
+
+		Self { pixels: scaled }
+	}
+
+	/// Decodes a PNG image, returning `None` if it is invalid
+	#[cfg(feature = "png")]
+	pub fn from_png(bytes: &[u8]) -> Option<Self> {
+		let image = image::load_from_memory_with_format(bytes, image::ImageFormat::Png)
+			.ok()?
+			.into_luma8();
+		let (width, height) = image.dimensions();
+		Some(Self::from_greyscale(
+			width as usize,
+			height as usize,
+			image.as_raw(),
+		))
+	}
+}
+
+impl ImageProvider for StaticImage {
+	fn capture(&mut self) -> Vec<u8> {
+		self.pixels.clone()
+	}
+}
+
+/// Generated gradient with a checkerboard, scrolling by one pixel every capture
+#[derive(Clone, Debug, Default)]
+pub struct TestPattern {
+	frame: usize,
+}
+
+impl ImageProvider for TestPattern {
+	fn capture(&mut self) -> Vec<u8> {
+		self.frame = self.frame.wrapping_add(1);
+
+		let mut pixels = vec![0; SENSOR_WIDTH * SENSOR_HEIGHT];
+		for y in 0..SENSOR_HEIGHT {
+			for x in 0..SENSOR_WIDTH {
+				let scrolled = (x + self.frame) % SENSOR_WIDTH;
+				pixels[y * SENSOR_WIDTH + x] = match (scrolled / 16 + y / 16) % 2 {
+					0 => (scrolled * 255 / (SENSOR_WIDTH - 1)) as u8,
+					_ => (y * 255 / (SENSOR_HEIGHT - 1)) as u8,
+				};
+			}
+		}
+		pixels
+	}
+}
+
+/// Shared handle to the image provider, which is not part of the serialized state
+#[derive(Clone)]
+pub struct ImageSource(Arc<Mutex<dyn ImageProvider + Send>>);
+
+impl ImageSource {
+	pub fn new(provider: impl ImageProvider + Send + 'static) -> Self {
+		Self(Arc::new(Mutex::new(provider)))
+	}
+
+	fn capture(&self) -> Vec<u8> {
+		match self.0.lock() {
+			Ok(mut provider) => provider.capture(),
+			Err(_) => vec![],
+		}
+	}
+}
+
+impl Default for ImageSource {
+	fn default() -> Self {
+		Self::new(TestPattern::default())
+	}
+}
+
+impl Debug for ImageSource {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str("ImageSource")
+	}
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct PocketCameraState {
+	rom_bank: u8,
+	ram_bank: u8,
+	ram_enabled: bool,
+	registers_mapped: bool,
+
+	// A000-A035, mirrored every 0x80 bytes
+	registers: Vec<u8>,
+
+	// T-states until the capture in progress is written to RAM
+	capture_remaining: u32,
+	capture: Option<Vec<u8>>,
+
+	#[serde(skip)]
+	pub image_source: ImageSource,
+}
+
+impl Default for PocketCameraState {
+	fn default() -> Self {
+		Self {
+			rom_bank: 1,
+			ram_bank: 0,
+			ram_enabled: false,
+			registers_mapped: false,
+			registers: vec![0; 0x36],
+			capture_remaining: 0,
+			capture: None,
+			image_source: ImageSource::default(),
+		}
+	}
+}
+
+impl PocketCameraState {
+	pub fn capturing(&self) -> bool {
+		self.capture.is_some()
+	}
+
+	fn exposure(&self) -> u16 {
+		u16::from_be_bytes([self.registers[2], self.registers[3]])
+	}
+
+	// Capture time in t-states, the camera runs at the single speed CPU clock
+	fn capture_t_states(&self) -> u32 {
+		let n_bit = self.registers[1] & BIT_7 != 0;
+		let m_cycles = 32446 + if n_bit { 0 } else { 512 } + 16 * self.exposure() as u32;
+		m_cycles * 4
+	}
+
+	fn sensor_image(&self) -> Vec<i32> {
+		let mut pixels = self.image_source.capture();
+		pixels.resize(SENSOR_WIDTH * SENSOR_HEIGHT, 0);
+
+		let exposure = self.exposure() as u32;
+		let invert = self.registers[4] & BIT_3 != 0;
+
+		pixels
+			.into_iter()
+			.map(|pixel| {
+				let value = (pixel as u32 * exposure / EXPOSURE_REFERENCE).min(255) as i32;
+				if invert {
+					255 - value
+				} else {
+					value
+				}
+			})
+			.collect()
+	}
+
+	fn enhance_edges(&self, pixels: &[i32]) -> Vec<i32> {
+		let (horizontal, vertical) = match (self.registers[1] >> 5) & 0b11 {
+			0 => return pixels.to_vec(),
+			1 => (true, false),
+			2 => (false, true),
+			_ => (true, true),
+		};
+		let ratio = EDGE_RATIOS[((self.registers[4] >> 4) & 0b111) as usize];
+
+		let pixel = |x: usize, y: usize| pixels[y * SENSOR_WIDTH + x];
+		let mut enhanced = pixels.to_vec();
+
+		for y in 0..SENSOR_HEIGHT {
+			for x in 0..SENSOR_WIDTH {
+				let center = pixel(x, y);
+				let mut edge = 0;
+				if horizontal {
+					edge += 2 * center
+						- pixel(x.saturating_sub(1), y)
+						- pixel((x + 1).min(SENSOR_WIDTH - 1), y);
+				}
+				if vertical {
+					edge += 2 * center
+						- pixel(x, y.saturating_sub(1))
+						- pixel(x, (y + 1).min(SENSOR_HEIGHT - 1));
+				}
+				enhanced[y * SENSOR_WIDTH + x] = (center + edge * ratio / 4).clamp(0, 255);
+			}
+		}
+		enhanced
+	}
+
+	// Each 4x4 block of pixels is quantized using three thresholds per pixel
+	fn dither(&self, x: usize, y: usize, value: i32) -> u8 {
+		let index = 6 + ((y % 4) * 4 + x % 4) * 3;
+		let thresholds = &self.registers[index..index + 3];
+
+		match value {
+			v if v < thresholds[0] as i32 => 3,
+			v if v < thresholds[1] as i32 => 2,
+			v if v < thresholds[2] as i32 => 1,
+			_ => 0,
+		}
+	}
+
+	fn process_image(&self) -> Vec<u8> {
+		let pixels = self.enhance_edges(&self.sensor_image());
+		let mut tiles = vec![0; IMAGE_SIZE];
+
+		for y in 0..SENSOR_HEIGHT {
+			for x in 0..SENSOR_WIDTH {
+				let color = self.dither(x, y, pixels[y * SENSOR_WIDTH + x]);
+				let tile = (y / 8) * (SENSOR_WIDTH / 8) + x / 8;
+				let offset = tile * 16 + (y % 8) * 2;
+				let bit = 7 - (x % 8);
+
+				tiles[offset] |= (color & 1) << bit;
+				tiles[offset + 1] |= (color >> 1) << bit;
+			}
+		}
+		tiles
+	}
+
+	fn start_capture(&mut self) {
+		self.capture_remaining = self.capture_t_states();
+		self.capture = Some(self.process_image());
+	}
+
+	/// Advances a capture in progress
+	pub fn tick(&mut self, data: &mut CartridgeData, t_states: u32) {
+		if self.capture.is_none() {
+			return;
+		}
+
+		self.capture_remaining = self.capture_remaining.saturating_sub(t_states);
+		if self.capture_remaining > 0 {
+			return;
+		}
+
+		if let (Some(image), Some(bank)) = (self.capture.take(), data.ram_banks.first_mut()) {
+			bank.data[IMAGE_OFFSET..IMAGE_OFFSET + IMAGE_SIZE].copy_from_slice(&image);
+		}
+	}
+
+	fn read_register(&self, addr: u16) -> u8 {
+		match addr & 0x7F {
+			0x00 => (self.registers[0] & 0x06) | self.capturing() as u8,
+			// All other registers are write only
+			_ => 0x00,
+		}
+	}
+
+	fn write_register(&mut self, addr: u16, value: u8) {
+		let register = (addr & 0x7F) as usize;
+		if register >= self.registers.len() {
+			return;
+		}
+		self.registers[register] = value;
+
+		if register == 0 {
+			match (value & BIT_0 != 0, self.capturing()) {
+				(true, false) => self.start_capture(),
+				// Clearing the busy bit aborts the capture
+				(false, true) => self.capture = None,
+				_ => {}
+			}
+		}
+	}
+
+	pub fn read(&self, data: &CartridgeData, addr: u16) -> u8 {
+		match addr {
+			0..0x4000 => data.rom_banks[0][addr as usize],
+			0x4000..0x8000 => {
+				let bank = self.rom_bank as usize % data.rom_banks.len();
+				data.rom_banks[bank][(addr - 0x4000) as usize]
+			}
+			0xA000..0xC000 if self.registers_mapped => self.read_register(addr),
+			// RAM is readable even while disabled, but not while the sensor is busy
+			0xA000..0xC000 if self.capturing() => 0x00,
+			0xA000..0xC000 => {
+				if data.ram_banks.is_empty() {
+					return 0xFF;
+				}
+				let bank = self.ram_bank as usize % data.ram_banks.len();
+				data.ram_banks[bank][(addr - 0xA000) as usize]
+			}
+			_ => unreachable!(),
+		}
+	}
+
+	pub fn write(&mut self, data: &mut CartridgeData, addr: u16, value: u8) {
+		match addr {
+			0..0x2000 => self.ram_enabled = value & 0x0F == 0x0A,
+			0x2000..0x4000 => self.rom_bank = value & 0x3F,
+			0x4000..0x6000 => {
+				self.registers_mapped = value & 0x10 != 0;
+				self.ram_bank = value & 0x0F;
+			}
+			0x6000..0x8000 => {}
+			0xA000..0xC000 if self.registers_mapped => self.write_register(addr, value),
+			0xA000..0xC000 => {
+				if data.ram_banks.is_empty() || !self.ram_enabled || self.capturing() {
+					return;
+				}
+				let bank = self.ram_bank as usize % data.ram_banks.len();
+				data.ram_banks[bank][(addr - 0xA000) as usize] = value;
+			}
+			_ => unreachable!(),
+		}
+	}
+}
@@ -57,21 +57,48 @@ pub struct CartridgeData {
 }
 
 impl CartridgeData {
+	// Roms shorter than the header claims are padded to a whole bank with 0xFF,
+	// the remaining banks mirror the data, like the unconnected address lines of a smaller chip.
+	// Overdumps are truncated to the size in the header.
 	pub fn create_rom_banks(banks: u32, raw_data: &[u8]) -> Vec<RomBank> {
-		let mut banks: Vec<RomBank> = (0..banks).map(|_| RomBank { data: [0; 0x4000] }).collect();
-		// Populate rom banks
-		for bank in 0..banks.len() {
-			for i in 0..0x4000 {
-				banks[bank].data[i] = raw_data[i + bank * 0x4000]
-			}
-		}
-		banks
+		let dumped_banks = raw_data.len().div_ceil(0x4000).max(1);
+
+		(0..banks as usize)
+			.map(|bank| {
+				let mut data = [0xFF; 0x4000];
+				let start = (bank % dumped_banks) * 0x4000;
+				let end = (start + 0x4000).min(raw_data.len());
+				if start < end {
+					data[..end - start].copy_from_slice(&raw_data[start..end]);
+				}
+				RomBank { data }
+			})
+			.collect()
 	}
 
 	fn create_ram_banks(banks: u32) -> Vec<RamBank> {
 		(0..banks).map(|_| RamBank { data: [0; 0x2000] }).collect()
 	}
 
+	/// Size of the cartridge RAM in bytes
+	pub fn ram_size(&self) -> usize {
+		self.ram_banks.len() * 0x2000
+	}
+
+	/// Contents of all RAM banks, in order
+	pub fn ram_bytes(&self) -> Vec<u8> {
+		self.ram_banks
+			.iter()
+			.flat_map(|bank| bank.data.iter().copied())
+			.collect()
+	}
+
+	pub fn load_ram_bytes(&mut self, bytes: &[u8]) {
+		for (bank, bytes) in self.ram_banks.iter_mut().zip(bytes.chunks(0x2000)) {
+			bank.data[..bytes.len()].copy_from_slice(bytes);
+		}
+	}
+
 	pub fn new(raw_data: &[u8], rom_banks: impl Into<u32>, ram_banks: impl Into<u32>) -> Self {
 		let rom_banks = Self::create_rom_banks(rom_banks.into(), raw_data);
 		let ram_banks = Self::create_ram_banks(ram_banks.into());
@@ -1,20 +1,57 @@
 // https://gbdev.io/pandocs/The_Cartridge_Header.html
 
+use std::fmt::Display;
+
 use serde::{Deserialize, Serialize};
 
-use crate::save_state::RomSource;
+use crate::{
+	save_state::RomSource,
+	util::{crc32::crc32, sha1::sha1},
+};
+
+use super::licensee::licensee_name;
+
+/// Logo bitmap checked by the boot rom, stored at 0104-0133
+pub const NINTENDO_LOGO: [u8; 48] = [
+	0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+	0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+	0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E,
+];
 
-#[derive(Debug, Clone)]
+/// The header ends at 014F, anything shorter can't be a valid rom
+pub const HEADER_END: usize = 0x0150;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CartridgeParseError {
-	MBCType,
-	RomSize,
-	RamSize,
+	// Size of the rom, which is too small to contain a header
+	Truncated(usize),
+	MBCType(u8),
+	RomSize(u8),
+	RamSize(u8),
 	Title,
 }
+
+impl Display for CartridgeParseError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Truncated(size) => write!(
+				f,
+				"Rom is {size} bytes, too small to contain a cartridge header"
+			),
+			Self::MBCType(value) => write!(f, "Unsupported cartridge type: {value:#04X}"),
+			Self::RomSize(value) => write!(f, "Invalid rom size in header: {value:#04X}"),
+			Self::RamSize(value) => write!(f, "Invalid ram size in header: {value:#04X}"),
+			Self::Title => write!(f, "Cartridge title is not valid text"),
+		}
+	}
+}
+
+impl std::error::Error for CartridgeParseError {}
 #[allow(unused)]
 #[derive(Debug)]
 pub struct RawCartridgeHeader {
 	pub rom_source: Option<RomSource>,
+	pub logo: Vec<u8>, // 0104-0133
 	pub title: Vec<u8>,
 	pub cgb_flag: u8,                // 0143
 	pub license_code: u16,           // 0144-0145
@@ -22,27 +59,78 @@ pub struct RawCartridgeHeader {
 	pub cartridge_type: u8,          // 0147
 	pub rom_size: u8,                // 0148
 	pub ram_size: u8,                // 0149
+	pub destination_code: u8,        // 014A
 	pub old_license_code: u8,        // 014B
 	pub mask_rom_version_number: u8, // 014C
 	pub header_checksum: u8,         // 014D
 	pub global_checksum: u16,        // 014E-014F
+
+	pub computed_header_checksum: u8,
+	pub computed_global_checksum: u16,
+}
+
+/// Hashes of the whole rom image, unlike the header checksums
+/// these tell apart revisions and roms with the same title
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RomFingerprint {
+	pub crc32: u32,
+	pub sha1: [u8; 20],
+}
+
+impl RomFingerprint {
+	pub fn new(rom: &[u8]) -> Self {
+		Self {
+			crc32: crc32(rom),
+			sha1: sha1(rom),
+		}
+	}
+}
+
+impl Display for RomFingerprint {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		for byte in self.sha1 {
+			write!(f, "{byte:02x}")?;
+		}
+		Ok(())
+	}
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CartridgeInfo {
 	pub rom_source: Option<RomSource>,
 	pub title: String,
+	pub licensee: Option<String>,
+	pub cartridge_type: u8,
 	pub cgb: bool,
 	pub sgb: bool,
 	pub rom_banks: u16,
 	pub ram_banks: u16,
+
+	// Hardware on the cartridge
+	pub has_battery: bool,
+	pub has_rtc: bool,
+	pub has_rumble: bool,
+	pub has_sensor: bool,
+	pub has_camera: bool,
+
+	// Checksums from the header, identifying the rom
+	pub header_checksum: u8,
+	pub global_checksum: u16,
+	// Hashes of the rom image, computed when the cartridge is created
+	pub fingerprint: RomFingerprint,
+
+	// Integrity checks, the boot rom only verifies the logo and header checksum
+	pub logo_valid: bool,
+	pub header_checksum_valid: bool,
+	pub global_checksum_valid: bool,
 }
 
 impl RawCartridgeHeader {
 	fn get_rom_banks(&self) -> Result<u16, CartridgeParseError> {
 		match self.rom_size {
 			0x0..0x09 => Ok(2 * (1 << self.rom_size)),
-			_ => Err(CartridgeParseError::RomSize),
+			_ => Err(CartridgeParseError::RomSize(self.rom_size)),
 		}
 	}
 
@@ -54,7 +142,7 @@ impl RawCartridgeHeader {
 			0x03 => Ok(4),
 			0x04 => Ok(16),
 			0x05 => Ok(8),
-			_ => Err(CartridgeParseError::RamSize),
+			_ => Err(CartridgeParseError::RamSize(self.ram_size)),
 		}
 	}
 
@@ -64,16 +152,75 @@ impl RawCartridgeHeader {
 			title: std::str::from_utf8(&self.title)
 				.or(Err(CartridgeParseError::Title))?
 				.to_owned(),
+			licensee: licensee_name(self.old_license_code, self.license_code.to_be_bytes())
+				.map(str::to_owned),
+			cartridge_type: self.cartridge_type,
 			cgb: matches!(self.cgb_flag, 0x80 | 0xC0),
 			sgb: matches!(self.sgb_flag, 0x03),
 			rom_banks: self.get_rom_banks()?,
 			ram_banks: self.get_ram_banks()?,
+			has_battery: matches!(
+				self.cartridge_type,
+				0x03 | 0x06 | 0x09 | 0x0D | 0x0F | 0x10 | 0x13 | 0x1B | 0x1E | 0x20 | 0x22 | 0xFC
+					..=0xFF
+			),
+			has_rtc: matches!(self.cartridge_type, 0x0F | 0x10 | 0xFE),
+			has_rumble: matches!(self.cartridge_type, 0x1C..=0x1E | 0x22),
+			has_sensor: matches!(self.cartridge_type, 0x22),
+			has_camera: matches!(self.cartridge_type, 0xFC),
+			header_checksum: self.header_checksum,
+			global_checksum: self.global_checksum,
+			fingerprint: RomFingerprint::default(),
+			logo_valid: self.logo == NINTENDO_LOGO,
+			header_checksum_valid: self.header_checksum == self.computed_header_checksum,
+			global_checksum_valid: self.global_checksum == self.computed_global_checksum,
 		})
 	}
 
-	pub fn new(rom: &[u8], rom_source: Option<RomSource>) -> Self {
-		RawCartridgeHeader {
+	fn compute_header_checksum(header: &[u8]) -> u8 {
+		header[0x0134..=0x014C]
+			.iter()
+			.fold(0u8, |sum, byte| sum.wrapping_sub(*byte).wrapping_sub(1))
+	}
+
+	// Sum of every byte in the rom, except the checksum itself
+	fn compute_global_checksum(rom: &[u8], header_offset: usize) -> u16 {
+		let checksum = header_offset + 0x014E..=header_offset + 0x014F;
+		rom.iter()
+			.enumerate()
+			.filter(|(index, _)| !checksum.contains(index))
+			.fold(0u16, |sum, (_, byte)| sum.wrapping_add(*byte as u16))
+	}
+
+	// MMM01 multicarts boot from the last 32KiB of the rom, the header of the
+	// whole cartridge lives there and the first bank holds the first game's header
+	fn header_offset(rom: &[u8]) -> usize {
+		if rom.len() < 0x10000 || rom.len() % 0x8000 != 0 {
+			return 0;
+		}
+
+		let offset = rom.len() - 0x8000;
+		let header = &rom[offset..offset + HEADER_END];
+		if matches!(header[0x0147], 0x0B..=0x0D)
+			&& Self::compute_header_checksum(header) == header[0x014D]
+		{
+			offset
+		} else {
+			0
+		}
+	}
+
+	pub fn new(rom: &[u8], rom_source: Option<RomSource>) -> Result<Self, CartridgeParseError> {
+		if rom.len() < HEADER_END {
+			return Err(CartridgeParseError::Truncated(rom.len()));
+		}
+
+		let header_offset = Self::header_offset(rom);
+		let computed_global_checksum = Self::compute_global_checksum(rom, header_offset);
+		let rom = &rom[header_offset..];
+		Ok(RawCartridgeHeader {
 			rom_source,
+			logo: rom[0x0104..0x0134].to_vec(),
 			title: rom[0x0134..0x0143].to_vec(),
 			cgb_flag: rom[0x0143],                                           // 0143
 			license_code: u16::from_be_bytes([rom[0x0144], rom[0x0145]]),    // 0144-0145
@@ -81,10 +228,13 @@ impl RawCartridgeHeader {
 			cartridge_type: rom[0x0147],                                     // 0147
 			rom_size: rom[0x0148],                                           // 0148
 			ram_size: rom[0x0149],                                           // 0149
+			destination_code: rom[0x014A],                                   // 014A
 			old_license_code: rom[0x014B],                                   // 014B
 			mask_rom_version_number: rom[0x014C],                            // 014C
 			header_checksum: rom[0x014D],                                    // 014D
 			global_checksum: u16::from_be_bytes([rom[0x014E], rom[0x014F]]), //014E-014F
-		}
+			computed_header_checksum: Self::compute_header_checksum(rom),
+			computed_global_checksum,
+		})
 	}
 }
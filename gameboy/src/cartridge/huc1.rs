@@ -0,0 +1,85 @@
+// https://gbdev.io/pandocs/HuC1.html
+
+use serde::{Deserialize, Serialize};
+
+use super::cartridge_data::CartridgeData;
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default)]
+enum RamMode {
+	#[default]
+	Ram,
+	Infrared,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct HuC1State {
+	mode: RamMode,
+	rom_bank: u8,
+	ram_bank: u8,
+	ir_led: bool,
+}
+
+impl Default for HuC1State {
+	fn default() -> Self {
+		Self {
+			mode: RamMode::Ram,
+			rom_bank: 1,
+			ram_bank: 0,
+			ir_led: false,
+		}
+	}
+}
+
+impl HuC1State {
+	/// True while the cartridge is emitting infrared light
+	pub fn ir_led(&self) -> bool {
+		self.ir_led
+	}
+
+	pub fn read(&self, data: &CartridgeData, addr: u16) -> u8 {
+		match addr {
+			0..0x4000 => data.rom_banks[0][addr as usize],
+			0x4000..0x8000 => {
+				let bank = self.rom_bank as usize % data.rom_banks.len();
+				data.rom_banks[bank][(addr - 0x4000) as usize]
+			}
+			0xA000..0xC000 => match self.mode {
+				RamMode::Ram => {
+					if data.ram_banks.is_empty() {
+						return 0xFF;
+					}
+					let bank = self.ram_bank as usize % data.ram_banks.len();
+					data.ram_banks[bank][(addr - 0xA000) as usize]
+				}
+				// No other device is ever sending light
+				RamMode::Infrared => 0xC0,
+			},
+			_ => unreachable!(),
+		}
+	}
+
+	pub fn write(&mut self, data: &mut CartridgeData, addr: u16, value: u8) {
+		match addr {
+			0..0x2000 => {
+				self.mode = match value {
+					0x0E => RamMode::Infrared,
+					_ => RamMode::Ram,
+				}
+			}
+			0x2000..0x4000 => self.rom_bank = value & 0x3F,
+			0x4000..0x6000 => self.ram_bank = value & 0x03,
+			0x6000..0x8000 => {}
+			0xA000..0xC000 => match self.mode {
+				RamMode::Ram => {
+					if data.ram_banks.is_empty() {
+						return;
+					}
+					let bank = self.ram_bank as usize % data.ram_banks.len();
+					data.ram_banks[bank][(addr - 0xA000) as usize] = value;
+				}
+				RamMode::Infrared => self.ir_led = value & 1 != 0,
+			},
+			_ => unreachable!(),
+		}
+	}
+}
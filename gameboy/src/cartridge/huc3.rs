@@ -0,0 +1,224 @@
+// https://gbdev.io/pandocs/HuC3.html
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+	cartridge_data::CartridgeData,
+	rtc::{RtcClock, TICKS_PER_SECOND},
+};
+
+const TICKS_PER_MINUTE: u64 = TICKS_PER_SECOND * 60;
+const MINUTES_PER_DAY: u64 = 60 * 24;
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default)]
+enum RamMode {
+	#[default]
+	RamReadOnly,
+	RamReadWrite,
+	Command,
+	Response,
+	Semaphore,
+	Infrared,
+	Unknown,
+}
+
+impl From<u8> for RamMode {
+	fn from(value: u8) -> Self {
+		match value & 0x0F {
+			0x0 => Self::RamReadOnly,
+			0xA => Self::RamReadWrite,
+			0xB => Self::Command,
+			0xC => Self::Response,
+			0xD => Self::Semaphore,
+			0xE => Self::Infrared,
+			_ => Self::Unknown,
+		}
+	}
+}
+
+/// The HuC3 clock only counts minutes and days
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct HuC3Clock {
+	clock: RtcClock,
+	pub minutes: u16,
+	pub days: u16,
+	sub_minute: u64,
+	last_sync: Option<u64>,
+}
+
+impl HuC3Clock {
+	pub fn set_clock(&mut self, clock: RtcClock) {
+		self.clock = clock;
+		self.last_sync = None;
+	}
+
+	pub fn sync(&mut self, t_states: u64) {
+		let now = self.clock.now(t_states);
+		let last = self.last_sync.replace(now).unwrap_or(now);
+
+		let ticks = self.sub_minute + now.saturating_sub(last);
+		self.sub_minute = ticks % TICKS_PER_MINUTE;
+
+		let minutes = self.minutes as u64 + ticks / TICKS_PER_MINUTE;
+		self.minutes = (minutes % MINUTES_PER_DAY) as u16;
+		self.days = ((self.days as u64 + minutes / MINUTES_PER_DAY) & 0xFFF) as u16;
+	}
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct HuC3State {
+	mode: RamMode,
+	rom_bank: u8,
+	ram_bank: u8,
+	ir_led: bool,
+
+	// Pending command, executed when the semaphore is released
+	command: u8,
+	argument: u8,
+	result: u8,
+
+	// Nibble addressed memory of the RTC chip
+	address: u8,
+	memory: Vec<u8>,
+
+	pub clock: HuC3Clock,
+	speaker_tone: Option<u8>,
+}
+
+impl Default for HuC3State {
+	fn default() -> Self {
+		Self {
+			mode: RamMode::default(),
+			rom_bank: 1,
+			ram_bank: 0,
+			ir_led: false,
+			command: 0,
+			argument: 0,
+			result: 0,
+			address: 0,
+			memory: vec![0; 0x100],
+			clock: HuC3Clock::default(),
+			speaker_tone: None,
+		}
+	}
+}
+
+impl HuC3State {
+	/// True while the cartridge is emitting infrared light
+	pub fn ir_led(&self) -> bool {
+		self.ir_led
+	}
+
+	/// Returns the tone requested from the cartridge speaker since the last call
+	pub fn take_speaker_tone(&mut self) -> Option<u8> {
+		self.speaker_tone.take()
+	}
+
+	fn store_nibbles(&mut self, start: usize, value: u16) {
+		for i in 0..3 {
+			self.memory[start + i] = ((value >> (i * 4)) & 0xF) as u8;
+		}
+	}
+
+	fn load_nibbles(&self, start: usize) -> u16 {
+		(0..3).fold(0, |value, i| {
+			value | ((self.memory[start + i] as u16 & 0xF) << (i * 4))
+		})
+	}
+
+	fn execute(&mut self) {
+		let argument = self.argument;
+		match self.command {
+			// Read and increment address
+			0x1 => {
+				self.result = self.memory[self.address as usize] & 0xF;
+				self.address = self.address.wrapping_add(1);
+			}
+			// Write and increment address
+			0x3 => {
+				self.memory[self.address as usize] = argument;
+				self.address = self.address.wrapping_add(1);
+			}
+			0x4 => self.address = (self.address & 0xF0) | argument,
+			0x5 => self.address = (self.address & 0x0F) | (argument << 4),
+			0x6 => match argument {
+				// Copy the current time to memory
+				0x0 => {
+					self.store_nibbles(0x00, self.clock.minutes);
+					self.store_nibbles(0x03, self.clock.days);
+				}
+				// Set the current time from memory
+				0x1 => {
+					self.clock.minutes = self.load_nibbles(0x00) % MINUTES_PER_DAY as u16;
+					self.clock.days = self.load_nibbles(0x03);
+				}
+				// Status, always ready
+				0x2 => self.result = 0x1,
+				// Play the tone selected in memory
+				0xE if self.memory[0x26] == 0x1 => {
+					self.speaker_tone = Some(self.memory[0x27]);
+				}
+				_ => {}
+			},
+			_ => {}
+		}
+	}
+
+	fn read_ram(&self, data: &CartridgeData, addr: u16) -> u8 {
+		if data.ram_banks.is_empty() {
+			return 0xFF;
+		}
+		let bank = self.ram_bank as usize % data.ram_banks.len();
+		data.ram_banks[bank][(addr - 0xA000) as usize]
+	}
+
+	pub fn read(&self, data: &CartridgeData, addr: u16) -> u8 {
+		match addr {
+			0..0x4000 => data.rom_banks[0][addr as usize],
+			0x4000..0x8000 => {
+				let bank = self.rom_bank as usize % data.rom_banks.len();
+				data.rom_banks[bank][(addr - 0x4000) as usize]
+			}
+			0xA000..0xC000 => match self.mode {
+				RamMode::RamReadOnly | RamMode::RamReadWrite => self.read_ram(data, addr),
+				RamMode::Response => (self.command << 4) | self.result,
+				// No other device is ever sending light
+				RamMode::Infrared => 0xC0,
+				// The semaphore reads as ready
+				RamMode::Semaphore | RamMode::Command | RamMode::Unknown => 0xFF,
+			},
+			_ => unreachable!(),
+		}
+	}
+
+	pub fn write(&mut self, data: &mut CartridgeData, addr: u16, value: u8) {
+		match addr {
+			0..0x2000 => self.mode = RamMode::from(value),
+			0x2000..0x4000 => self.rom_bank = value & 0x7F,
+			0x4000..0x6000 => self.ram_bank = value & 0x03,
+			0x6000..0x8000 => {}
+			0xA000..0xC000 => match self.mode {
+				RamMode::RamReadWrite => {
+					if data.ram_banks.is_empty() {
+						return;
+					}
+					let bank = self.ram_bank as usize % data.ram_banks.len();
+					data.ram_banks[bank][(addr - 0xA000) as usize] = value;
+				}
+				RamMode::Command => {
+					self.command = (value >> 4) & 0x7;
+					self.argument = value & 0xF;
+				}
+				// Releasing the semaphore executes the pending command
+				RamMode::Semaphore => {
+					if value & 1 == 0 {
+						self.execute();
+					}
+				}
+				RamMode::Infrared => self.ir_led = value & 1 != 0,
+				RamMode::RamReadOnly | RamMode::Response | RamMode::Unknown => {}
+			},
+			_ => unreachable!(),
+		}
+	}
+}
@@ -19,24 +19,43 @@ pub struct MBC1State {
 	banking_mode: BankingMode,
 	banking_register: u8,
 	ram_enabled: bool,
+
+	// MBC1M multicarts wire the secondary banking register to ROM bank bits 4-5
+	multicart: bool,
 }
 
 impl MBC1State {
+	pub fn multicart() -> Self {
+		Self {
+			multicart: true,
+			..Default::default()
+		}
+	}
+
+	// Moves the secondary register bits down for multicarts, the lower 5 bits are unaffected
+	fn compose_bank(&self, bank: u8) -> u16 {
+		(match self.multicart {
+			true => ((bank & 0b01100000) >> 1) | (bank & 0b00001111),
+			false => bank,
+		}) as u16
+	}
+
 	fn get_zero_rom_bank(&self) -> u16 {
 		let bank = self.banking_register;
 		match self.banking_mode {
 			BankingMode::Simple => 0,
-			BankingMode::Complex => (bank as u16) & 0b01100000,
+			BankingMode::Complex => self.compose_bank(bank & 0b01100000),
 		}
 	}
 
 	fn get_rom_bank(&self) -> u16 {
 		let bank = self.banking_register & 0b01111111;
-		(if bank & 0b00011111 == 0 {
+		// The zero check uses all 5 bits, even when only 4 are used on multicarts
+		self.compose_bank(if bank & 0b00011111 == 0 {
 			bank + 1
 		} else {
 			bank
-		}) as u16
+		})
 	}
 
 	fn get_ram_bank(&self) -> u16 {
@@ -69,6 +88,19 @@ impl MBC1State {
 		};
 	}
 
+	/// Register writes which restore the current banking state
+	pub fn register_writes(&self) -> Vec<(u16, u8)> {
+		vec![
+			(0x0000, if self.ram_enabled { 0x0A } else { 0x00 }),
+			(0x2000, self.banking_register & 0b00011111),
+			(0x4000, (self.banking_register >> 5) & 0b11),
+			(
+				0x6000,
+				matches!(self.banking_mode, BankingMode::Complex) as u8,
+			),
+		]
+	}
+
 	pub fn read(&self, data: &CartridgeData, addr: u16) -> u8 {
 		match addr {
 			0..0x4000 => {
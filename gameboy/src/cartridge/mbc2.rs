@@ -5,7 +5,7 @@ use super::cartridge_data::CartridgeData;
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct MBC2State {
 	ram_enabled: bool,
-	ram_data: Vec<u8>,
+	pub ram_data: Vec<u8>,
 	rom_bank: u8,
 }
 
@@ -31,6 +31,14 @@ impl MBC2State {
 		}
 	}
 
+	/// Register writes which restore the current banking state
+	pub fn register_writes(&self) -> Vec<(u16, u8)> {
+		vec![
+			(0x0000, if self.ram_enabled { 0x0A } else { 0x00 }),
+			(0x0100, self.rom_bank),
+		]
+	}
+
 	pub fn read(&self, data: &CartridgeData, addr: u16) -> u8 {
 		match addr {
 			0..0x4000 => data.rom_banks[0][addr as usize],
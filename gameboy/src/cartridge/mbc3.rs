@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 
-use super::cartridge_data::CartridgeData;
+use super::{
+	cartridge_data::CartridgeData,
+	rtc::{Rtc, RtcClock},
+};
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 enum BankingMode {
@@ -21,6 +24,7 @@ pub struct MBC3State {
 	ram_bank: usize,
 	ram_enabled: bool,
 	rtc_register: usize,
+	pub rtc: Rtc,
 }
 
 impl MBC3State {
@@ -38,7 +42,7 @@ impl MBC3State {
 				self.banking_mode = BankingMode::Ram;
 				self.ram_bank = value as usize;
 			}
-			8..0xC => {
+			0x08..=0x0C => {
 				self.banking_mode = BankingMode::Rtc;
 				self.rtc_register = value as usize;
 			}
@@ -46,6 +50,27 @@ impl MBC3State {
 		}
 	}
 
+	pub fn sync_clock(&mut self, t_states: u64) {
+		self.rtc.sync(t_states);
+	}
+
+	pub fn set_rtc_clock(&mut self, clock: RtcClock) {
+		self.rtc.set_clock(clock);
+	}
+
+	/// Register writes which restore the current banking state
+	pub fn register_writes(&self) -> Vec<(u16, u8)> {
+		let bank_register = match self.banking_mode {
+			BankingMode::Ram => self.ram_bank,
+			BankingMode::Rtc => self.rtc_register,
+		};
+		vec![
+			(0x0000, if self.ram_enabled { 0x0A } else { 0x00 }),
+			(0x2000, self.rom_bank as u8),
+			(0x4000, bank_register as u8),
+		]
+	}
+
 	pub fn read(&self, data: &CartridgeData, addr: u16) -> u8 {
 		match addr {
 			0..0x4000 => data.rom_banks[0][addr as usize],
@@ -55,7 +80,8 @@ impl MBC3State {
 			}
 			0xA000..0xC000 => match self.banking_mode {
 				BankingMode::Ram => data.ram_banks[self.ram_bank][(addr - 0xA000) as usize],
-				BankingMode::Rtc => 0,
+				BankingMode::Rtc if self.ram_enabled => self.rtc.read(self.rtc_register as u8),
+				BankingMode::Rtc => 0xFF,
 			},
 			_ => unreachable!(),
 		}
@@ -66,10 +92,14 @@ impl MBC3State {
 			0..0x2000 => self.ram_enabled = value == 0x0A,
 			0x2000..0x4000 => self.rom_bank = value as usize,
 			0x4000..0x6000 => self.write_register(value),
-			0x6000..0x8000 => {}
+			0x6000..0x8000 => self.rtc.write_latch(value),
 			0xA000..0xC000 => match self.banking_mode {
 				BankingMode::Ram => data.ram_banks[self.ram_bank][(addr - 0xA000) as usize] = value,
-				BankingMode::Rtc => {}
+				BankingMode::Rtc => {
+					if self.ram_enabled {
+						self.rtc.write(self.rtc_register as u8, value)
+					}
+				}
 			},
 			_ => {}
 		}
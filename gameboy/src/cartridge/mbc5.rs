@@ -46,6 +46,17 @@ impl MBC5State {
 		self.ram_enabled = value & 0xF == 0xA;
 	}
 
+	/// Register writes which restore the current banking state
+	pub fn register_writes(&self) -> Vec<(u16, u8)> {
+		let [high, low] = self.rom_bank.to_be_bytes();
+		vec![
+			(0x0000, if self.ram_enabled { 0x0A } else { 0x00 }),
+			(0x2000, low),
+			(0x3000, high),
+			(0x4000, self.ram_bank),
+		]
+	}
+
 	pub fn read(&self, data: &CartridgeData, addr: u16) -> u8 {
 		match addr {
 			0..0x4000 => data.rom_banks[0][addr as usize],
@@ -0,0 +1,207 @@
+// https://gbdev.io/pandocs/MBC6.html
+
+use serde::{Deserialize, Serialize};
+
+use crate::util::bits::BIT_0;
+
+use super::cartridge_data::CartridgeData;
+
+/// Macronix MX29F008 1MiB flash
+const FLASH_SIZE: usize = 0x100000;
+const FLASH_SECTOR_SIZE: usize = 0x20000;
+const FLASH_MANUFACTURER_ID: u8 = 0xC2;
+const FLASH_DEVICE_ID: u8 = 0x81;
+
+/// ROM and flash are mapped in 8KiB windows, RAM in 4KiB windows
+const WINDOW_SIZE: usize = 0x2000;
+const RAM_WINDOW_SIZE: usize = 0x1000;
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+enum FlashCommand {
+	#[default]
+	Idle,
+	// Waiting for the second unlock byte, 0x55 at 0x2AAA
+	Unlock1,
+	// Waiting for the command byte at 0x5555
+	Unlock2,
+	// The next write programs a byte
+	Program,
+	// Erase setup, waiting for the unlock sequence again
+	Erase,
+	EraseUnlock1,
+	EraseUnlock2,
+	// Manufacturer and device ids are mapped to the flash
+	Id,
+}
+
+/// Flash memory state, the contents persist like battery backed RAM
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Flash {
+	pub data: Vec<u8>,
+	command: FlashCommand,
+	enabled: bool,
+	write_enabled: bool,
+}
+
+impl Default for Flash {
+	fn default() -> Self {
+		Self {
+			data: vec![0xFF; FLASH_SIZE],
+			command: FlashCommand::Idle,
+			enabled: false,
+			write_enabled: false,
+		}
+	}
+}
+
+impl Flash {
+	fn read(&self, offset: usize) -> u8 {
+		match self.command {
+			FlashCommand::Id => match offset & 0xFF {
+				0x00 => FLASH_MANUFACTURER_ID,
+				0x01 => FLASH_DEVICE_ID,
+				_ => 0x00,
+			},
+			_ => self.data[offset % FLASH_SIZE],
+		}
+	}
+
+	fn write(&mut self, offset: usize, value: u8) {
+		use FlashCommand::*;
+
+		if !self.enabled {
+			return;
+		}
+
+		// Commands are decoded from the address within the first 32KiB
+		let command_address = offset & 0x7FFF;
+
+		self.command = match (self.command, command_address, value) {
+			// Reset can be issued at any point
+			(_, _, 0xF0) => Idle,
+
+			(Idle | Id, 0x5555, 0xAA) => Unlock1,
+			(Unlock1, 0x2AAA, 0x55) => Unlock2,
+			(Unlock2, 0x5555, 0x90) => Id,
+			(Unlock2, 0x5555, 0xA0) => Program,
+			(Unlock2, 0x5555, 0x80) => Erase,
+
+			(Program, _, _) => {
+				// Programming can only clear bits
+				if self.write_enabled {
+					self.data[offset % FLASH_SIZE] &= value;
+				}
+				Idle
+			}
+
+			(Erase, 0x5555, 0xAA) => EraseUnlock1,
+			(EraseUnlock1, 0x2AAA, 0x55) => EraseUnlock2,
+			(EraseUnlock2, 0x5555, 0x10) => {
+				if self.write_enabled {
+					self.data.fill(0xFF);
+				}
+				Idle
+			}
+			(EraseUnlock2, _, 0x30) => {
+				if self.write_enabled {
+					let sector = (offset % FLASH_SIZE) / FLASH_SECTOR_SIZE * FLASH_SECTOR_SIZE;
+					self.data[sector..sector + FLASH_SECTOR_SIZE].fill(0xFF);
+				}
+				Idle
+			}
+
+			// Anything unexpected aborts the command sequence
+			(Id, _, _) => Id,
+			_ => Idle,
+		};
+	}
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default)]
+struct Window {
+	bank: u8,
+	flash: bool,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, Debug)]
+pub struct MBC6State {
+	ram_enabled: bool,
+	ram_bank_a: u8,
+	ram_bank_b: u8,
+
+	window_a: Window,
+	window_b: Window,
+
+	pub flash: Flash,
+}
+
+impl MBC6State {
+	fn read_window(&self, data: &CartridgeData, window: Window, addr: u16) -> u8 {
+		let offset = window.bank as usize * WINDOW_SIZE + (addr as usize & (WINDOW_SIZE - 1));
+		match window.flash {
+			true => self.flash.read(offset),
+			false => {
+				let bank = offset / 0x4000 % data.rom_banks.len();
+				data.rom_banks[bank][offset % 0x4000]
+			}
+		}
+	}
+
+	fn write_window(&mut self, window: Window, addr: u16, value: u8) {
+		if window.flash {
+			let offset = window.bank as usize * WINDOW_SIZE + (addr as usize & (WINDOW_SIZE - 1));
+			self.flash.write(offset, value);
+		}
+	}
+
+	// RAM is banked in 4KiB units over the 8KiB banks of the cartridge data
+	fn ram_location(&self, data: &CartridgeData, addr: u16) -> Option<(usize, usize)> {
+		if data.ram_banks.is_empty() || !self.ram_enabled {
+			return None;
+		}
+
+		let bank = match addr {
+			0xA000..0xB000 => self.ram_bank_a,
+			_ => self.ram_bank_b,
+		};
+		let offset = (bank as usize * RAM_WINDOW_SIZE + (addr as usize & (RAM_WINDOW_SIZE - 1)))
+			% (data.ram_banks.len() * 0x2000);
+		Some((offset / 0x2000, offset % 0x2000))
+	}
+
+	pub fn read(&self, data: &CartridgeData, addr: u16) -> u8 {
+		match addr {
+			0..0x4000 => data.rom_banks[0][addr as usize],
+			0x4000..0x6000 => self.read_window(data, self.window_a, addr),
+			0x6000..0x8000 => self.read_window(data, self.window_b, addr),
+			0xA000..0xC000 => match self.ram_location(data, addr) {
+				Some((bank, offset)) => data.ram_banks[bank][offset],
+				None => 0xFF,
+			},
+			_ => unreachable!(),
+		}
+	}
+
+	pub fn write(&mut self, data: &mut CartridgeData, addr: u16, value: u8) {
+		match addr {
+			0..0x0400 => self.ram_enabled = value & 0x0F == 0x0A,
+			0x0400..0x0800 => self.ram_bank_a = value & 0x07,
+			0x0800..0x0C00 => self.ram_bank_b = value & 0x07,
+			0x0C00..0x1000 => self.flash.enabled = value & BIT_0 != 0,
+			0x1000 => self.flash.write_enabled = value & BIT_0 != 0,
+			0x1001..0x2000 => {}
+			0x2000..0x2800 => self.window_a.bank = value & 0x7F,
+			0x2800..0x3000 => self.window_a.flash = value == 0x08,
+			0x3000..0x3800 => self.window_b.bank = value & 0x7F,
+			0x3800..0x4000 => self.window_b.flash = value == 0x08,
+			0x4000..0x6000 => self.write_window(self.window_a, addr, value),
+			0x6000..0x8000 => self.write_window(self.window_b, addr, value),
+			0xA000..0xC000 => {
+				if let Some((bank, offset)) = self.ram_location(data, addr) {
+					data.ram_banks[bank][offset] = value;
+				}
+			}
+			_ => unreachable!(),
+		}
+	}
+}
@@ -0,0 +1,330 @@
+// https://gbdev.io/pandocs/MBC7.html
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	joypad::TiltState,
+	util::bits::{BIT_0, BIT_1, BIT_6, BIT_7},
+};
+
+use super::cartridge_data::CartridgeData;
+
+/// Latched accelerometer value when the device is level
+const ACCELEROMETER_CENTER: u16 = 0x81D0;
+
+/// Change in the latched value per g of acceleration
+const ACCELEROMETER_SCALE: f32 = 0x70 as f32;
+
+/// Reported by the accelerometer after erasing the latch
+const ACCELEROMETER_ERASED: u16 = 0x8000;
+
+const EEPROM_WORDS: usize = 128;
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default)]
+enum EepromCommand {
+	#[default]
+	Idle,
+	// Shifting in the opcode and address bits following the start bit
+	Command {
+		bits: u16,
+		count: u8,
+	},
+	// Shifting out a word, preceded by a dummy zero
+	Read {
+		address: u8,
+		count: u8,
+	},
+	// Shifting in a word to write to the given address, `None` writes all words
+	Write {
+		address: Option<u8>,
+		bits: u16,
+		count: u8,
+	},
+}
+
+/// 93LC56 serial EEPROM in 16-bit word mode
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Eeprom {
+	pub data: Vec<u16>,
+	command: EepromCommand,
+	write_enabled: bool,
+	cs: bool,
+	clk: bool,
+	di: bool,
+	do_: bool,
+}
+
+impl Default for Eeprom {
+	fn default() -> Self {
+		Self {
+			data: vec![0xFFFF; EEPROM_WORDS],
+			command: EepromCommand::Idle,
+			write_enabled: false,
+			cs: false,
+			clk: false,
+			di: false,
+			do_: true,
+		}
+	}
+}
+
+impl Eeprom {
+	fn read(&self) -> u8 {
+		let mut value = 0;
+		if self.cs {
+			value |= BIT_7;
+		}
+		if self.clk {
+			value |= BIT_6;
+		}
+		if self.di {
+			value |= BIT_1;
+		}
+		if self.do_ {
+			value |= BIT_0;
+		}
+		value
+	}
+
+	fn write(&mut self, value: u8) {
+		let cs = value & BIT_7 != 0;
+		let clk = value & BIT_6 != 0;
+		self.di = value & BIT_1 != 0;
+
+		if !cs {
+			// Deselecting the chip aborts any command in progress
+			self.command = EepromCommand::Idle;
+			self.do_ = true;
+		} else if clk && !self.clk {
+			self.clock_bit(self.di);
+		}
+
+		self.cs = cs;
+		self.clk = clk;
+	}
+
+	fn clock_bit(&mut self, bit: bool) {
+		use EepromCommand::*;
+
+		self.command = match self.command {
+			// Leading zeros are ignored until the start bit
+			Idle if !bit => Idle,
+			Idle => Command { bits: 0, count: 0 },
+			Command { bits, count } => {
+				let bits = (bits << 1) | bit as u16;
+				if count + 1 < 10 {
+					Command {
+						bits,
+						count: count + 1,
+					}
+				} else {
+					self.execute(bits)
+				}
+			}
+			Read { address, count } => {
+				let word = self.data[address as usize];
+				self.do_ = (word >> (15 - count)) & 1 != 0;
+				if count + 1 < 16 {
+					Read {
+						address,
+						count: count + 1,
+					}
+				} else {
+					// Sequential reads continue with the next word
+					Read {
+						address: (address + 1) % EEPROM_WORDS as u8,
+						count: 0,
+					}
+				}
+			}
+			Write {
+				address,
+				bits,
+				count,
+			} => {
+				let bits = (bits << 1) | bit as u16;
+				if count + 1 < 16 {
+					Write {
+						address,
+						bits,
+						count: count + 1,
+					}
+				} else {
+					if self.write_enabled {
+						match address {
+							Some(address) => self.data[address as usize] = bits,
+							None => self.data.fill(bits),
+						}
+					}
+					self.do_ = true;
+					Idle
+				}
+			}
+		};
+	}
+
+	fn execute(&mut self, command: u16) -> EepromCommand {
+		let opcode = (command >> 8) & 0b11;
+		let address = (command & 0x7F) as u8;
+
+		match (opcode, (command >> 6) & 0b11) {
+			// READ
+			(0b10, _) => {
+				self.do_ = false;
+				EepromCommand::Read { address, count: 0 }
+			}
+			// WRITE
+			(0b01, _) => EepromCommand::Write {
+				address: Some(address),
+				bits: 0,
+				count: 0,
+			},
+			// ERASE
+			(0b11, _) => {
+				if self.write_enabled {
+					self.data[address as usize] = 0xFFFF;
+				}
+				EepromCommand::Idle
+			}
+			// EWDS
+			(0b00, 0b00) => {
+				self.write_enabled = false;
+				EepromCommand::Idle
+			}
+			// WRAL
+			(0b00, 0b01) => EepromCommand::Write {
+				address: None,
+				bits: 0,
+				count: 0,
+			},
+			// ERAL
+			(0b00, 0b10) => {
+				if self.write_enabled {
+					self.data.fill(0xFFFF);
+				}
+				EepromCommand::Idle
+			}
+			// EWEN
+			(0b00, 0b11) => {
+				self.write_enabled = true;
+				EepromCommand::Idle
+			}
+			_ => unreachable!(),
+		}
+	}
+
+	/// EEPROM contents as bytes, each word is stored big endian
+	pub fn to_bytes(&self) -> Vec<u8> {
+		self.data
+			.iter()
+			.flat_map(|word| word.to_be_bytes())
+			.collect()
+	}
+
+	pub fn load_bytes(&mut self, bytes: &[u8]) {
+		for (word, bytes) in self.data.iter_mut().zip(bytes.chunks_exact(2)) {
+			*word = u16::from_be_bytes([bytes[0], bytes[1]]);
+		}
+	}
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct MBC7State {
+	rom_bank: u8,
+	ram_enabled_1: bool,
+	ram_enabled_2: bool,
+
+	tilt: TiltState,
+	latch_erased: bool,
+	x_latch: u16,
+	y_latch: u16,
+
+	pub eeprom: Eeprom,
+}
+
+impl Default for MBC7State {
+	fn default() -> Self {
+		Self {
+			rom_bank: 1,
+			ram_enabled_1: false,
+			ram_enabled_2: false,
+			tilt: TiltState::default(),
+			latch_erased: false,
+			x_latch: ACCELEROMETER_ERASED,
+			y_latch: ACCELEROMETER_ERASED,
+			eeprom: Eeprom::default(),
+		}
+	}
+}
+
+impl MBC7State {
+	pub fn set_tilt(&mut self, tilt: &TiltState) {
+		self.tilt = *tilt;
+	}
+
+	fn ram_enabled(&self) -> bool {
+		self.ram_enabled_1 && self.ram_enabled_2
+	}
+
+	fn accelerometer_value(tilt: f32) -> u16 {
+		let offset = (tilt.clamp(-1.0, 1.0) * ACCELEROMETER_SCALE) as i16;
+		ACCELEROMETER_CENTER.wrapping_add_signed(offset)
+	}
+
+	fn latch_accelerometer(&mut self) {
+		if !self.latch_erased {
+			return;
+		}
+		self.latch_erased = false;
+		self.x_latch = Self::accelerometer_value(self.tilt.x);
+		self.y_latch = Self::accelerometer_value(self.tilt.y);
+	}
+
+	fn read_register(&self, addr: u16) -> u8 {
+		match (addr >> 4) & 0xF {
+			0x2 => self.x_latch as u8,
+			0x3 => (self.x_latch >> 8) as u8,
+			0x4 => self.y_latch as u8,
+			0x5 => (self.y_latch >> 8) as u8,
+			0x6 => 0x00,
+			0x8 => self.eeprom.read(),
+			_ => 0xFF,
+		}
+	}
+
+	fn write_register(&mut self, addr: u16, value: u8) {
+		match (addr >> 4) & 0xF {
+			0x0 if value == 0x55 => {
+				self.latch_erased = true;
+				self.x_latch = ACCELEROMETER_ERASED;
+				self.y_latch = ACCELEROMETER_ERASED;
+			}
+			0x1 if value == 0xAA => self.latch_accelerometer(),
+			0x8 => self.eeprom.write(value),
+			_ => {}
+		}
+	}
+
+	pub fn read(&self, data: &CartridgeData, addr: u16) -> u8 {
+		match addr {
+			0..0x4000 => data.rom_banks[0][addr as usize],
+			0x4000..0x8000 => {
+				let bank = self.rom_bank as usize % data.rom_banks.len();
+				data.rom_banks[bank][(addr - 0x4000) as usize]
+			}
+			0xA000..0xB000 if self.ram_enabled() => self.read_register(addr),
+			0xA000..0xC000 => 0xFF,
+			_ => unreachable!(),
+		}
+	}
+
+	pub fn write(&mut self, _data: &mut CartridgeData, addr: u16, value: u8) {
+		match addr {
+			0..0x2000 => self.ram_enabled_1 = value == 0x0A,
+			0x2000..0x4000 => self.rom_bank = value,
+			0x4000..0x6000 => self.ram_enabled_2 = value == 0x40,
+			0xA000..0xB000 if self.ram_enabled() => self.write_register(addr, value),
+			_ => {}
+		}
+	}
+}
@@ -1,7 +1,11 @@
 use serde::{Deserialize, Serialize};
 use sm83::memory_mapper::MemoryMapper;
 
-use super::{mbc1::MBC1State, mbc2::MBC2State, mbc3::MBC3State, mbc5::MBC5State, Cartridge};
+use super::{
+	camera::PocketCameraState, huc1::HuC1State, huc3::HuC3State, mbc1::MBC1State, mbc2::MBC2State,
+	mbc3::MBC3State, mbc5::MBC5State, mbc6::MBC6State, mbc7::MBC7State, mmm01::MMM01State,
+	Cartridge,
+};
 
 pub trait MemoryBankController: Default + Clone {
 	fn read(&mut self, addr: u16) -> u8;
@@ -15,15 +19,33 @@ pub enum Mbc {
 	MBC2(MBC2State),
 	MBC3(MBC3State),
 	MBC5(MBC5State),
-	MBC6,
-	MMM01,
-	MBC7,
-	HUC3,
-	HUC1,
+	MBC6(MBC6State),
+	MMM01(MMM01State),
+	MBC7(MBC7State),
+	HUC3(HuC3State),
+	HUC1(HuC1State),
+	CAMERA(PocketCameraState),
 }
 
-impl MemoryMapper for Cartridge {
-	fn read(&self, addr: u16) -> u8 {
+impl Mbc {
+	/// Register writes which restore the banking state,
+	/// `None` for mappers with state that can't be restored by writes alone
+	pub fn register_writes(&self) -> Option<Vec<(u16, u8)>> {
+		use Mbc::*;
+
+		match self {
+			ROM => Some(vec![]),
+			MBC1(state) => Some(state.register_writes()),
+			MBC2(state) => Some(state.register_writes()),
+			MBC3(state) => Some(state.register_writes()),
+			MBC5(state) => Some(state.register_writes()),
+			_ => None,
+		}
+	}
+}
+
+impl Cartridge {
+	fn read_mbc(&self, addr: u16) -> u8 {
 		use Mbc::*;
 
 		match &self.mbc {
@@ -36,20 +58,75 @@ impl MemoryMapper for Cartridge {
 			MBC2(state) => state.read(&self.data, addr),
 			MBC3(state) => state.read(&self.data, addr),
 			MBC5(state) => state.read(&self.data, addr),
-			_ => todo!(),
+			MBC6(state) => state.read(&self.data, addr),
+			MBC7(state) => state.read(&self.data, addr),
+			MMM01(state) => state.read(&self.data, addr),
+			HUC1(state) => state.read(&self.data, addr),
+			HUC3(state) => state.read(&self.data, addr),
+			CAMERA(state) => state.read(&self.data, addr),
 		}
 	}
+}
+
+impl MemoryMapper for Cartridge {
+	fn read(&self, addr: u16) -> u8 {
+		let value = self.read_mbc(addr);
+		if addr >= 0x8000 {
+			return value;
+		}
+
+		self.game_genie_codes
+			.iter()
+			.fold(value, |value, code| code.apply(addr, value))
+	}
 
 	fn write(&mut self, addr: u16, value: u8) {
 		use Mbc::*;
 
+		let saved = self.battery_bytes(addr);
+
 		match &mut self.mbc {
 			ROM => {}
 			MBC1(state) => state.write(&mut self.data, addr, value),
 			MBC2(state) => state.write(&mut self.data, addr, value),
 			MBC3(state) => state.write(&mut self.data, addr, value),
 			MBC5(state) => state.write(&mut self.data, addr, value),
-			_ => todo!(),
+			MBC6(state) => state.write(&mut self.data, addr, value),
+			MBC7(state) => state.write(&mut self.data, addr, value),
+			MMM01(state) => state.write(&mut self.data, addr, value),
+			HUC1(state) => state.write(&mut self.data, addr, value),
+			HUC3(state) => state.write(&mut self.data, addr, value),
+			CAMERA(state) => state.write(&mut self.data, addr, value),
+		}
+
+		// MBC6 flash is programmed and erased through the ROM area, commands only come when saving
+		let flash_write = matches!((addr, &self.mbc), (0x4000..0x8000, MBC6(_)));
+		let changed = saved.is_some() && saved != self.battery_bytes(addr);
+		if (self.info.has_battery && flash_write) || changed {
+			self.save_dirty = true;
 		}
 	}
 }
+
+impl Cartridge {
+	// Battery backed bytes a write to `addr` could change, to detect actual changes.
+	// RAM banks are compared at the offset in both 4KiB halves, as MBC6 maps RAM in 4KiB windows
+	fn battery_bytes(&self, addr: u16) -> Option<Vec<u16>> {
+		if !self.info.has_battery || !(0xA000..0xC000).contains(&addr) {
+			return None;
+		}
+
+		let offset = addr as usize & 0xFFF;
+		let bytes = match &self.mbc {
+			Mbc::MBC2(state) => vec![state.ram_data[offset % 0x200] as u16],
+			Mbc::MBC7(state) => state.eeprom.data.clone(),
+			_ => self
+				.data
+				.ram_banks
+				.iter()
+				.flat_map(|bank| [bank[offset] as u16, bank[offset + 0x1000] as u16])
+				.collect(),
+		};
+		Some(bytes)
+	}
+}
@@ -0,0 +1,174 @@
+// https://gbdev.io/pandocs/MMM01.html
+
+use serde::{Deserialize, Serialize};
+
+use crate::util::bits::{BIT_0, BIT_6};
+
+use super::cartridge_data::CartridgeData;
+
+#[derive(Clone, Default, Serialize, Deserialize, Debug)]
+pub struct MMM01State {
+	// Set once the menu has selected a game, most registers become read only
+	mapped: bool,
+	ram_enabled: bool,
+
+	// ROM bank number, bits 0-4 are controlled by the game,
+	// bits 5-8 select the game within the multicart
+	rom_bank_low: u8,
+	rom_bank_mid: u8,
+	rom_bank_high: u8,
+	// Bits 1-4 of the ROM bank number locked while mapped
+	rom_bank_mask: u8,
+
+	ram_bank_low: u8,
+	ram_bank_high: u8,
+	// Bits 0-1 of the RAM bank number locked while mapped
+	ram_bank_mask: u8,
+
+	mbc1_mode: bool,
+	mbc1_mode_locked: bool,
+	// Swaps the upper ROM bank bits with the lower RAM bank bits, for games using MBC1 large ROM banking
+	multiplex: bool,
+}
+
+impl MMM01State {
+	/// True once the multicart menu has mapped a game
+	pub fn mapped(&self) -> bool {
+		self.mapped
+	}
+
+	fn rom_bank_mask(&self) -> u8 {
+		(self.rom_bank_mask << 1) & 0x1E
+	}
+
+	fn ram_bank_mask(&self) -> u8 {
+		self.ram_bank_mask & 0b11
+	}
+
+	// Bits above the 5 bits controlled by the game
+	fn rom_bank_upper(&self) -> u16 {
+		let high = match self.multiplex {
+			true => self.ram_bank_low,
+			false => self.rom_bank_high,
+		};
+		((high as u16) << 7) | ((self.rom_bank_mid as u16) << 5)
+	}
+
+	fn get_zero_rom_bank(&self, data: &CartridgeData) -> usize {
+		if !self.mapped {
+			// The menu runs from the last 32KiB of the rom
+			return data.rom_banks.len().saturating_sub(2);
+		}
+
+		// The locked bits stay in place, the game's bits are zero
+		let mut bank = self.rom_bank_upper() | (self.rom_bank_low & self.rom_bank_mask()) as u16;
+		if self.multiplex && !self.mbc1_mode {
+			bank &= !(0b11 << 7);
+		}
+		bank as usize
+	}
+
+	fn get_rom_bank(&self, data: &CartridgeData) -> usize {
+		if !self.mapped {
+			return data.rom_banks.len().saturating_sub(1);
+		}
+
+		let low = match self.rom_bank_low & !self.rom_bank_mask() & 0x1F {
+			// Like MBC1, the game can't select bank 0 in the switchable area
+			0 => self.rom_bank_low | 1,
+			_ => self.rom_bank_low,
+		};
+		(self.rom_bank_upper() | low as u16) as usize
+	}
+
+	fn get_ram_bank(&self) -> usize {
+		// In MBC1 mode 0 the multiplexed bits only select the ROM bank
+		let low = match self.multiplex && !self.mbc1_mode {
+			true => 0,
+			false => self.ram_bank_low,
+		};
+		((self.ram_bank_high << 2) | low) as usize
+	}
+
+	// Writes to bits locked by a mask are ignored once mapped
+	fn masked_write(&self, register: u8, value: u8, mask: u8) -> u8 {
+		match self.mapped {
+			true => (register & mask) | (value & !mask),
+			false => value,
+		}
+	}
+
+	fn write_ram_enable(&mut self, value: u8) {
+		self.ram_enabled = value & 0x0F == 0x0A;
+		if !self.mapped {
+			self.ram_bank_mask = (value >> 4) & 0b11;
+			self.mapped = value & BIT_6 != 0;
+		}
+	}
+
+	fn write_rom_bank(&mut self, value: u8) {
+		self.rom_bank_low =
+			self.masked_write(self.rom_bank_low, value & 0x1F, self.rom_bank_mask());
+		if !self.mapped {
+			self.rom_bank_mid = (value >> 5) & 0b11;
+		}
+	}
+
+	fn write_ram_bank(&mut self, value: u8) {
+		self.ram_bank_low =
+			self.masked_write(self.ram_bank_low, value & 0b11, self.ram_bank_mask());
+		if !self.mapped {
+			self.ram_bank_high = (value >> 2) & 0b11;
+			self.rom_bank_high = (value >> 4) & 0b11;
+			self.mbc1_mode_locked = value & BIT_6 != 0;
+		}
+	}
+
+	fn write_mode(&mut self, value: u8) {
+		if !self.mbc1_mode_locked || !self.mapped {
+			self.mbc1_mode = value & BIT_0 != 0;
+		}
+		if !self.mapped {
+			self.rom_bank_mask = (value >> 2) & 0x0F;
+			self.multiplex = value & BIT_6 != 0;
+		}
+	}
+
+	pub fn read(&self, data: &CartridgeData, addr: u16) -> u8 {
+		match addr {
+			0..0x4000 => {
+				let bank = self.get_zero_rom_bank(data) % data.rom_banks.len();
+				data.rom_banks[bank][addr as usize]
+			}
+			0x4000..0x8000 => {
+				let bank = self.get_rom_bank(data) % data.rom_banks.len();
+				data.rom_banks[bank][(addr - 0x4000) as usize]
+			}
+			0xA000..0xC000 => {
+				if data.ram_banks.is_empty() || !self.ram_enabled {
+					return 0xFF;
+				}
+				let bank = self.get_ram_bank() % data.ram_banks.len();
+				data.ram_banks[bank][(addr - 0xA000) as usize]
+			}
+			_ => unreachable!(),
+		}
+	}
+
+	pub fn write(&mut self, data: &mut CartridgeData, addr: u16, value: u8) {
+		match addr {
+			0..0x2000 => self.write_ram_enable(value),
+			0x2000..0x4000 => self.write_rom_bank(value),
+			0x4000..0x6000 => self.write_ram_bank(value),
+			0x6000..0x8000 => self.write_mode(value),
+			0xA000..0xC000 => {
+				if data.ram_banks.is_empty() || !self.ram_enabled {
+					return;
+				}
+				let bank = self.get_ram_bank() % data.ram_banks.len();
+				data.ram_banks[bank][(addr - 0xA000) as usize] = value;
+			}
+			_ => unreachable!(),
+		}
+	}
+}
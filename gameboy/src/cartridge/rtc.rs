@@ -0,0 +1,274 @@
+// https://gbdev.io/pandocs/MBC3.html#the-clock-counter-registers
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::util::bits::{BIT_0, BIT_6, BIT_7};
+
+/// The RTC oscillator runs at 32768Hz
+pub(super) const TICKS_PER_SECOND: u64 = 32768;
+
+/// T-States per RTC oscillator tick, the t-state counter always runs at 4194304Hz
+const T_STATES_PER_TICK: u64 = 4194304 / TICKS_PER_SECOND;
+
+/// Size of the RTC footer appended to `.sav` files by VBA / BGB / SameBoy
+pub const RTC_FOOTER_SIZE: usize = 48;
+
+/// Where the RTC gets its notion of time from
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub enum RtcClock {
+	/// Wall clock time of the host machine, time passes while the emulator is closed
+	Host,
+
+	/// Deterministic time derived from the number of emulated t-states
+	#[default]
+	Emulated,
+}
+
+/// Wall clock time of the host since the unix epoch
+#[cfg(not(target_arch = "wasm32"))]
+fn host_time() -> Duration {
+	use std::time::{SystemTime, UNIX_EPOCH};
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.unwrap_or_default()
+}
+
+/// Wall clock time of the host since the unix epoch, `SystemTime` panics on wasm32
+#[cfg(target_arch = "wasm32")]
+fn host_time() -> Duration {
+	Duration::from_secs_f64(js_sys::Date::now() / 1000.0)
+}
+
+impl RtcClock {
+	/// Current time in oscillator ticks
+	pub(super) fn now(&self, t_states: u64) -> u64 {
+		match self {
+			RtcClock::Host => {
+				(host_time().as_nanos() * TICKS_PER_SECOND as u128 / 1_000_000_000) as u64
+			}
+			RtcClock::Emulated => t_states / T_STATES_PER_TICK,
+		}
+	}
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct RtcRegisters {
+	pub seconds: u8,
+	pub minutes: u8,
+	pub hours: u8,
+	pub days: u16,
+	pub halt: bool,
+	pub carry: bool,
+}
+
+impl RtcRegisters {
+	fn read(&self, register: u8) -> u8 {
+		match register {
+			0x08 => self.seconds & 0x3F,
+			0x09 => self.minutes & 0x3F,
+			0x0A => self.hours & 0x1F,
+			0x0B => self.days as u8,
+			0x0C => {
+				let mut value = (self.days >> 8) as u8 & BIT_0;
+				if self.halt {
+					value |= BIT_6;
+				}
+				if self.carry {
+					value |= BIT_7;
+				}
+				value
+			}
+			_ => 0xFF,
+		}
+	}
+
+	fn write(&mut self, register: u8, value: u8) {
+		match register {
+			0x08 => self.seconds = value & 0x3F,
+			0x09 => self.minutes = value & 0x3F,
+			0x0A => self.hours = value & 0x1F,
+			0x0B => self.days = (self.days & 0x100) | value as u16,
+			0x0C => {
+				self.days = (self.days & 0xFF) | ((value & BIT_0) as u16) << 8;
+				self.halt = value & BIT_6 != 0;
+				self.carry = value & BIT_7 != 0;
+			}
+			_ => {}
+		}
+	}
+
+	fn is_valid(&self) -> bool {
+		self.seconds < 60 && self.minutes < 60 && self.hours < 24
+	}
+
+	// Out of range values count up to their bit width before wrapping to zero,
+	// without carrying into the next register
+	fn increment_second(&mut self) {
+		self.seconds = (self.seconds + 1) & 0x3F;
+		if self.seconds != 60 {
+			return;
+		}
+		self.seconds = 0;
+
+		self.minutes = (self.minutes + 1) & 0x3F;
+		if self.minutes != 60 {
+			return;
+		}
+		self.minutes = 0;
+
+		self.hours = (self.hours + 1) & 0x1F;
+		if self.hours != 24 {
+			return;
+		}
+		self.hours = 0;
+
+		self.increment_days(1);
+	}
+
+	fn increment_days(&mut self, days: u64) {
+		let days = self.days as u64 + days;
+		if days > 0x1FF {
+			self.carry = true;
+		}
+		self.days = (days & 0x1FF) as u16;
+	}
+
+	fn advance(&mut self, mut seconds: u64) {
+		// Step one at a time until all registers are in range
+		while seconds > 0 && !self.is_valid() {
+			self.increment_second();
+			seconds -= 1;
+		}
+
+		let total =
+			self.seconds as u64 + self.minutes as u64 * 60 + self.hours as u64 * 60 * 60 + seconds;
+
+		self.seconds = (total % 60) as u8;
+		self.minutes = (total / 60 % 60) as u8;
+		self.hours = (total / (60 * 60) % 24) as u8;
+		self.increment_days(total / (60 * 60 * 24));
+	}
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct Rtc {
+	clock: RtcClock,
+	registers: RtcRegisters,
+	latched: RtcRegisters,
+
+	// Last value written to the latch register, latching happens on a 0 -> 1 transition
+	latch_value: u8,
+
+	// Oscillator ticks since the last whole second
+	sub_second: u64,
+
+	// Time of the last synchronization, in oscillator ticks of the current clock
+	last_sync: Option<u64>,
+}
+
+impl Rtc {
+	pub fn clock(&self) -> RtcClock {
+		self.clock
+	}
+
+	pub fn set_clock(&mut self, clock: RtcClock) {
+		self.clock = clock;
+		self.last_sync = None;
+	}
+
+	pub fn registers(&self) -> RtcRegisters {
+		self.registers
+	}
+
+	pub fn latched_registers(&self) -> RtcRegisters {
+		self.latched
+	}
+
+	/// Advances the counters up to the current time
+	pub fn sync(&mut self, t_states: u64) {
+		let now = self.clock.now(t_states);
+		let last = self.last_sync.replace(now).unwrap_or(now);
+
+		if self.registers.halt {
+			return;
+		}
+
+		let ticks = self.sub_second + now.saturating_sub(last);
+		self.sub_second = ticks % TICKS_PER_SECOND;
+		self.registers.advance(ticks / TICKS_PER_SECOND);
+	}
+
+	pub fn write_latch(&mut self, value: u8) {
+		if self.latch_value == 0 && value == 1 {
+			self.latched = self.registers;
+		}
+		self.latch_value = value;
+	}
+
+	pub fn read(&self, register: u8) -> u8 {
+		self.latched.read(register)
+	}
+
+	pub fn write(&mut self, register: u8, value: u8) {
+		if register == 0x08 {
+			// Writing the seconds register resets the sub-second divider
+			self.sub_second = 0;
+		}
+		self.registers.write(register, value);
+	}
+
+	/// Serializes the RTC in the 48 byte footer format used by VBA, BGB and SameBoy
+	pub fn to_save_footer(&self) -> Vec<u8> {
+		let mut footer = Vec::with_capacity(RTC_FOOTER_SIZE);
+
+		for registers in [&self.registers, &self.latched] {
+			for register in 0x08..=0x0C {
+				footer.extend_from_slice(&(registers.read(register) as u32).to_le_bytes());
+			}
+		}
+
+		let timestamp = match self.clock {
+			RtcClock::Host => host_time().as_secs(),
+			RtcClock::Emulated => 0,
+		};
+		footer.extend_from_slice(&timestamp.to_le_bytes());
+		footer
+	}
+
+	/// Restores the RTC from a footer, when using the host clock,
+	/// time elapsed since the footer was written is applied
+	pub fn load_save_footer(&mut self, footer: &[u8]) -> bool {
+		// Some emulators write a 32-bit timestamp instead
+		if footer.len() != RTC_FOOTER_SIZE && footer.len() != RTC_FOOTER_SIZE - 4 {
+			return false;
+		}
+
+		let word = |index: usize| {
+			let bytes = &footer[index * 4..index * 4 + 4];
+			u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as u8
+		};
+
+		for register in 0x08..=0x0C {
+			let index = (register - 0x08) as usize;
+			self.registers.write(register, word(index));
+			self.latched.write(register, word(index + 5));
+		}
+
+		let mut timestamp = [0; 8];
+		timestamp[..footer.len() - 40].copy_from_slice(&footer[40..]);
+		let timestamp = u64::from_le_bytes(timestamp);
+
+		self.sub_second = 0;
+		self.last_sync = None;
+
+		if let (RtcClock::Host, true) = (self.clock, timestamp != 0) {
+			let now = host_time().as_secs();
+			if !self.registers.halt {
+				self.registers.advance(now.saturating_sub(timestamp));
+			}
+		}
+
+		true
+	}
+}
@@ -78,4 +78,8 @@ impl CGBState {
 	pub fn current_speed(&self) -> Speed {
 		self.speed
 	}
+
+	pub fn set_speed(&mut self, speed: Speed) {
+		self.speed = speed;
+	}
 }
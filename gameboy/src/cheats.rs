@@ -0,0 +1,230 @@
+// Game Genie and GameShark cheat codes
+//
+// Game Genie codes substitute bytes read from the cartridge rom,
+// GameShark codes write to external or work RAM once per frame, at the start of VBlank
+
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CheatParseError {
+	InvalidLength(usize),
+	InvalidCharacter(char),
+	// Game Genie codes can only patch the rom area,
+	// GameShark codes can only write external and work RAM
+	InvalidAddress(u16),
+	Empty,
+}
+
+impl Display for CheatParseError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::InvalidLength(length) => write!(
+				f,
+				"Code has {length} digits, expected 6 or 9 (Game Genie) or 8 (GameShark)"
+			),
+			Self::InvalidCharacter(char) => write!(f, "Code contains invalid character {char:?}"),
+			Self::InvalidAddress(address) => write!(
+				f,
+				"Code targets {address:#06X}, Game Genie codes patch $0000-$7FFF \
+				and GameShark codes write $A000-$DFFF"
+			),
+			Self::Empty => write!(f, "Cheat contains no codes"),
+		}
+	}
+}
+
+impl std::error::Error for CheatParseError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameGenieCode {
+	pub address: u16,
+	pub value: u8,
+	// Only substitute when the original byte matches, used to target a specific bank
+	pub compare: Option<u8>,
+}
+
+impl GameGenieCode {
+	/// Parses codes in the `ABC-DEF` or `ABC-DEF-GHI` format
+	pub fn parse(code: &str) -> Result<Self, CheatParseError> {
+		let digits = parse_hex_digits(code)?;
+		if digits.len() != 6 && digits.len() != 9 {
+			return Err(CheatParseError::InvalidLength(digits.len()));
+		}
+
+		let value = (digits[0] << 4) | digits[1];
+		let address = (((digits[5] ^ 0xF) as u16) << 12)
+			| ((digits[2] as u16) << 8)
+			| ((digits[3] as u16) << 4)
+			| digits[4] as u16;
+
+		if address >= 0x8000 {
+			return Err(CheatParseError::InvalidAddress(address));
+		}
+
+		// Digit H is unused, G and I hold the scrambled compare byte
+		let compare =
+			(digits.len() == 9).then(|| ((digits[6] << 4) | digits[8]).rotate_right(2) ^ 0xBA);
+
+		Ok(Self {
+			address,
+			value,
+			compare,
+		})
+	}
+
+	pub fn apply(&self, address: u16, value: u8) -> u8 {
+		match (address == self.address, self.compare) {
+			(true, None) => self.value,
+			(true, Some(compare)) if compare == value => self.value,
+			_ => value,
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameSharkCode {
+	// External RAM bank for $A000-$BFFF, wrapping around the banks of the cart.
+	// For $D000-$DFFF 0x90-0x97 selects the work RAM bank, otherwise the current one is written
+	pub bank: u8,
+	pub address: u16,
+	pub value: u8,
+}
+
+impl GameSharkCode {
+	/// Parses codes in the `ttvvaaaa` format, the address is little endian
+	pub fn parse(code: &str) -> Result<Self, CheatParseError> {
+		let digits = parse_hex_digits(code)?;
+		if digits.len() != 8 {
+			return Err(CheatParseError::InvalidLength(digits.len()));
+		}
+
+		let byte = |index: usize| (digits[index] << 4) | digits[index + 1];
+		let address = u16::from_le_bytes([byte(4), byte(6)]);
+		if !(0xA000..0xE000).contains(&address) {
+			return Err(CheatParseError::InvalidAddress(address));
+		}
+
+		Ok(Self {
+			bank: byte(0),
+			value: byte(2),
+			address,
+		})
+	}
+
+	/// The work RAM bank to write to, if the code selects one
+	pub fn work_ram_bank(&self) -> Option<u8> {
+		match (self.bank, self.address) {
+			(0x90..=0x97, 0xD000..0xE000) => Some(self.bank & 0x07),
+			_ => None,
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CheatCode {
+	GameGenie(GameGenieCode),
+	GameShark(GameSharkCode),
+}
+
+impl CheatCode {
+	/// Parses a single code, the type is determined by the number of digits
+	pub fn parse(code: &str) -> Result<Self, CheatParseError> {
+		match parse_hex_digits(code)?.len() {
+			8 => GameSharkCode::parse(code).map(Self::GameShark),
+			_ => GameGenieCode::parse(code).map(Self::GameGenie),
+		}
+	}
+}
+
+fn parse_hex_digits(code: &str) -> Result<Vec<u8>, CheatParseError> {
+	code.chars()
+		.filter(|char| *char != '-')
+		.map(|char| {
+			char.to_digit(16)
+				.map(|digit| digit as u8)
+				.ok_or(CheatParseError::InvalidCharacter(char))
+		})
+		.collect()
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Cheat {
+	pub name: String,
+	// The code as entered by the user
+	pub source: String,
+	pub codes: Vec<CheatCode>,
+	pub enabled: bool,
+}
+
+impl Cheat {
+	/// Parses a cheat made of one or more codes, separated by whitespace or `+`
+	pub fn parse(name: &str, source: &str) -> Result<Self, CheatParseError> {
+		let codes = source
+			.split(|char: char| char.is_whitespace() || char == '+')
+			.filter(|code| !code.is_empty())
+			.map(CheatCode::parse)
+			.collect::<Result<Vec<_>, _>>()?;
+
+		if codes.is_empty() {
+			return Err(CheatParseError::Empty);
+		}
+
+		Ok(Self {
+			name: name.to_owned(),
+			source: source.to_owned(),
+			codes,
+			enabled: true,
+		})
+	}
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Cheats {
+	cheats: Vec<Cheat>,
+}
+
+impl Cheats {
+	pub fn add(&mut self, cheat: Cheat) -> usize {
+		self.cheats.push(cheat);
+		self.cheats.len() - 1
+	}
+
+	pub fn remove(&mut self, index: usize) -> Option<Cheat> {
+		(index < self.cheats.len()).then(|| self.cheats.remove(index))
+	}
+
+	pub fn set_enabled(&mut self, index: usize, enabled: bool) {
+		if let Some(cheat) = self.cheats.get_mut(index) {
+			cheat.enabled = enabled;
+		}
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = &Cheat> {
+		self.cheats.iter()
+	}
+
+	fn enabled_codes(&self) -> impl Iterator<Item = &CheatCode> {
+		self.cheats
+			.iter()
+			.filter(|cheat| cheat.enabled)
+			.flat_map(|cheat| cheat.codes.iter())
+	}
+
+	pub fn game_genie_codes(&self) -> Vec<GameGenieCode> {
+		self.enabled_codes()
+			.filter_map(|code| match code {
+				CheatCode::GameGenie(code) => Some(*code),
+				CheatCode::GameShark(_) => None,
+			})
+			.collect()
+	}
+
+	pub fn game_shark_codes(&self) -> impl Iterator<Item = &GameSharkCode> {
+		self.enabled_codes().filter_map(|code| match code {
+			CheatCode::GameShark(code) => Some(code),
+			CheatCode::GameGenie(_) => None,
+		})
+	}
+}
@@ -16,7 +16,8 @@ pub fn main() {
 	gb.load_rom(
 		include_bytes!("../../../test_data/blargg/cpu_instrs/cpu_instrs.gb"),
 		None,
-	);
+	)
+	.unwrap();
 
 	for run in 0..runs {
 		let mut gb = gb.clone();
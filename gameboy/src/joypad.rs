@@ -14,6 +14,16 @@ pub struct JoypadState {
 	pub down: bool,
 }
 
+/// Accelerometer input for cartridges with a tilt sensor,
+/// each axis is measured in g, and clamped to the range -1.0..=1.0
+#[derive(Deserialize, Serialize, Default, Clone, Copy, Debug)]
+pub struct TiltState {
+	/// Positive when tilted to the right
+	pub x: f32,
+	/// Positive when tilted towards the player
+	pub y: f32,
+}
+
 impl BitOrAssign for JoypadState {
 	fn bitor_assign(&mut self, rhs: Self) {
 		self.a |= rhs.a;
@@ -2,13 +2,16 @@ mod apu;
 pub mod audio;
 pub mod cartridge;
 pub mod cgb;
+pub mod cheats;
 mod dma_controller;
 pub mod io_registers;
 pub mod joypad;
 pub mod lcd;
 pub mod memory_mapper;
 mod oam_dma;
+pub mod patch;
 pub mod ppu;
+pub mod rewind;
 pub mod save_state;
 mod state;
 mod timer;
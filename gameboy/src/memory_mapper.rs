@@ -85,6 +85,7 @@ impl MemoryMapper for Gameboy {
 			// Cartridge Rom
 			0x0000..0x8000 => {
 				if let Some(rom) = &mut self.cartridge_state {
+					rom.sync_clock(self.t_states);
 					rom.write(addr, value);
 				}
 			}
@@ -96,6 +97,7 @@ impl MemoryMapper for Gameboy {
 			// Cartage RAM
 			0xA000..0xC000 => {
 				if let Some(rom) = &mut self.cartridge_state {
+					rom.sync_clock(self.t_states);
 					rom.write(addr, value);
 				}
 			}
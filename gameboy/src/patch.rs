@@ -0,0 +1,350 @@
+// Soft-patching of roms with IPS, UPS and BPS patches
+// https://zerosoft.zophar.net/ips.php
+// https://www.romhacking.net/documents/392/ (UPS)
+// https://www.romhacking.net/documents/746/ (BPS)
+
+use std::fmt::Display;
+
+use crate::util::crc32::crc32;
+
+const IPS_MAGIC: &[u8] = b"PATCH";
+const IPS_EOF: &[u8] = b"EOF";
+const UPS_MAGIC: &[u8] = b"UPS1";
+const BPS_MAGIC: &[u8] = b"BPS1";
+
+/// Source, target and patch checksums at the end of UPS and BPS patches
+const FOOTER_SIZE: usize = 12;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PatchFormat {
+	Ips,
+	Ups,
+	Bps,
+}
+
+impl PatchFormat {
+	pub fn detect(patch: &[u8]) -> Option<Self> {
+		if patch.starts_with(IPS_MAGIC) {
+			Some(Self::Ips)
+		} else if patch.starts_with(UPS_MAGIC) {
+			Some(Self::Ups)
+		} else if patch.starts_with(BPS_MAGIC) {
+			Some(Self::Bps)
+		} else {
+			None
+		}
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PatchError {
+	UnknownFormat,
+	UnexpectedEnd,
+	// The patch refers to data outside of the rom
+	OutOfBounds,
+	SourceSize { expected: usize, actual: usize },
+	SourceChecksum { expected: u32, actual: u32 },
+	TargetChecksum { expected: u32, actual: u32 },
+	PatchChecksum { expected: u32, actual: u32 },
+}
+
+impl Display for PatchError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::UnknownFormat => write!(f, "Not an IPS, UPS or BPS patch"),
+			Self::UnexpectedEnd => write!(f, "Patch ended unexpectedly"),
+			Self::OutOfBounds => write!(f, "Patch refers to data outside of the rom"),
+			Self::SourceSize { expected, actual } => write!(
+				f,
+				"Patch expects a rom of {expected} bytes, but the rom is {actual} bytes"
+			),
+			Self::SourceChecksum { expected, actual } => write!(
+				f,
+				"Patch is for a different rom, expected CRC32 {expected:08X}, got {actual:08X}"
+			),
+			Self::TargetChecksum { expected, actual } => write!(
+				f,
+				"Patched rom is invalid, expected CRC32 {expected:08X}, got {actual:08X}"
+			),
+			Self::PatchChecksum { expected, actual } => write!(
+				f,
+				"Patch is corrupted, expected CRC32 {expected:08X}, got {actual:08X}"
+			),
+		}
+	}
+}
+
+impl std::error::Error for PatchError {}
+
+/// Reads patch data sequentially
+struct PatchReader<'a> {
+	data: &'a [u8],
+	position: usize,
+}
+
+impl<'a> PatchReader<'a> {
+	fn new(data: &'a [u8], position: usize) -> Self {
+		Self { data, position }
+	}
+
+	fn bytes(&mut self, count: usize) -> Result<&'a [u8], PatchError> {
+		let end = self
+			.position
+			.checked_add(count)
+			.ok_or(PatchError::OutOfBounds)?;
+		let bytes = self
+			.data
+			.get(self.position..end)
+			.ok_or(PatchError::UnexpectedEnd)?;
+		self.position = end;
+		Ok(bytes)
+	}
+
+	fn byte(&mut self) -> Result<u8, PatchError> {
+		Ok(self.bytes(1)?[0])
+	}
+
+	fn u16_be(&mut self) -> Result<usize, PatchError> {
+		let bytes = self.bytes(2)?;
+		Ok(u16::from_be_bytes([bytes[0], bytes[1]]) as usize)
+	}
+
+	fn u24_be(&mut self) -> Result<usize, PatchError> {
+		let bytes = self.bytes(3)?;
+		Ok(u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]) as usize)
+	}
+
+	fn u32_le(&mut self) -> Result<u32, PatchError> {
+		let bytes = self.bytes(4)?;
+		Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+	}
+
+	// Variable length integer used by UPS and BPS, each byte holds 7 bits
+	fn varint(&mut self) -> Result<usize, PatchError> {
+		let mut value: usize = 0;
+		let mut shift: usize = 1;
+		loop {
+			let byte = self.byte()?;
+			value = (byte as usize & 0x7F)
+				.checked_mul(shift)
+				.and_then(|bits| value.checked_add(bits))
+				.ok_or(PatchError::OutOfBounds)?;
+			if byte & 0x80 != 0 {
+				return Ok(value);
+			}
+			shift = shift.checked_shl(7).ok_or(PatchError::OutOfBounds)?;
+			value = value.checked_add(shift).ok_or(PatchError::OutOfBounds)?;
+		}
+	}
+}
+
+/// Applies a patch to a rom, the format is detected from the patch header
+pub fn apply_patch(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+	match PatchFormat::detect(patch).ok_or(PatchError::UnknownFormat)? {
+		PatchFormat::Ips => apply_ips(rom, patch),
+		PatchFormat::Ups => apply_ups(rom, patch),
+		PatchFormat::Bps => apply_bps(rom, patch),
+	}
+}
+
+pub fn apply_ips(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+	let mut reader = PatchReader::new(patch, IPS_MAGIC.len());
+	let mut output = rom.to_vec();
+
+	loop {
+		if reader.bytes(IPS_EOF.len())? == IPS_EOF {
+			break;
+		}
+		reader.position -= IPS_EOF.len();
+
+		let offset = reader.u24_be()?;
+		let (size, value) = match reader.u16_be()? {
+			// Run length encoded record
+			0 => (reader.u16_be()?, None),
+			size => (size, Some(reader.bytes(size)?)),
+		};
+
+		if output.len() < offset + size {
+			output.resize(offset + size, 0);
+		}
+
+		match value {
+			Some(bytes) => output[offset..offset + size].copy_from_slice(bytes),
+			None => output[offset..offset + size].fill(reader.byte()?),
+		}
+	}
+
+	// Optional truncation extension
+	if let Ok(size) = reader.u24_be() {
+		output.truncate(size);
+	}
+
+	Ok(output)
+}
+
+fn read_footer(patch: &[u8]) -> Result<(u32, u32), PatchError> {
+	if patch.len() < FOOTER_SIZE {
+		return Err(PatchError::UnexpectedEnd);
+	}
+
+	let body = patch.len() - FOOTER_SIZE;
+	let mut footer = PatchReader::new(patch, body);
+	let source = footer.u32_le()?;
+	let target = footer.u32_le()?;
+	let expected = footer.u32_le()?;
+
+	let actual = crc32(&patch[..patch.len() - 4]);
+	if expected != actual {
+		return Err(PatchError::PatchChecksum { expected, actual });
+	}
+
+	Ok((source, target))
+}
+
+fn verify_checksum(data: &[u8], expected: u32) -> Result<(), u32> {
+	match crc32(data) {
+		actual if actual == expected => Ok(()),
+		actual => Err(actual),
+	}
+}
+
+pub fn apply_ups(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+	let (source_crc, target_crc) = read_footer(patch)?;
+	let body = patch.len() - FOOTER_SIZE;
+
+	let mut reader = PatchReader::new(&patch[..body], UPS_MAGIC.len());
+	let source_size = reader.varint()?;
+	let target_size = reader.varint()?;
+
+	if rom.len() != source_size {
+		return Err(PatchError::SourceSize {
+			expected: source_size,
+			actual: rom.len(),
+		});
+	}
+	verify_checksum(rom, source_crc).map_err(|actual| PatchError::SourceChecksum {
+		expected: source_crc,
+		actual,
+	})?;
+
+	let mut output = rom.to_vec();
+	output.resize(target_size, 0);
+
+	let mut position: usize = 0;
+	while reader.position < body {
+		position = position
+			.checked_add(reader.varint()?)
+			.ok_or(PatchError::OutOfBounds)?;
+
+		// Bytes are XORed with the rom until a zero byte
+		loop {
+			let byte = reader.byte()?;
+			if byte == 0 {
+				position += 1;
+				break;
+			}
+			*output.get_mut(position).ok_or(PatchError::OutOfBounds)? ^= byte;
+			position += 1;
+		}
+	}
+
+	verify_checksum(&output, target_crc).map_err(|actual| PatchError::TargetChecksum {
+		expected: target_crc,
+		actual,
+	})?;
+
+	Ok(output)
+}
+
+pub fn apply_bps(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+	let (source_crc, target_crc) = read_footer(patch)?;
+	let body = patch.len() - FOOTER_SIZE;
+
+	let mut reader = PatchReader::new(&patch[..body], BPS_MAGIC.len());
+	let source_size = reader.varint()?;
+	let target_size = reader.varint()?;
+	let metadata_size = reader.varint()?;
+	reader.bytes(metadata_size)?;
+
+	if rom.len() != source_size {
+		return Err(PatchError::SourceSize {
+			expected: source_size,
+			actual: rom.len(),
+		});
+	}
+	verify_checksum(rom, source_crc).map_err(|actual| PatchError::SourceChecksum {
+		expected: source_crc,
+		actual,
+	})?;
+
+	// The target size comes from the patch, don't trust it for the allocation
+	let mut output: Vec<u8> = Vec::new();
+	let mut source_offset: usize = 0;
+	let mut target_offset: usize = 0;
+
+	// Relative offsets are stored as a magnitude with the sign in the lowest bit
+	let relative = |offset: usize, data: usize| {
+		let magnitude = data >> 1;
+		match data & 1 {
+			0 => offset.checked_add(magnitude),
+			_ => offset.checked_sub(magnitude),
+		}
+		.ok_or(PatchError::OutOfBounds)
+	};
+
+	while reader.position < body {
+		let data = reader.varint()?;
+		let length = (data >> 2) + 1;
+
+		if output
+			.len()
+			.checked_add(length)
+			.map_or(true, |end| end > target_size)
+		{
+			return Err(PatchError::OutOfBounds);
+		}
+
+		match data & 0b11 {
+			// Source read, copy from the same offset in the rom
+			0 => {
+				let start = output.len();
+				let bytes = rom
+					.get(start..start + length)
+					.ok_or(PatchError::OutOfBounds)?;
+				output.extend_from_slice(bytes);
+			}
+			// Target read, copy from the patch
+			1 => output.extend_from_slice(reader.bytes(length)?),
+			// Source copy
+			2 => {
+				source_offset = relative(source_offset, reader.varint()?)?;
+				let end = source_offset
+					.checked_add(length)
+					.ok_or(PatchError::OutOfBounds)?;
+				let bytes = rom.get(source_offset..end).ok_or(PatchError::OutOfBounds)?;
+				output.extend_from_slice(bytes);
+				source_offset = end;
+			}
+			// Target copy, byte by byte as the ranges may overlap
+			_ => {
+				target_offset = relative(target_offset, reader.varint()?)?;
+				for _ in 0..length {
+					let byte = *output.get(target_offset).ok_or(PatchError::OutOfBounds)?;
+					output.push(byte);
+					target_offset += 1;
+				}
+			}
+		}
+	}
+
+	if output.len() != target_size {
+		return Err(PatchError::UnexpectedEnd);
+	}
+
+	verify_checksum(&output, target_crc).map_err(|actual| PatchError::TargetChecksum {
+		expected: target_crc,
+		actual,
+	})?;
+
+	Ok(output)
+}
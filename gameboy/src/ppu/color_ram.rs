@@ -68,6 +68,22 @@ impl ColorRamController {
 		}
 	}
 
+	/// Palette memory as it is laid out in hardware, little endian colors
+	pub fn bytes(&self) -> [u8; 64] {
+		let mut bytes = [0; 64];
+		for (bytes, color) in bytes.chunks_mut(2).zip(self.data) {
+			bytes.copy_from_slice(&color.to_le_bytes());
+		}
+		bytes
+	}
+
+	pub fn load_bytes(&mut self, bytes: &[u8]) {
+		for (index, color) in bytes.chunks_exact(2).take(self.data.len()).enumerate() {
+			self.data[index] = u16::from_le_bytes([color[0], color[1]]);
+			self.update_color(index);
+		}
+	}
+
 	fn update_color(&mut self, index: usize) {
 		let color = self.data[index];
 
@@ -0,0 +1,202 @@
+// Rewind buffer
+//
+// Snapshots are binary save states (see `save_state::format`) with the screen added.
+// Only the newest snapshot is kept whole, every older one is stored as a delta
+// against the snapshot after it, so consecutive frames only cost the bytes that changed.
+// Stepping back undoes the newest delta, and the oldest delta can be dropped
+// without touching the others.
+
+use std::collections::VecDeque;
+
+use crate::{
+	save_state::{
+		format::{Chunk, ChunkId, Container},
+		SaveError,
+	},
+	Gameboy,
+};
+
+const LCD_CHUNK: ChunkId = *b"LCD ";
+
+pub struct Rewind {
+	// Maximum number of steps back
+	capacity: usize,
+	// Frames between snapshots
+	interval: u32,
+	frames_since_snapshot: u32,
+
+	latest: Option<Vec<u8>>,
+	// Deltas which restore the previous snapshot, the last one is the most recent
+	deltas: VecDeque<Vec<u8>>,
+}
+
+impl Default for Rewind {
+	// 10 seconds, one snapshot every frame
+	fn default() -> Self {
+		Self::new(600, 1)
+	}
+}
+
+impl Rewind {
+	pub fn new(capacity: usize, interval: u32) -> Self {
+		Self {
+			capacity,
+			interval: interval.max(1),
+			frames_since_snapshot: 0,
+			latest: None,
+			deltas: VecDeque::with_capacity(capacity),
+		}
+	}
+
+	/// Should be called after every frame, a snapshot is taken every `interval` frames
+	pub fn capture(&mut self, gameboy: &Gameboy) {
+		self.frames_since_snapshot += 1;
+		if self.latest.is_some() && self.frames_since_snapshot < self.interval {
+			return;
+		}
+		self.frames_since_snapshot = 0;
+
+		let Ok(snapshot) = gameboy.to_rewind_snapshot() else {
+			return;
+		};
+
+		if let Some(previous) = self.latest.replace(snapshot) {
+			let latest = self.latest.as_ref().unwrap();
+			self.deltas.push_back(encode_delta(latest, &previous));
+		}
+
+		if self.deltas.len() > self.capacity {
+			self.deltas.pop_front();
+		}
+	}
+
+	/// Restores the snapshot before the latest one, which is discarded.
+	/// Returns `None` once the buffer is exhausted.
+	pub fn step_back(&mut self, gameboy: &Gameboy) -> Option<Gameboy> {
+		let delta = self.deltas.pop_back()?;
+		let latest = self.latest.as_ref()?;
+		let previous = apply_delta(latest, &delta)?;
+
+		let restored = gameboy.restore_rewind_snapshot(&previous).ok();
+		self.latest = Some(previous);
+		self.frames_since_snapshot = 0;
+		restored
+	}
+
+	pub fn clear(&mut self) {
+		self.latest = None;
+		self.deltas.clear();
+		self.frames_since_snapshot = 0;
+	}
+
+	/// Number of steps that can be taken back
+	pub fn len(&self) -> usize {
+		self.deltas.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.deltas.is_empty()
+	}
+
+	/// Memory used by the snapshots in bytes
+	pub fn size(&self) -> usize {
+		self.latest.as_ref().map_or(0, Vec::len) + self.deltas.iter().map(Vec::len).sum::<usize>()
+	}
+}
+
+impl Gameboy {
+	fn to_rewind_snapshot(&self) -> Result<Vec<u8>, SaveError> {
+		let mut container = self.to_save_container()?;
+		container
+			.chunks
+			.push(Chunk::encode(LCD_CHUNK, &self.ppu.lcd)?);
+		Ok(container.to_bytes())
+	}
+
+	fn restore_rewind_snapshot(&self, snapshot: &[u8]) -> Result<Self, SaveError> {
+		let container = Container::from_bytes(snapshot)?;
+		let mut gameboy = self.inherit_rom(Gameboy::from_save_container(&container)?)?;
+		gameboy.ppu.lcd = container.decode(LCD_CHUNK)?;
+		Ok(gameboy)
+	}
+}
+
+fn write_length(output: &mut Vec<u8>, mut value: usize) {
+	while value >= 0x80 {
+		output.push(value as u8 | 0x80);
+		value >>= 7;
+	}
+	output.push(value as u8);
+}
+
+fn read_length(input: &mut impl Iterator<Item = u8>) -> Option<usize> {
+	let mut value = 0;
+	for shift in (0..usize::BITS).step_by(7) {
+		let byte = input.next()?;
+		value |= ((byte & 0x7F) as usize) << shift;
+		if byte & 0x80 == 0 {
+			return Some(value);
+		}
+	}
+	None
+}
+
+// Bytes past the end of the base read as zero
+fn base_byte(base: &[u8], index: usize) -> u8 {
+	base.get(index).copied().unwrap_or_default()
+}
+
+/// Encodes `target` as the runs of bytes which differ from `base`.
+/// Layout: target length, then pairs of (unchanged length, changed length, changed bytes).
+/// Lengths are LEB128 encoded.
+pub(crate) fn encode_delta(base: &[u8], target: &[u8]) -> Vec<u8> {
+	let mut delta = vec![];
+	write_length(&mut delta, target.len());
+
+	let mut position = 0;
+	while position < target.len() {
+		let unchanged = target[position..]
+			.iter()
+			.enumerate()
+			.take_while(|(index, byte)| **byte == base_byte(base, position + index))
+			.count();
+		if position + unchanged == target.len() {
+			break;
+		}
+		position += unchanged;
+
+		let changed = target[position..]
+			.iter()
+			.enumerate()
+			.take_while(|(index, byte)| **byte != base_byte(base, position + index))
+			.count();
+
+		write_length(&mut delta, unchanged);
+		write_length(&mut delta, changed);
+		delta.extend_from_slice(&target[position..position + changed]);
+		position += changed;
+	}
+	delta
+}
+
+pub(crate) fn apply_delta(base: &[u8], delta: &[u8]) -> Option<Vec<u8>> {
+	let mut input = delta.iter().copied();
+	let length = read_length(&mut input)?;
+	let mut target = Vec::with_capacity(length);
+
+	while let Some(unchanged) = read_length(&mut input) {
+		let changed = read_length(&mut input)?;
+		let start = target.len();
+		target.extend((start..start + unchanged).map(|index| base_byte(base, index)));
+		for _ in 0..changed {
+			target.push(input.next()?);
+		}
+	}
+
+	if target.len() > length {
+		return None;
+	}
+	let start = target.len();
+	target.extend((start..length).map(|index| base_byte(base, index)));
+	Some(target)
+}
@@ -2,8 +2,18 @@ use std::fmt::Display;
 
 use serde::{Deserialize, Serialize};
 
-use crate::Gameboy;
+use crate::{cartridge::header::RomFingerprint, util::base64, Gameboy};
 use std::time::SystemTime;
+use thumbnail::Thumbnail;
+
+pub mod bess;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod file_save_manager;
+pub mod format;
+pub mod thumbnail;
+
+/// Number of save state slots offered by the frontends
+pub const SAVE_SLOTS: usize = 10;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum RomSource {
@@ -11,17 +21,29 @@ pub enum RomSource {
 	LocalUrl(String),
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SaveState {
-	pub data: String,
+	// Binary save state, see `format`
+	#[serde(with = "encoded_data")]
+	pub data: Vec<u8>,
 	pub info: SaveStateEntry,
 	pub rom_source: Option<RomSource>,
+	// IPS, UPS or BPS patch the rom from `rom_source` was loaded with,
+	// it has to be applied again before the save can be loaded
+	#[serde(default, with = "encoded_patch")]
+	pub rom_patch: Option<Vec<u8>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SaveStateEntry {
 	pub date: SystemTime,
 	pub game_title: String,
+	// Missing in legacy saves
+	#[serde(default)]
+	pub rom_fingerprint: Option<RomFingerprint>,
+	// Screen at the time of saving
+	#[serde(default)]
+	pub thumbnail: Option<Thumbnail>,
 }
 
 #[derive(Debug, Clone)]
@@ -32,19 +54,65 @@ pub enum SaveError {
 	NoSource,
 	MissingIndex,
 	IndexOutOfBounds(usize),
+	// The save state format version can't be migrated to the current version
+	UnsupportedVersion(u16),
+	// Reading or writing save files failed
+	Io(std::io::ErrorKind),
+}
+
+impl From<std::io::Error> for SaveError {
+	fn from(error: std::io::Error) -> Self {
+		match error.kind() {
+			std::io::ErrorKind::NotFound => SaveError::NoSource,
+			kind => SaveError::Io(kind),
+		}
+	}
 }
 
 pub trait SaveManager {
-	fn load_save_state(slot: usize) -> Result<SaveState, SaveError>;
-	fn save_save_state(state: SaveState, slot: usize) -> Result<(), SaveError>;
-	fn get_save_states() -> Vec<Option<String>>;
+	fn load_save_state(&self, slot: usize) -> Result<SaveState, SaveError>;
+	fn save_save_state(&mut self, state: SaveState, slot: usize) -> Result<(), SaveError>;
+	/// Entry of every slot, `None` for empty slots
+	fn get_save_states(&self) -> Vec<Option<SaveStateEntry>>;
+}
+
+/// Keeps save states in memory, for frontends without storage
+#[derive(Default)]
+pub struct MemorySaveManager {
+	slots: Vec<Option<SaveState>>,
+}
+
+impl SaveManager for MemorySaveManager {
+	fn load_save_state(&self, slot: usize) -> Result<SaveState, SaveError> {
+		match self.slots.get(slot) {
+			Some(Some(save)) => Ok(save.clone()),
+			Some(None) => Err(SaveError::NoSource),
+			None if slot < SAVE_SLOTS => Err(SaveError::NoSource),
+			None => Err(SaveError::IndexOutOfBounds(slot)),
+		}
+	}
+
+	fn save_save_state(&mut self, state: SaveState, slot: usize) -> Result<(), SaveError> {
+		if slot >= SAVE_SLOTS {
+			return Err(SaveError::IndexOutOfBounds(slot));
+		}
+		self.slots.resize(SAVE_SLOTS, None);
+		self.slots[slot] = Some(state);
+		Ok(())
+	}
+
+	fn get_save_states(&self) -> Vec<Option<SaveStateEntry>> {
+		(0..SAVE_SLOTS)
+			.map(|slot| Some(self.slots.get(slot)?.as_ref()?.info.clone()))
+			.collect()
+	}
 }
 
 impl TryFrom<&Gameboy> for SaveState {
 	type Error = SaveError;
 
 	fn try_from(value: &Gameboy) -> Result<Self, SaveError> {
-		let data = serde_json::to_string(value).or(Err(SaveError::Serialization))?;
+		let data = value.to_save_container()?.to_bytes();
 
 		let info = &value
 			.cartridge_state
@@ -57,7 +125,14 @@ impl TryFrom<&Gameboy> for SaveState {
 
 		Ok(Self {
 			rom_source: info.rom_source.clone(),
-			info: SaveStateEntry { date, game_title },
+			// Only the frontend knows which patch it applied
+			rom_patch: None,
+			info: SaveStateEntry {
+				date,
+				game_title,
+				rom_fingerprint: Some(info.fingerprint),
+				thumbnail: Some(Thumbnail::capture(&value.ppu.lcd)),
+			},
 			data,
 		})
 	}
@@ -68,3 +143,51 @@ impl Display for SaveStateEntry {
 		write!(f, "[{:?}] : {}", self.date, self.game_title)
 	}
 }
+
+// Save states are stored as base64 in text formats like JSON.
+// Legacy saves stored the emulator as a JSON string, which is kept as is to be migrated.
+mod encoded_data {
+	use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+	use super::{base64, format::is_legacy_json};
+
+	pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(&base64::encode(data))
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+		let data = String::deserialize(deserializer)?;
+		if is_legacy_json(data.as_bytes()) {
+			return Ok(data.into_bytes());
+		}
+		base64::decode(&data).ok_or(D::Error::custom("invalid base64 save state"))
+	}
+}
+
+// Patches are stored as base64 like the save data
+mod encoded_patch {
+	use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+	use super::base64;
+
+	pub fn serialize<S: Serializer>(
+		patch: &Option<Vec<u8>>,
+		serializer: S,
+	) -> Result<S::Ok, S::Error> {
+		match patch {
+			Some(patch) => serializer.serialize_some(&base64::encode(patch)),
+			None => serializer.serialize_none(),
+		}
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>>(
+		deserializer: D,
+	) -> Result<Option<Vec<u8>>, D::Error> {
+		match Option::<String>::deserialize(deserializer)? {
+			Some(patch) => base64::decode(&patch)
+				.map(Some)
+				.ok_or(D::Error::custom("invalid base64 rom patch")),
+			None => Ok(None),
+		}
+	}
+}
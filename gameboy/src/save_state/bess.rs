@@ -0,0 +1,532 @@
+// BESS (Best Effort Save State) import and export
+// https://github.com/LIJI32/SameBoy/blob/master/BESS.md
+//
+// A BESS file starts with raw memory dumps, referenced by offset from the CORE block,
+// followed by the blocks and an 8 byte footer holding the offset of the first block.
+// Blocks are a 4 byte id, a little endian u32 length, and the block data.
+
+use std::fmt::Display;
+
+use sm83::{
+	memory_mapper::MemoryMapper,
+	registers::{Addressable, CPURegister16},
+};
+
+use crate::{
+	cartridge::{memory_bank_controller::Mbc, Cartridge},
+	cgb::{CGBState, Speed},
+	io_registers::{
+		IORegisters, BGPD, DISABLE_BOOT, DIV, DMA, HDMA5, KEY0, KEY1, LY, NR52, OBPD, SC,
+	},
+	util::bits::BIT_7,
+	work_ram::{BankedWorkRam, WorkRam},
+	Gameboy, Mode,
+};
+
+use super::SaveError;
+
+const FOOTER_MAGIC: &[u8] = b"BESS";
+const FOOTER_SIZE: usize = 8;
+const BLOCK_HEADER_SIZE: usize = 8;
+
+type BlockId = [u8; 4];
+
+const NAME_BLOCK: BlockId = *b"NAME";
+const INFO_BLOCK: BlockId = *b"INFO";
+const CORE_BLOCK: BlockId = *b"CORE";
+const MBC_BLOCK: BlockId = *b"MBC ";
+const RTC_BLOCK: BlockId = *b"RTC ";
+const END_BLOCK: BlockId = *b"END ";
+
+const CORE_MAJOR_VERSION: u16 = 1;
+const CORE_MINOR_VERSION: u16 = 1;
+const CORE_SIZE: usize = 0xD0;
+const CORE_IO_OFFSET: usize = 0x18;
+const CORE_REGIONS_OFFSET: usize = 0x98;
+const INFO_SIZE: usize = 0x12;
+
+const EMULATOR_NAME: &str = concat!("rust-gbc v", env!("CARGO_PKG_VERSION"));
+
+const DMG_MODEL: &[u8; 4] = b"GD  ";
+const CGB_MODEL: &[u8; 4] = b"CCE ";
+
+const WORK_RAM_BANK_SIZE: usize = 0x1000;
+const VIDEO_RAM_BANK_SIZE: usize = 0x2000;
+const OAM_SIZE: usize = 0xA0;
+const HRAM_SIZE: usize = 0x7F;
+const PALETTE_SIZE: usize = 0x40;
+
+// Writing the trigger bit of the NRx4 registers would restart the channel
+const CHANNEL_TRIGGERS: [u16; 4] = [0xFF14, 0xFF19, 0xFF1E, 0xFF23];
+
+/// State which could not be imported exactly
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BessWarning {
+	// The title or checksum in the INFO block doesn't match the loaded rom
+	DifferentRom,
+	UnsupportedModel(String),
+	// Blocks for hardware which isn't emulated, or isn't part of the loaded cartridge
+	IgnoredBlock(String),
+	// Only the overlapping part of the region is imported
+	SizeMismatch {
+		region: &'static str,
+		expected: usize,
+		actual: usize,
+	},
+	// Stopped CPUs are resumed as running
+	Stopped,
+}
+
+impl Display for BessWarning {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::DifferentRom => write!(f, "Save state was made with a different rom"),
+			Self::UnsupportedModel(model) => write!(f, "Unsupported model {model:?}"),
+			Self::IgnoredBlock(block) => write!(f, "Ignored block {block:?}"),
+			Self::SizeMismatch {
+				region,
+				expected,
+				actual,
+			} => write!(f, "{region} is {actual} bytes, expected {expected} bytes"),
+			Self::Stopped => write!(f, "CPU was stopped, resuming as running"),
+		}
+	}
+}
+
+// Memory regions referenced by the CORE block, in order
+const REGIONS: [&str; 7] = [
+	"Work RAM",
+	"Video RAM",
+	"Cartridge RAM",
+	"OAM",
+	"HRAM",
+	"Background palettes",
+	"Object palettes",
+];
+
+fn write_block(file: &mut Vec<u8>, id: BlockId, data: &[u8]) {
+	file.extend_from_slice(&id);
+	file.extend_from_slice(&(data.len() as u32).to_le_bytes());
+	file.extend_from_slice(data);
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+	u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn read_u32(data: &[u8], offset: usize) -> usize {
+	u32::from_le_bytes([
+		data[offset],
+		data[offset + 1],
+		data[offset + 2],
+		data[offset + 3],
+	]) as usize
+}
+
+fn copy_region(region: &'static str, dest: &mut [u8], src: &[u8], warnings: &mut Vec<BessWarning>) {
+	if dest.len() != src.len() {
+		warnings.push(BessWarning::SizeMismatch {
+			region,
+			expected: dest.len(),
+			actual: src.len(),
+		});
+	}
+	let length = dest.len().min(src.len());
+	dest[..length].copy_from_slice(&src[..length]);
+}
+
+// MBC2 RAM is built into the mapper, rather than being part of the cartridge data
+fn cartridge_ram(cart: &Cartridge) -> Vec<u8> {
+	match &cart.mbc {
+		Mbc::MBC2(state) => state.ram_data.clone(),
+		_ => cart.data.ram_bytes(),
+	}
+}
+
+fn load_cartridge_ram(cart: &mut Cartridge, bytes: &[u8], warnings: &mut Vec<BessWarning>) {
+	let mut ram = cartridge_ram(cart);
+	copy_region(REGIONS[2], &mut ram, bytes, warnings);
+	match &mut cart.mbc {
+		Mbc::MBC2(state) => state.ram_data = ram,
+		_ => cart.data.load_ram_bytes(&ram),
+	}
+}
+
+// Title and global checksum from the header
+fn rom_info(cart: &Cartridge) -> Vec<u8> {
+	let Some(bank) = cart.data.rom_banks.first() else {
+		return vec![0; INFO_SIZE];
+	};
+	[&bank.data[0x0134..0x0144], &bank.data[0x014E..0x0150]].concat()
+}
+
+impl Gameboy {
+	fn is_cgb(&self) -> bool {
+		matches!(self.mode, Mode::GBC(_))
+	}
+
+	fn work_ram_banks(&self) -> u8 {
+		match self.w_ram {
+			WorkRam::Cgb(_) => 8,
+			WorkRam::Dmg(_) => 2,
+		}
+	}
+
+	// All work RAM banks in order, the bank register is not affected
+	fn work_ram_bytes(&self) -> Vec<u8> {
+		let mut w_ram = self.w_ram.clone();
+		let mut bytes = w_ram.get_low_bank().to_vec();
+		for bank in 1..self.work_ram_banks() {
+			w_ram.set_bank_number(bank);
+			bytes.extend_from_slice(w_ram.get_high_bank());
+		}
+		bytes
+	}
+
+	fn load_work_ram_bytes(&mut self, bytes: &[u8]) {
+		let current = self.w_ram.get_bank_number();
+		let mut banks = bytes.chunks_exact(WORK_RAM_BANK_SIZE);
+
+		if let Some(bank) = banks.next() {
+			self.w_ram.get_low_bank_mut().copy_from_slice(bank);
+		}
+		for (number, bank) in (1..self.work_ram_banks()).zip(banks) {
+			self.w_ram.set_bank_number(number);
+			self.w_ram.get_high_bank_mut().copy_from_slice(bank);
+		}
+		self.w_ram.set_bank_number(current);
+	}
+
+	fn video_ram_bytes(&self) -> Vec<u8> {
+		match self.is_cgb() {
+			true => [self.ppu.v_ram_bank_0, self.ppu.v_ram_bank_1].concat(),
+			false => self.ppu.v_ram_bank_0.to_vec(),
+		}
+	}
+
+	/// Exports the emulator state as a BESS file, which can be loaded by other emulators
+	pub fn export_bess(&self) -> Result<Vec<u8>, SaveError> {
+		let cart = self
+			.cartridge_state
+			.as_ref()
+			.ok_or(SaveError::InvalidGame)?;
+
+		let (background_palettes, object_palettes) = match self.is_cgb() {
+			true => (
+				self.ppu.bg_color.bytes().to_vec(),
+				self.ppu.obj_color.bytes().to_vec(),
+			),
+			false => (vec![], vec![]),
+		};
+
+		let regions = [
+			self.work_ram_bytes(),
+			self.video_ram_bytes(),
+			cartridge_ram(cart),
+			self.ppu.oam.to_vec(),
+			self.hram[..HRAM_SIZE].to_vec(),
+			background_palettes,
+			object_palettes,
+		];
+
+		let mut file = vec![];
+		let mut region_table = Vec::with_capacity(regions.len() * 8);
+		for region in regions {
+			region_table.extend_from_slice(&(region.len() as u32).to_le_bytes());
+			region_table.extend_from_slice(&(file.len() as u32).to_le_bytes());
+			file.extend_from_slice(&region);
+		}
+
+		let first_block = file.len() as u32;
+		write_block(&mut file, NAME_BLOCK, EMULATOR_NAME.as_bytes());
+		write_block(&mut file, INFO_BLOCK, &rom_info(cart));
+		write_block(&mut file, CORE_BLOCK, &self.bess_core(&region_table));
+
+		if let Some(writes) = cart.mbc.register_writes() {
+			let writes: Vec<u8> = writes
+				.into_iter()
+				.flat_map(|(addr, value)| {
+					let [low, high] = addr.to_le_bytes();
+					[low, high, value]
+				})
+				.collect();
+			if !writes.is_empty() {
+				write_block(&mut file, MBC_BLOCK, &writes);
+			}
+		}
+
+		if let (Mbc::MBC3(state), true) = (&cart.mbc, cart.info.has_rtc) {
+			write_block(&mut file, RTC_BLOCK, &state.rtc.to_save_footer());
+		}
+
+		write_block(&mut file, END_BLOCK, &[]);
+
+		file.extend_from_slice(&first_block.to_le_bytes());
+		file.extend_from_slice(FOOTER_MAGIC);
+		Ok(file)
+	}
+
+	fn bess_core(&self, region_table: &[u8]) -> Vec<u8> {
+		let mut core = Vec::with_capacity(CORE_SIZE);
+		core.extend_from_slice(&CORE_MAJOR_VERSION.to_le_bytes());
+		core.extend_from_slice(&CORE_MINOR_VERSION.to_le_bytes());
+		core.extend_from_slice(if self.is_cgb() { CGB_MODEL } else { DMG_MODEL });
+
+		use CPURegister16::*;
+		for register in [PC, AF, BC, DE, HL, SP] {
+			core.extend_from_slice(&self.cpu_state.read(register).to_le_bytes());
+		}
+
+		core.push(self.cpu_state.ime() as u8);
+		core.push(self.cpu_state.interrupt_enable);
+		// Execution state, running or halted
+		core.push(self.cpu_state.halted as u8);
+		core.push(0);
+
+		core.extend((0xFF00..0xFF80).map(|addr| self.read_io(addr)));
+		core.extend_from_slice(region_table);
+		core
+	}
+
+	/// Imports a BESS file made by this or another emulator, for the currently loaded rom.
+	/// State which can't be represented exactly is reported as warnings.
+	pub fn import_bess(&self, file: &[u8]) -> Result<(Gameboy, Vec<BessWarning>), SaveError> {
+		if self.cartridge_state.is_none() {
+			return Err(SaveError::InvalidGame);
+		}
+
+		let footer = file
+			.len()
+			.checked_sub(FOOTER_SIZE)
+			.ok_or(SaveError::Deserialization)?;
+		if &file[footer + 4..] != FOOTER_MAGIC {
+			return Err(SaveError::Deserialization);
+		}
+
+		let mut gameboy = self.clone();
+		let mut warnings = vec![];
+		let mut position = read_u32(file, footer);
+		let mut core_loaded = false;
+
+		loop {
+			let header = file
+				.get(position..position + BLOCK_HEADER_SIZE)
+				.ok_or(SaveError::Deserialization)?;
+			let id: BlockId = [header[0], header[1], header[2], header[3]];
+			let length = read_u32(header, 4);
+			position += BLOCK_HEADER_SIZE;
+
+			let block = file
+				.get(position..position + length)
+				.ok_or(SaveError::Deserialization)?;
+			position += length;
+
+			match id {
+				NAME_BLOCK => {}
+				INFO_BLOCK => gameboy.check_bess_info(block, &mut warnings),
+				CORE_BLOCK => {
+					gameboy.load_bess_core(file, block, &mut warnings)?;
+					core_loaded = true;
+				}
+				MBC_BLOCK => gameboy.load_bess_mbc(block)?,
+				RTC_BLOCK => gameboy.load_bess_rtc(block, &mut warnings),
+				END_BLOCK => break,
+				_ => warnings.push(BessWarning::IgnoredBlock(
+					String::from_utf8_lossy(&id).into_owned(),
+				)),
+			}
+		}
+
+		if !core_loaded {
+			return Err(SaveError::Deserialization);
+		}
+		Ok((gameboy, warnings))
+	}
+
+	fn check_bess_info(&self, block: &[u8], warnings: &mut Vec<BessWarning>) {
+		let Some(cart) = &self.cartridge_state else {
+			return;
+		};
+		if block != rom_info(cart) {
+			warnings.push(BessWarning::DifferentRom);
+		}
+	}
+
+	fn load_bess_core(
+		&mut self,
+		file: &[u8],
+		core: &[u8],
+		warnings: &mut Vec<BessWarning>,
+	) -> Result<(), SaveError> {
+		if core.len() < CORE_SIZE {
+			return Err(SaveError::Deserialization);
+		}
+
+		let major_version = read_u16(core, 0);
+		if major_version != CORE_MAJOR_VERSION {
+			return Err(SaveError::UnsupportedVersion(major_version));
+		}
+
+		let model = &core[4..8];
+		let cgb = match model[0] {
+			b'G' => false,
+			b'C' => true,
+			_ => {
+				warnings.push(BessWarning::UnsupportedModel(
+					String::from_utf8_lossy(model).into_owned(),
+				));
+				// Super Game Boy features are ignored, other models keep the current mode
+				model[0] != b'S' && self.is_cgb()
+			}
+		};
+		self.set_gb_mode(match cgb {
+			true => Mode::GBC(CGBState::default()),
+			false => Mode::DMG,
+		});
+		self.booting = false;
+
+		for (index, name) in REGIONS.iter().enumerate() {
+			let size = read_u32(core, CORE_REGIONS_OFFSET + index * 8);
+			let offset = read_u32(core, CORE_REGIONS_OFFSET + index * 8 + 4);
+			let bytes = file
+				.get(offset..offset + size)
+				.ok_or(SaveError::Deserialization)?;
+			self.load_bess_region(index, name, bytes, warnings);
+		}
+
+		self.load_bess_io(&core[CORE_IO_OFFSET..CORE_IO_OFFSET + 0x80]);
+
+		use CPURegister16::*;
+		for (index, register) in [PC, AF, BC, DE, HL, SP].into_iter().enumerate() {
+			self.cpu_state
+				.write(register, read_u16(core, 8 + index * 2));
+		}
+
+		match core[0x14] {
+			0 => self.cpu_state.disable_interrupts(),
+			_ => {
+				self.cpu_state.enable_interrupts();
+				self.cpu_state.tick_ie_delay();
+			}
+		}
+		self.cpu_state.interrupt_enable = core[0x15];
+
+		self.cpu_state.halted = core[0x16] == 1;
+		if core[0x16] == 2 {
+			warnings.push(BessWarning::Stopped);
+		}
+		Ok(())
+	}
+
+	fn load_bess_region(
+		&mut self,
+		index: usize,
+		name: &'static str,
+		bytes: &[u8],
+		warnings: &mut Vec<BessWarning>,
+	) {
+		match index {
+			0 => {
+				let mut w_ram = vec![0; self.work_ram_banks() as usize * WORK_RAM_BANK_SIZE];
+				copy_region(name, &mut w_ram, bytes, warnings);
+				self.load_work_ram_bytes(&w_ram);
+			}
+			1 => {
+				let mut v_ram = self.video_ram_bytes();
+				copy_region(name, &mut v_ram, bytes, warnings);
+				let (bank_0, bank_1) = v_ram.split_at(VIDEO_RAM_BANK_SIZE);
+				self.ppu.v_ram_bank_0.copy_from_slice(bank_0);
+				if !bank_1.is_empty() {
+					self.ppu.v_ram_bank_1.copy_from_slice(bank_1);
+				}
+			}
+			2 => {
+				if let Some(cart) = &mut self.cartridge_state {
+					load_cartridge_ram(cart, bytes, warnings);
+				}
+			}
+			3 => copy_region(name, &mut self.ppu.oam[..OAM_SIZE], bytes, warnings),
+			4 => copy_region(name, &mut self.hram[..HRAM_SIZE], bytes, warnings),
+			// Palette RAM only exists on the CGB
+			5 | 6 if !self.is_cgb() => {}
+			5 | 6 => {
+				let mut palettes = [0; PALETTE_SIZE];
+				copy_region(name, &mut palettes, bytes, warnings);
+				match index {
+					5 => self.ppu.bg_color.load_bytes(&palettes),
+					_ => self.ppu.obj_color.load_bytes(&palettes),
+				}
+			}
+			_ => unreachable!(),
+		}
+	}
+
+	// Registers are written like the CPU would, except where that has side effects
+	fn load_bess_io(&mut self, io: &[u8]) {
+		let value = |addr: u16| io[(addr - 0xFF00) as usize];
+
+		// The APU ignores writes to the other registers while it is powered off
+		self.write_io(NR52, value(NR52));
+
+		for addr in 0xFF00..0xFF80 {
+			let value = value(addr);
+			match addr {
+				// Palette data comes from the palette regions,
+				// LY and HDMA5 belong to internal state that BESS doesn't capture
+				NR52 | LY | HDMA5 | BGPD | OBPD | DISABLE_BOOT => {}
+				_ if CHANNEL_TRIGGERS.contains(&addr) => self.write_io(addr, value & !BIT_7),
+				DIV => self.timer.load_div(value),
+				SC | DMA => self.io_register_state[addr] = value,
+				KEY0 | KEY1 if !self.is_cgb() => {}
+				KEY1 => {
+					if let Mode::GBC(state) = &mut self.mode {
+						state.write_key1(value);
+						state.set_speed(match value & BIT_7 {
+							0 => Speed::Normal,
+							_ => Speed::Double,
+						});
+					}
+				}
+				_ => self.write_io(addr, value),
+			}
+		}
+	}
+
+	fn load_bess_mbc(&mut self, block: &[u8]) -> Result<(), SaveError> {
+		if block.len() % 3 != 0 {
+			return Err(SaveError::Deserialization);
+		}
+		let Some(cart) = &mut self.cartridge_state else {
+			return Ok(());
+		};
+
+		for write in block.chunks_exact(3) {
+			let addr = read_u16(write, 0);
+			if matches!(addr, 0x0000..0x8000 | 0xA000..0xC000) {
+				cart.write(addr, write[2]);
+			}
+		}
+		Ok(())
+	}
+
+	fn load_bess_rtc(&mut self, block: &[u8], warnings: &mut Vec<BessWarning>) {
+		let Some(cart) = &mut self.cartridge_state else {
+			return;
+		};
+
+		match &mut cart.mbc {
+			Mbc::MBC3(state) if cart.info.has_rtc => {
+				if !state.rtc.load_save_footer(block) {
+					warnings.push(BessWarning::SizeMismatch {
+						region: "RTC",
+						expected: 0x30,
+						actual: block.len(),
+					});
+				}
+			}
+			_ => warnings.push(BessWarning::IgnoredBlock(
+				String::from_utf8_lossy(&RTC_BLOCK).into_owned(),
+			)),
+		}
+	}
+}
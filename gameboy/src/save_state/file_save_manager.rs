@@ -0,0 +1,156 @@
+// Save states and battery saves on the filesystem, shared by the native frontends
+//
+// Every rom image gets its own folder, named after its SHA-1 fingerprint:
+//   <directory>/<sha1>/index.json    entries of the slots with thumbnails, null for empty slots
+//   <directory>/<sha1>/slot<n>.json  save state of a slot
+//   <directory>/<sha1>/battery.sav   battery backed memory, see `Cartridge::export_save`
+
+use std::{
+	env, fs, io,
+	path::{Path, PathBuf},
+	time::{Duration, Instant},
+};
+
+use crate::{cartridge::header::CartridgeInfo, Gameboy};
+
+use super::{SaveError, SaveManager, SaveState, SaveStateEntry, SAVE_SLOTS};
+
+const INDEX_FILE: &str = "index.json";
+const BATTERY_FILE: &str = "battery.sav";
+
+pub struct FileSaveManager {
+	// Folder of the rom
+	directory: PathBuf,
+	flush_interval: Duration,
+	last_flush: Instant,
+}
+
+impl FileSaveManager {
+	/// Manages the saves of a rom inside `directory`
+	pub fn new(directory: impl AsRef<Path>, info: &CartridgeInfo) -> Self {
+		Self {
+			directory: directory.as_ref().join(info.fingerprint.to_string()),
+			flush_interval: Duration::from_secs(5),
+			last_flush: Instant::now(),
+		}
+	}
+
+	/// `$GBC_SAVE_DIR`, or the platform's data directory
+	pub fn default_directory() -> PathBuf {
+		if let Some(directory) = env::var_os("GBC_SAVE_DIR") {
+			return directory.into();
+		}
+
+		let data_directory = env::var_os("XDG_DATA_HOME")
+			.map(PathBuf::from)
+			.or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+			.or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")));
+
+		match data_directory {
+			Some(directory) => directory.join("rust-gbc").join("saves"),
+			None => PathBuf::from("saves"),
+		}
+	}
+
+	/// How often `flush_battery_periodically` writes battery saves
+	pub fn with_flush_interval(mut self, interval: Duration) -> Self {
+		self.flush_interval = interval;
+		self
+	}
+
+	pub fn directory(&self) -> &Path {
+		&self.directory
+	}
+
+	fn slot_file(slot: usize) -> String {
+		format!("slot{slot}.json")
+	}
+
+	fn read_index(&self) -> Vec<Option<SaveStateEntry>> {
+		let mut index = fs::read(self.directory.join(INDEX_FILE))
+			.ok()
+			.and_then(|index| serde_json::from_slice::<Vec<Option<SaveStateEntry>>>(&index).ok())
+			.unwrap_or_default();
+		index.resize(SAVE_SLOTS, None);
+		index
+	}
+
+	// Writes to a temporary file first, so a crash never leaves a partial file behind
+	fn write_file(&self, name: &str, data: &[u8]) -> Result<(), SaveError> {
+		fs::create_dir_all(&self.directory)?;
+
+		let path = self.directory.join(name);
+		let temporary = path.with_extension("tmp");
+		fs::write(&temporary, data)?;
+		fs::rename(temporary, path)?;
+		Ok(())
+	}
+
+	/// Loads the battery save of the rom, returns false when there is none
+	pub fn load_battery(&self, gameboy: &mut Gameboy) -> Result<bool, SaveError> {
+		let cart = gameboy
+			.cartridge_state
+			.as_mut()
+			.ok_or(SaveError::InvalidGame)?;
+
+		match fs::read(self.directory.join(BATTERY_FILE)) {
+			Ok(save) => cart.import_save(&save).map(|_| true),
+			Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(false),
+			Err(error) => Err(error.into()),
+		}
+	}
+
+	/// Writes battery backed memory if it changed since the last flush,
+	/// should be called when the frontend exits
+	pub fn flush_battery(&mut self, gameboy: &mut Gameboy) -> Result<bool, SaveError> {
+		self.last_flush = Instant::now();
+
+		let Some(cart) = gameboy.cartridge_state.as_mut() else {
+			return Ok(false);
+		};
+		if !cart.info.has_battery || !cart.is_save_dirty() {
+			return Ok(false);
+		}
+
+		self.write_file(BATTERY_FILE, &cart.export_save())?;
+		Ok(true)
+	}
+
+	/// Flushes battery backed memory once every flush interval
+	pub fn flush_battery_periodically(&mut self, gameboy: &mut Gameboy) -> Result<bool, SaveError> {
+		if self.last_flush.elapsed() < self.flush_interval {
+			return Ok(false);
+		}
+		self.flush_battery(gameboy)
+	}
+}
+
+impl SaveManager for FileSaveManager {
+	fn load_save_state(&self, slot: usize) -> Result<SaveState, SaveError> {
+		if slot >= SAVE_SLOTS {
+			return Err(SaveError::IndexOutOfBounds(slot));
+		}
+
+		let data = fs::read(self.directory.join(Self::slot_file(slot)))?;
+		serde_json::from_slice(&data).or(Err(SaveError::Deserialization))
+	}
+
+	fn save_save_state(&mut self, state: SaveState, slot: usize) -> Result<(), SaveError> {
+		if slot >= SAVE_SLOTS {
+			return Err(SaveError::IndexOutOfBounds(slot));
+		}
+
+		let mut index = self.read_index();
+		index[slot] = Some(state.info.clone());
+
+		let data = serde_json::to_vec(&state).or(Err(SaveError::Serialization))?;
+		let index = serde_json::to_vec(&index).or(Err(SaveError::Serialization))?;
+
+		self.write_file(&Self::slot_file(slot), &data)?;
+		self.write_file(INDEX_FILE, &index)
+	}
+
+	fn get_save_states(&self) -> Vec<Option<SaveStateEntry>> {
+		self.read_index()
+	}
+}
@@ -0,0 +1,341 @@
+// Binary save state container
+//
+// Layout, all integers are little endian:
+//   magic "GBCS" | version: u16 | chunks...
+// Each chunk is:
+//   id: [u8; 4] | length: u32 | payload
+//
+// Payloads are bincode encoded subsystem states. Unknown chunks are skipped,
+// so new subsystems can be added without breaking older readers of the same version.
+// Saves from older versions are migrated chunk by chunk, see `migrate`.
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sm83::CPULock;
+
+use crate::{
+	cartridge::{
+		header::{CartridgeInfo, RomFingerprint},
+		Cartridge,
+	},
+	work_ram::WorkRam,
+	Gameboy,
+};
+
+use super::SaveError;
+
+pub const MAGIC: [u8; 4] = *b"GBCS";
+pub const FORMAT_VERSION: u16 = 5;
+
+pub type ChunkId = [u8; 4];
+
+pub const ROM_CHUNK: ChunkId = *b"ROM ";
+pub const CPU_CHUNK: ChunkId = *b"CPU ";
+pub const PPU_CHUNK: ChunkId = *b"PPU ";
+pub const APU_CHUNK: ChunkId = *b"APU ";
+pub const CARTRIDGE_CHUNK: ChunkId = *b"CART";
+pub const WORK_RAM_CHUNK: ChunkId = *b"WRAM";
+pub const TIMER_CHUNK: ChunkId = *b"TIMR";
+pub const DMA_CHUNK: ChunkId = *b"DMA ";
+// Mode, IO registers and other system state
+pub const SYSTEM_CHUNK: ChunkId = *b"SYS ";
+pub const CHEATS_CHUNK: ChunkId = *b"CHTS";
+
+const HEADER_SIZE: usize = MAGIC.len() + 2;
+const CHUNK_HEADER_SIZE: usize = 8;
+
+/// Identifies the rom a save state was created with
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RomIdentity {
+	pub title: String,
+	pub header_checksum: u8,
+	pub global_checksum: u16,
+	// Missing in saves migrated from version 1
+	pub fingerprint: Option<RomFingerprint>,
+}
+
+impl RomIdentity {
+	/// Saves with a fingerprint only match the exact same rom image,
+	/// older saves fall back to the header
+	pub fn matches(&self, info: &CartridgeInfo) -> bool {
+		match self.fingerprint {
+			Some(fingerprint) => fingerprint == info.fingerprint,
+			None => {
+				self.title == info.title
+					&& self.header_checksum == info.header_checksum
+					&& self.global_checksum == info.global_checksum
+			}
+		}
+	}
+}
+
+impl From<&CartridgeInfo> for RomIdentity {
+	fn from(info: &CartridgeInfo) -> Self {
+		Self {
+			title: info.title.clone(),
+			header_checksum: info.header_checksum,
+			global_checksum: info.global_checksum,
+			fingerprint: Some(info.fingerprint),
+		}
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct Chunk {
+	pub id: ChunkId,
+	pub data: Vec<u8>,
+}
+
+impl Chunk {
+	pub(crate) fn encode<T: Serialize>(id: ChunkId, value: &T) -> Result<Self, SaveError> {
+		let data = bincode::serialize(value).or(Err(SaveError::Serialization))?;
+		Ok(Self { id, data })
+	}
+
+	fn decode<T: DeserializeOwned>(&self) -> Result<T, SaveError> {
+		bincode::deserialize(&self.data).or(Err(SaveError::Deserialization))
+	}
+}
+
+/// The chunks of a save state, migrated to the current version
+pub struct Container {
+	pub chunks: Vec<Chunk>,
+}
+
+impl Container {
+	fn chunk(&self, id: ChunkId) -> Result<&Chunk, SaveError> {
+		self.chunks
+			.iter()
+			.find(|chunk| chunk.id == id)
+			.ok_or(SaveError::Deserialization)
+	}
+
+	pub(crate) fn decode<T: DeserializeOwned>(&self, id: ChunkId) -> Result<T, SaveError> {
+		self.chunk(id)?.decode()
+	}
+
+	pub fn identity(&self) -> Result<RomIdentity, SaveError> {
+		self.decode(ROM_CHUNK)
+	}
+
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let size = self
+			.chunks
+			.iter()
+			.map(|chunk| CHUNK_HEADER_SIZE + chunk.data.len())
+			.sum::<usize>();
+
+		let mut bytes = Vec::with_capacity(HEADER_SIZE + size);
+		bytes.extend_from_slice(&MAGIC);
+		bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+
+		for chunk in &self.chunks {
+			bytes.extend_from_slice(&chunk.id);
+			bytes.extend_from_slice(&(chunk.data.len() as u32).to_le_bytes());
+			bytes.extend_from_slice(&chunk.data);
+		}
+		bytes
+	}
+
+	/// Parses a container, migrating it from older versions if needed
+	pub fn from_bytes(bytes: &[u8]) -> Result<Self, SaveError> {
+		if bytes.len() < HEADER_SIZE || bytes[..MAGIC.len()] != MAGIC {
+			return Err(SaveError::Deserialization);
+		}
+		let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+
+		let mut chunks = vec![];
+		let mut position = HEADER_SIZE;
+		while position < bytes.len() {
+			let header = bytes
+				.get(position..position + CHUNK_HEADER_SIZE)
+				.ok_or(SaveError::Deserialization)?;
+			let id = [header[0], header[1], header[2], header[3]];
+			let length = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+			position += CHUNK_HEADER_SIZE;
+
+			let data = bytes
+				.get(position..position + length)
+				.ok_or(SaveError::Deserialization)?;
+			chunks.push(Chunk {
+				id,
+				data: data.to_vec(),
+			});
+			position += length;
+		}
+
+		Ok(Self {
+			chunks: migrate(version, chunks)?,
+		})
+	}
+}
+
+/// Upgrades the chunks of an older version, one version at a time.
+/// Versions which are newer, or too old to migrate, are rejected.
+fn migrate(version: u16, chunks: Vec<Chunk>) -> Result<Vec<Chunk>, SaveError> {
+	match version {
+		FORMAT_VERSION => Ok(chunks),
+		1 => migrate(2, migrate_v1(chunks)?),
+		2 => migrate(3, migrate_v2(chunks)?),
+		3 => migrate(4, migrate_v3(chunks)?),
+		4 => migrate(5, migrate_v4(chunks)?),
+		_ => Err(SaveError::UnsupportedVersion(version)),
+	}
+}
+
+// Version 1 identified the rom by its header only
+fn migrate_v1(mut chunks: Vec<Chunk>) -> Result<Vec<Chunk>, SaveError> {
+	for chunk in chunks.iter_mut().filter(|chunk| chunk.id == ROM_CHUNK) {
+		let (title, header_checksum, global_checksum) = chunk.decode()?;
+		*chunk = Chunk::encode(
+			ROM_CHUNK,
+			&RomIdentity {
+				title,
+				header_checksum,
+				global_checksum,
+				fingerprint: None,
+			},
+		)?;
+	}
+	Ok(chunks)
+}
+
+// Version 3 added the HALT bug flag after the other CPU fields
+fn migrate_v2(mut chunks: Vec<Chunk>) -> Result<Vec<Chunk>, SaveError> {
+	for chunk in chunks.iter_mut().filter(|chunk| chunk.id == CPU_CHUNK) {
+		let halt_bug = bincode::serialize(&false).or(Err(SaveError::Serialization))?;
+		chunk.data.extend(halt_bug);
+	}
+	Ok(chunks)
+}
+
+// Version 4 added the illegal opcode lock up after the HALT bug flag
+fn migrate_v3(mut chunks: Vec<Chunk>) -> Result<Vec<Chunk>, SaveError> {
+	for chunk in chunks.iter_mut().filter(|chunk| chunk.id == CPU_CHUNK) {
+		let locked = bincode::serialize(&None::<CPULock>).or(Err(SaveError::Serialization))?;
+		chunk.data.extend(locked);
+	}
+	Ok(chunks)
+}
+
+// Version 5 stopped saving the cartridge info after the cartridge state,
+// it is rebuilt from the rom. Bincode ignores the trailing old info when decoding.
+fn migrate_v4(mut chunks: Vec<Chunk>) -> Result<Vec<Chunk>, SaveError> {
+	for chunk in chunks
+		.iter_mut()
+		.filter(|chunk| chunk.id == CARTRIDGE_CHUNK)
+	{
+		let (data, mbc) = chunk.decode()?;
+		let cart = Cartridge::from_saved_state(data, mbc);
+		*chunk = Chunk::encode(CARTRIDGE_CHUNK, &cart.saved_state())?;
+	}
+	Ok(chunks)
+}
+
+/// Legacy saves stored the whole emulator as JSON
+pub fn is_legacy_json(bytes: &[u8]) -> bool {
+	bytes.first() == Some(&b'{')
+}
+
+impl Gameboy {
+	pub(crate) fn to_save_container(&self) -> Result<Container, SaveError> {
+		let cart = self
+			.cartridge_state
+			.as_ref()
+			.ok_or(SaveError::InvalidGame)?;
+
+		let chunks = vec![
+			Chunk::encode(ROM_CHUNK, &RomIdentity::from(&cart.info))?,
+			Chunk::encode(CPU_CHUNK, &self.cpu_state)?,
+			Chunk::encode(PPU_CHUNK, &self.ppu)?,
+			// Buffered output samples are not saved, the frontend only needs new samples
+			Chunk::encode(APU_CHUNK, &self.apu)?,
+			Chunk::encode(CARTRIDGE_CHUNK, &cart.saved_state())?,
+			Chunk::encode(WORK_RAM_CHUNK, &(&self.w_ram, &self.hram[..]))?,
+			Chunk::encode(TIMER_CHUNK, &self.timer)?,
+			Chunk::encode(DMA_CHUNK, &(&self.dma_controller, &self.oam_dma))?,
+			Chunk::encode(
+				SYSTEM_CHUNK,
+				&(
+					&self.mode,
+					&self.io_register_state,
+					&self.serial_output,
+					self.raw_joyp_input,
+					self.booting,
+					self.t_states,
+					self.speed_switch_delay,
+				),
+			)?,
+			Chunk::encode(CHEATS_CHUNK, &self.cheats)?,
+		];
+
+		Ok(Container { chunks })
+	}
+
+	/// Decodes save data, which must have been created with the given rom.
+	/// Without a rom the save is loaded as is.
+	pub(crate) fn from_save_data(
+		data: &[u8],
+		info: Option<&CartridgeInfo>,
+	) -> Result<Self, SaveError> {
+		if is_legacy_json(data) {
+			return Self::from_legacy_json(data, info);
+		}
+
+		let container = Container::from_bytes(data)?;
+		if let Some(info) = info {
+			if !container.identity()?.matches(info) {
+				return Err(SaveError::InvalidGame);
+			}
+		}
+		Self::from_save_container(&container)
+	}
+
+	// Legacy saves only identify the rom by its title
+	fn from_legacy_json(data: &[u8], info: Option<&CartridgeInfo>) -> Result<Self, SaveError> {
+		let gameboy: Gameboy = serde_json::from_slice(data).or(Err(SaveError::Deserialization))?;
+
+		match (&gameboy.cartridge_state, info) {
+			(Some(_), None) => Ok(gameboy),
+			(Some(cart), Some(info)) if cart.info.title == info.title => Ok(gameboy),
+			_ => Err(SaveError::InvalidGame),
+		}
+	}
+
+	/// Restores the emulator from a container,
+	/// the rom banks of the cartridge are not part of the save and must be restored by the caller
+	pub(crate) fn from_save_container(container: &Container) -> Result<Self, SaveError> {
+		let mut gameboy = Gameboy::default();
+
+		let (
+			mode,
+			io_register_state,
+			serial_output,
+			raw_joyp_input,
+			booting,
+			t_states,
+			speed_switch_delay,
+		) = container.decode(SYSTEM_CHUNK)?;
+		gameboy.set_gb_mode(mode);
+		gameboy.io_register_state = io_register_state;
+		gameboy.serial_output = serial_output;
+		gameboy.raw_joyp_input = raw_joyp_input;
+		gameboy.booting = booting;
+		gameboy.t_states = t_states;
+		gameboy.speed_switch_delay = speed_switch_delay;
+
+		gameboy.cpu_state = container.decode(CPU_CHUNK)?;
+		gameboy.ppu = container.decode(PPU_CHUNK)?;
+		gameboy.apu = container.decode(APU_CHUNK)?;
+		let (data, mbc) = container.decode(CARTRIDGE_CHUNK)?;
+		gameboy.cartridge_state = Some(Cartridge::from_saved_state(data, mbc));
+		let (w_ram, hram): (WorkRam, Vec<u8>) = container.decode(WORK_RAM_CHUNK)?;
+		gameboy.w_ram = w_ram;
+		gameboy.hram = hram.try_into().or(Err(SaveError::Deserialization))?;
+		gameboy.timer = container.decode(TIMER_CHUNK)?;
+		(gameboy.dma_controller, gameboy.oam_dma) = container.decode(DMA_CHUNK)?;
+
+		gameboy.cheats = container.decode(CHEATS_CHUNK)?;
+
+		Ok(gameboy)
+	}
+}
@@ -0,0 +1,105 @@
+// Preview of the screen stored with save states
+//
+// The screen is scaled down to half size and stored as RGB555 colors,
+// compressed as runs of (length: u8, color: u16 little endian).
+
+use serde::{Deserialize, Serialize};
+
+use crate::{lcd::GameboyLCD, util::base64};
+
+pub const THUMBNAIL_WIDTH: usize = 80;
+pub const THUMBNAIL_HEIGHT: usize = 72;
+
+const SCREEN_WIDTH: usize = 160;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Thumbnail {
+	#[serde(with = "encoded_runs")]
+	runs: Vec<u8>,
+}
+
+fn to_rgb555(pixel: [u16; 3]) -> u16 {
+	let [r, g, b] = pixel.map(|channel| channel >> 3);
+	r | g << 5 | b << 10
+}
+
+fn to_rgba(color: u16) -> [u8; 4] {
+	let channel = |shift: u16| {
+		let value = ((color >> shift) & 0x1F) as u8;
+		value << 3 | value >> 2
+	};
+	[channel(0), channel(5), channel(10), 0xFF]
+}
+
+impl Thumbnail {
+	/// Captures the front buffer of the screen
+	pub fn capture(lcd: &GameboyLCD) -> Self {
+		let screen = lcd.front_buffer();
+
+		// Each pixel averages a 2x2 block of the screen
+		let colors = (0..THUMBNAIL_HEIGHT).flat_map(|y| {
+			(0..THUMBNAIL_WIDTH).map(move |x| {
+				let mut sum = [0u16; 3];
+				for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+					let index = ((y * 2 + dy) * SCREEN_WIDTH + x * 2 + dx) * 4;
+					for (channel, value) in sum.iter_mut().zip(&screen[index..index + 3]) {
+						*channel += *value as u16;
+					}
+				}
+				to_rgb555(sum.map(|channel| channel / 4))
+			})
+		});
+
+		let mut runs: Vec<(u8, u16)> = vec![];
+		for color in colors {
+			match runs.last_mut() {
+				Some((length, last)) if *last == color && *length < u8::MAX => *length += 1,
+				_ => runs.push((1, color)),
+			}
+		}
+
+		Self {
+			runs: runs
+				.into_iter()
+				.flat_map(|(length, color)| {
+					let [low, high] = color.to_le_bytes();
+					[length, low, high]
+				})
+				.collect(),
+		}
+	}
+
+	/// Size of the compressed thumbnail in bytes
+	pub fn compressed_size(&self) -> usize {
+		self.runs.len()
+	}
+
+	/// Decodes the thumbnail to `THUMBNAIL_WIDTH` x `THUMBNAIL_HEIGHT` RGBA pixels
+	pub fn to_rgba(&self) -> Vec<u8> {
+		let mut image = Vec::with_capacity(THUMBNAIL_WIDTH * THUMBNAIL_HEIGHT * 4);
+		for run in self.runs.chunks_exact(3) {
+			let pixel = to_rgba(u16::from_le_bytes([run[1], run[2]]));
+			for _ in 0..run[0] {
+				image.extend_from_slice(&pixel);
+			}
+		}
+		image.resize(THUMBNAIL_WIDTH * THUMBNAIL_HEIGHT * 4, 0);
+		image
+	}
+}
+
+// Stored as base64 in text formats like JSON
+mod encoded_runs {
+	use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+	use super::base64;
+
+	pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(&base64::encode(data))
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+		let data = String::deserialize(deserializer)?;
+		base64::decode(&data).ok_or(D::Error::custom("invalid base64 thumbnail"))
+	}
+}
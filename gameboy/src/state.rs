@@ -4,6 +4,7 @@ use crate::{
 	apu::Apu,
 	audio::Audio,
 	cgb::{CGBState, Speed},
+	cheats::{Cheat, Cheats},
 	dma_controller::{DMAController, DMATransferRequest},
 	io_registers::JOYP,
 	oam_dma::{step_oam_dma, OamDmaState},
@@ -13,11 +14,11 @@ use crate::{
 };
 
 use super::{
-	cartridge::Cartridge,
+	cartridge::{camera::ImageProvider, header::CartridgeParseError, rtc::RtcClock, Cartridge},
 	io_registers::IORegisterState,
-	joypad::JoypadState,
+	joypad::{JoypadState, TiltState},
 	ppu::{PPUMode, PPU},
-	save_state::{RomSource, SaveState},
+	save_state::{RomSource, SaveError, SaveState},
 	timer::Timer,
 };
 
@@ -63,6 +64,8 @@ pub struct Gameboy {
 	pub t_states: u64,
 	pub speed_switch_delay: u32,
 	pub audio: Audio,
+	#[serde(default)]
+	pub(crate) cheats: Cheats,
 }
 
 impl Default for Gameboy {
@@ -89,6 +92,7 @@ impl Default for Gameboy {
 			t_states: 0,
 			speed_switch_delay: 0,
 			audio: Audio::default(),
+			cheats: Cheats::default(),
 		};
 		emulator.set_gb_mode(Mode::GBC(CGBState::default()));
 		emulator
@@ -189,6 +193,10 @@ impl Gameboy {
 			self.audio.step(&mut self.apu);
 			let mode = self.ppu.step(&mut self.cpu_state.interrupt_request);
 
+			if let Some(PPUMode::VBlank) = mode {
+				self.apply_game_shark_codes();
+			}
+
 			if let Some(PPUMode::HBlank) = mode {
 				// HDMA is not processed during speed switch
 				if !self.speed_switch_delay > 0 {
@@ -198,6 +206,9 @@ impl Gameboy {
 				}
 			}
 		}
+		if let Some(cart) = &mut self.cartridge_state {
+			cart.tick(t_states);
+		}
 		self.t_states += t_states as u64;
 	}
 
@@ -205,9 +216,81 @@ impl Gameboy {
 		self.cpu_state.interrupt_request |= interrupt.flag_bit();
 	}
 
-	pub fn load_rom(&mut self, rom: &[u8], source: Option<RomSource>) {
-		if let Ok(cart) = Cartridge::try_new(rom, source) {
-			self.cartridge_state = Some(cart);
+	/// Inserts a cartridge, the current one is kept if the rom can't be parsed
+	pub fn load_rom(
+		&mut self,
+		rom: &[u8],
+		source: Option<RomSource>,
+	) -> Result<(), CartridgeParseError> {
+		self.cartridge_state = Some(Cartridge::try_new(rom, source)?);
+		self.update_cheats();
+		Ok(())
+	}
+
+	pub fn cheats(&self) -> &Cheats {
+		&self.cheats
+	}
+
+	/// Adds a cheat, returning its index
+	pub fn add_cheat(&mut self, cheat: Cheat) -> usize {
+		let index = self.cheats.add(cheat);
+		self.update_cheats();
+		index
+	}
+
+	pub fn remove_cheat(&mut self, index: usize) -> Option<Cheat> {
+		let cheat = self.cheats.remove(index);
+		self.update_cheats();
+		cheat
+	}
+
+	pub fn set_cheat_enabled(&mut self, index: usize, enabled: bool) {
+		self.cheats.set_enabled(index, enabled);
+		self.update_cheats();
+	}
+
+	/// Replaces all cheats, for example with a list restored alongside a save state
+	pub fn set_cheats(&mut self, cheats: Cheats) {
+		self.cheats = cheats;
+		self.update_cheats();
+	}
+
+	// Game Genie codes are applied by the cartridge
+	fn update_cheats(&mut self) {
+		if let Some(cart) = &mut self.cartridge_state {
+			cart.set_game_genie_codes(self.cheats.game_genie_codes());
+		}
+	}
+
+	// Codes write RAM directly, so they can't reach MBC or IO registers
+	fn apply_game_shark_codes(&mut self) {
+		let codes: Vec<_> = self.cheats.game_shark_codes().copied().collect();
+		for code in codes {
+			match code.address {
+				0xA000..0xC000 => {
+					if let Some(cart) = &mut self.cartridge_state {
+						cart.write_ram_bank(code.bank, code.address, code.value);
+					}
+				}
+				0xC000..0xD000 => {
+					self.w_ram.get_low_bank_mut()[code.address as usize - 0xC000] = code.value;
+				}
+				_ => {
+					let current = self.w_ram.get_bank_number();
+					if let Some(bank) = code.work_ram_bank() {
+						self.w_ram.set_bank_number(bank);
+					}
+					self.w_ram.get_high_bank_mut()[code.address as usize - 0xD000] = code.value;
+					self.w_ram.set_bank_number(current);
+				}
+			}
+		}
+	}
+
+	/// Selects the time source used by the cartridge real-time clock
+	pub fn set_rtc_clock(&mut self, clock: RtcClock) {
+		if let Some(cart) = &mut self.cartridge_state {
+			cart.set_rtc_clock(clock);
 		}
 	}
 
@@ -219,30 +302,77 @@ impl Gameboy {
 		self.raw_joyp_input = state.as_byte();
 	}
 
+	/// Sets the image source of the Game Boy Camera sensor
+	pub fn set_image_provider(&mut self, provider: impl ImageProvider + Send + 'static) {
+		if let Some(cart) = &mut self.cartridge_state {
+			cart.set_image_provider(provider);
+		}
+	}
+
+	/// Updates the accelerometer reading for cartridges with a tilt sensor
+	pub fn set_tilt_state(&mut self, state: &TiltState) {
+		if let Some(cart) = &mut self.cartridge_state {
+			cart.set_tilt(state);
+		}
+	}
+
 	pub fn load_save_state(self, save_state: SaveState) -> Self {
-		let Some(cart) = self.cartridge_state.as_ref() else {
-			return self;
-		};
+		self.try_load_save_state(&save_state).unwrap_or(self)
+	}
 
-		let Ok(mut new_state) = serde_json::from_str::<Gameboy>(&save_state.data) else {
-			return self;
-		};
+	/// Restores a save state made with the currently loaded rom,
+	/// states of any other rom image are rejected with `SaveError::InvalidGame`
+	pub fn try_load_save_state(&self, save_state: &SaveState) -> Result<Self, SaveError> {
+		self.restore_save_state(save_state, true)
+	}
 
-		let Some(new_cart) = new_state.cartridge_state.as_mut() else {
-			return self;
-		};
+	/// Restores a save state without checking it was made with the loaded rom,
+	/// e.g. to carry progress over to a patched or revised rom
+	pub fn force_load_save_state(&self, save_state: &SaveState) -> Result<Self, SaveError> {
+		self.restore_save_state(save_state, false)
+	}
 
-		if cart.info.title != new_cart.info.title {
-			return self;
+	fn restore_save_state(&self, save_state: &SaveState, verify: bool) -> Result<Self, SaveError> {
+		let cart = self
+			.cartridge_state
+			.as_ref()
+			.ok_or(SaveError::InvalidGame)?;
+
+		if verify
+			&& save_state
+				.info
+				.rom_fingerprint
+				.is_some_and(|fingerprint| fingerprint != cart.info.fingerprint)
+		{
+			return Err(SaveError::InvalidGame);
 		}
 
+		let info = verify.then_some(&cart.info);
+		self.inherit_rom(Gameboy::from_save_data(&save_state.data, info)?)
+	}
+
+	/// Moves the rom and attached devices of this emulator into a restored state,
+	/// roms are not part of save states
+	pub(crate) fn inherit_rom(&self, mut new_state: Gameboy) -> Result<Self, SaveError> {
+		let cart = self
+			.cartridge_state
+			.as_ref()
+			.ok_or(SaveError::InvalidGame)?;
+		let new_cart = new_state
+			.cartridge_state
+			.as_mut()
+			.ok_or(SaveError::InvalidGame)?;
+
 		new_cart.data.rom_banks = cart.data.rom_banks.clone();
+		new_cart.info = cart.info.clone();
+		new_cart.inherit_devices(cart);
 		new_cart.data.loaded = true;
+		new_state.update_cheats();
 
-		new_state
+		Ok(new_state)
 	}
 
-	fn set_gb_mode(&mut self, mode: Mode) {
+	pub(crate) fn set_gb_mode(&mut self, mode: Mode) {
 		self.boot_rom = match mode {
 			Mode::DMG => include_bytes!("../../roms/other/dmg_boot.bin").to_vec(),
 			Mode::GBC(_) => include_bytes!("../../roms/other/cgb_boot.bin").to_vec(),
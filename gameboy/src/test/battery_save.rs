@@ -0,0 +1,120 @@
+use sm83::memory_mapper::MemoryMapper;
+
+use crate::{
+	cartridge::{rtc::RTC_FOOTER_SIZE, Cartridge},
+	test::util::rom_loader::blank_rom,
+};
+
+fn cartridge(cartridge_type: u8, ram_size: u8) -> Cartridge {
+	let mut cart = Cartridge::try_new(&blank_rom(cartridge_type, 2, ram_size), None).unwrap();
+	cart.write(0x0000, 0x0A);
+	cart
+}
+
+#[test]
+fn save_round_trip() {
+	let mut cart = cartridge(0x1B, 3);
+	cart.write(0x4000, 0x02);
+	cart.write(0xA123, 0x42);
+
+	let save = cart.export_save();
+	assert_eq!(save.len(), 0x8000);
+	assert_eq!(save[0x4123], 0x42);
+
+	let mut new_cart = cartridge(0x1B, 3);
+	new_cart.import_save(&save).unwrap();
+	new_cart.write(0x4000, 0x02);
+	assert_eq!(new_cart.read(0xA123), 0x42);
+}
+
+#[test]
+fn save_dirty_flag() {
+	let mut cart = cartridge(0x03, 2);
+	assert!(!cart.is_save_dirty());
+
+	cart.write(0xA000, 0x01);
+	assert!(cart.is_save_dirty());
+
+	cart.export_save();
+	assert!(!cart.is_save_dirty());
+
+	cart.write(0x2000, 0x02);
+	assert!(!cart.is_save_dirty());
+
+	// Writing the value already stored
+	cart.write(0xA000, 0x01);
+	assert!(!cart.is_save_dirty());
+
+	// Writes while RAM is disabled are ignored
+	cart.write(0x0000, 0x00);
+	cart.write(0xA000, 0x02);
+	assert!(!cart.is_save_dirty());
+}
+
+#[test]
+fn save_dirty_flag_only_for_battery_ram() {
+	// MBC1 with RAM but no battery
+	let mut cart = cartridge(0x02, 2);
+	cart.write(0xA000, 0x01);
+	assert!(!cart.is_save_dirty());
+
+	// MBC3 RTC registers are mapped over RAM
+	let mut cart = cartridge(0x10, 3);
+	cart.write(0x4000, 0x08);
+	cart.write(0xA000, 0x12);
+	assert!(!cart.is_save_dirty());
+
+	cart.write(0x4000, 0x03);
+	cart.write(0xA000, 0x12);
+	assert!(cart.is_save_dirty());
+}
+
+#[test]
+fn save_rejects_short_data() {
+	let mut cart = cartridge(0x03, 2);
+	assert!(cart.import_save(&[0; 0x1000]).is_err());
+}
+
+#[test]
+fn mbc3_rtc_footer() {
+	let mut cart = cartridge(0x10, 3);
+	cart.write(0x4000, 0x09);
+	cart.write(0xA000, 42);
+
+	let save = cart.export_save();
+	assert_eq!(save.len(), 0x8000 + RTC_FOOTER_SIZE);
+
+	let mut new_cart = cartridge(0x10, 3);
+	new_cart.import_save(&save).unwrap();
+	assert_eq!(new_cart.rtc().unwrap().registers().minutes, 42);
+
+	// Carts without a timer have no footer, but accept saves with one
+	let mut no_rtc = cartridge(0x13, 3);
+	assert_eq!(no_rtc.export_save().len(), 0x8000);
+	no_rtc.import_save(&save).unwrap();
+}
+
+#[test]
+fn mbc2_save_is_512_nibbles() {
+	let mut cart = cartridge(0x06, 0);
+	cart.write(0xA010, 0x3C);
+
+	let save = cart.export_save();
+	assert_eq!(save.len(), 512);
+	assert_eq!(save[0x10], 0x0C);
+
+	let mut new_cart = cartridge(0x06, 0);
+	new_cart.import_save(&save).unwrap();
+	assert_eq!(new_cart.read(0xA010), 0xFC);
+}
+
+#[test]
+fn mbc7_save_is_eeprom() {
+	let mut cart = cartridge(0x22, 0);
+	assert_eq!(cart.export_save(), vec![0xFF; 256]);
+
+	let mut save = vec![0xFF; 256];
+	save[0] = 0x12;
+	cart.import_save(&save).unwrap();
+	assert_eq!(cart.export_save(), save);
+}
@@ -0,0 +1,227 @@
+use sm83::{
+	memory_mapper::MemoryMapper,
+	registers::{Addressable, CPURegister16},
+};
+
+use crate::{
+	cgb::Speed,
+	save_state::{bess::BessWarning, SaveError},
+	test::{
+		boot::{cgb_test_instance, dmg_test_instance},
+		util::rom_loader::blank_rom,
+	},
+	Gameboy, Mode,
+};
+
+const FOOTER_SIZE: usize = 8;
+const END_BLOCK_SIZE: usize = 8;
+
+fn mbc3_rom() -> Vec<u8> {
+	let mut rom = blank_rom(0x10, 2, 3);
+	rom[0x014E..0x0150].copy_from_slice(&[0x12, 0x34]);
+	rom
+}
+
+fn gameboy(mut gameboy: Gameboy) -> Gameboy {
+	gameboy.load_rom(&mbc3_rom(), None).unwrap();
+	gameboy.tick_m_cycles(1000);
+	gameboy
+}
+
+fn block_offset(file: &[u8], id: &[u8; 4]) -> usize {
+	let footer = file.len() - FOOTER_SIZE;
+	let first_block = u32::from_le_bytes(file[footer..footer + 4].try_into().unwrap()) as usize;
+	first_block
+		+ file[first_block..]
+			.windows(4)
+			.position(|window| window == id)
+			.unwrap()
+}
+
+// Inserts a block before the END block
+fn insert_block(file: &[u8], id: &[u8; 4], data: &[u8]) -> Vec<u8> {
+	let end = file.len() - FOOTER_SIZE - END_BLOCK_SIZE;
+	let mut block = id.to_vec();
+	block.extend_from_slice(&(data.len() as u32).to_le_bytes());
+	block.extend_from_slice(data);
+	[&file[..end], &block, &file[end..]].concat()
+}
+
+#[test]
+fn bess_file_layout() {
+	let file = gameboy(cgb_test_instance()).export_bess().unwrap();
+	assert!(file.ends_with(b"BESS"));
+
+	let name = block_offset(&file, b"NAME");
+	let length = u32::from_le_bytes(file[name + 4..name + 8].try_into().unwrap()) as usize;
+	assert!(file[name + 8..name + 8 + length].starts_with(b"rust-gbc"));
+
+	let core = block_offset(&file, b"CORE");
+	assert_eq!(&file[core + 4..core + 8], &0xD0u32.to_le_bytes());
+	assert_eq!(&file[core + 12..core + 16], b"CCE ");
+
+	// Work RAM is the first region, at the start of the file
+	let regions = core + 8 + 0x98;
+	assert_eq!(&file[regions..regions + 8], &[0, 0x80, 0, 0, 0, 0, 0, 0]);
+}
+
+#[test]
+fn bess_round_trip_cgb() {
+	let mut gameboy = gameboy(cgb_test_instance());
+	gameboy.write(0xFF70, 0x03);
+	gameboy.write(0xD123, 0x45);
+	gameboy.write(0xC010, 0x67);
+	gameboy.write(0xFF4F, 0x01);
+	gameboy.write(0x9800, 0x89);
+	gameboy.write(0xFF80, 0xAB);
+	gameboy.write(0xFF43, 0x21);
+	gameboy.write(0xFF68, 0x80);
+	gameboy.write(0xFF69, 0x1F);
+	gameboy.cpu_state.write(CPURegister16::HL, 0xBEEF);
+
+	// Enable RAM, select rom bank 5 and RAM bank 2
+	gameboy.write(0x0000, 0x0A);
+	gameboy.write(0x2000, 0x05);
+	gameboy.write(0x4000, 0x02);
+	gameboy.write(0xA100, 0xCD);
+
+	let file = gameboy.export_bess().unwrap();
+	// CGB-E, the second letter is C for CGB or A for AGB
+	let core = block_offset(&file, b"CORE");
+	assert_eq!(&file[core + 12..core + 16], b"CCE ");
+
+	let (restored, warnings) = cgb_test_instance()
+		.load_rom_and(&mbc3_rom())
+		.import_bess(&file)
+		.unwrap();
+
+	assert_eq!(warnings, vec![]);
+	assert_eq!(restored.get_wram_bank(), 3);
+	assert_eq!(restored.read(0xD123), 0x45);
+	assert_eq!(restored.read(0xC010), 0x67);
+	assert_eq!(restored.read(0x9800), 0x89);
+	assert_eq!(restored.read(0xFF80), 0xAB);
+	assert_eq!(restored.read(0xFF43), 0x21);
+	assert_eq!(restored.ppu.bg_color.bytes()[0], 0x1F);
+	assert_eq!(restored.read(0xA100), 0xCD);
+	assert_eq!(restored.cpu_state.read(CPURegister16::HL), 0xBEEF);
+	assert_eq!(
+		restored.cpu_state.read(CPURegister16::PC),
+		gameboy.cpu_state.read(CPURegister16::PC)
+	);
+	assert_eq!(
+		restored
+			.cartridge_state
+			.as_ref()
+			.unwrap()
+			.mbc
+			.register_writes(),
+		gameboy
+			.cartridge_state
+			.as_ref()
+			.unwrap()
+			.mbc
+			.register_writes()
+	);
+}
+
+#[test]
+fn bess_round_trip_dmg() {
+	let mut gameboy = gameboy(dmg_test_instance());
+	gameboy.write(0xD123, 0x45);
+	gameboy.cpu_state.halted = true;
+
+	let file = gameboy.export_bess().unwrap();
+	let core = block_offset(&file, b"CORE");
+	assert_eq!(&file[core + 12..core + 16], b"GD  ");
+
+	// Importing into a CGB switches to the model of the save state
+	let (restored, warnings) = cgb_test_instance()
+		.load_rom_and(&mbc3_rom())
+		.import_bess(&file)
+		.unwrap();
+	assert_eq!(warnings, vec![]);
+	assert!(matches!(restored.mode, Mode::DMG));
+	assert!(restored.cpu_state.halted);
+	assert_eq!(restored.read(0xD123), 0x45);
+}
+
+#[test]
+fn bess_double_speed() {
+	let mut gameboy = gameboy(cgb_test_instance());
+	if let Mode::GBC(state) = &mut gameboy.mode {
+		state.set_speed(Speed::Double);
+	}
+
+	let file = gameboy.export_bess().unwrap();
+	let (restored, _) = gameboy.import_bess(&file).unwrap();
+	assert!(matches!(restored.mode.get_speed(), Speed::Double));
+}
+
+#[test]
+fn bess_import_warnings() {
+	let gameboy = gameboy(cgb_test_instance());
+	let file = gameboy.export_bess().unwrap();
+	let file = insert_block(&file, b"SGB ", &[0; 4]);
+
+	let mut other_rom = mbc3_rom();
+	other_rom[0x0134..0x0138].copy_from_slice(b"GAME");
+	let (_, warnings) = cgb_test_instance()
+		.load_rom_and(&other_rom)
+		.import_bess(&file)
+		.unwrap();
+
+	assert_eq!(
+		warnings,
+		vec![
+			BessWarning::DifferentRom,
+			BessWarning::IgnoredBlock("SGB ".to_owned())
+		]
+	);
+}
+
+#[test]
+fn bess_region_size_mismatch() {
+	let gameboy = gameboy(cgb_test_instance());
+	let mut file = gameboy.export_bess().unwrap();
+
+	// Shrink the reported cartridge RAM region
+	let cart_ram = block_offset(&file, b"CORE") + 8 + 0x98 + 2 * 8;
+	file[cart_ram..cart_ram + 4].copy_from_slice(&0x2000u32.to_le_bytes());
+
+	let (_, warnings) = gameboy.import_bess(&file).unwrap();
+	assert_eq!(
+		warnings,
+		vec![BessWarning::SizeMismatch {
+			region: "Cartridge RAM",
+			expected: 0x8000,
+			actual: 0x2000
+		}]
+	);
+}
+
+#[test]
+fn bess_rejects_invalid_files() {
+	let gameboy = gameboy(cgb_test_instance());
+	let file = gameboy.export_bess().unwrap();
+
+	assert!(matches!(
+		gameboy.import_bess(&file[..file.len() - 1]),
+		Err(SaveError::Deserialization)
+	));
+
+	let mut unsupported = file.clone();
+	let core = block_offset(&unsupported, b"CORE");
+	unsupported[core + 8..core + 10].copy_from_slice(&2u16.to_le_bytes());
+	assert!(matches!(
+		gameboy.import_bess(&unsupported),
+		Err(SaveError::UnsupportedVersion(2))
+	));
+}
+
+impl Gameboy {
+	fn load_rom_and(mut self, rom: &[u8]) -> Self {
+		self.load_rom(rom, None).unwrap();
+		self
+	}
+}
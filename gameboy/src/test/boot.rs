@@ -7,7 +7,7 @@ lazy_static! {
 		// Not a specific rom, just one that has a valid logo and will pass checks
 		let rom = *include_bytes!("../../../test_data/dmg-acid2/dmg-acid2.gb");
 
-		state.load_rom(&rom, None);
+		state.load_rom(&rom, None).unwrap();
 
 		state.run_until_boot();
 		state
@@ -18,7 +18,7 @@ lazy_static! {
 		// Not a specific rom, just one that has a valid logo and will pass checks
 		let rom = *include_bytes!("../../../test_data/cgb-acid2/cgb-acid2.gbc");
 
-		state.load_rom(&rom, None);
+		state.load_rom(&rom, None).unwrap();
 
 		state.run_until_boot();
 		state
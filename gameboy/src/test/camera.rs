@@ -0,0 +1,139 @@
+use sm83::memory_mapper::MemoryMapper;
+
+use crate::{
+	cartridge::{
+		camera::{StaticImage, TestPattern, SENSOR_HEIGHT, SENSOR_WIDTH},
+		Cartridge,
+	},
+	test::util::rom_loader::blank_rom,
+};
+
+const REGISTERS: u8 = 0x10;
+
+fn camera_cartridge() -> Cartridge {
+	let mut cart = Cartridge::try_new(&blank_rom(0xFC, 5, 4), None).unwrap();
+	cart.write(0x0000, 0x0A);
+	cart.write(0x4000, REGISTERS);
+
+	// Exposure of 0x0800 passes the image through unchanged
+	cart.write(0xA002, 0x08);
+	cart.write(0xA003, 0x00);
+
+	// Evenly spaced thresholds for every pixel of the dithering matrix
+	for i in 0..16 {
+		cart.write(0xA006 + i * 3, 0x40);
+		cart.write(0xA007 + i * 3, 0x80);
+		cart.write(0xA008 + i * 3, 0xC0);
+	}
+	cart
+}
+
+fn solid_image(value: u8) -> StaticImage {
+	StaticImage::from_greyscale(1, 1, &[value])
+}
+
+// Runs a capture to completion, returning the number of t-states it took
+fn capture(cart: &mut Cartridge) -> u32 {
+	cart.write(0x4000, REGISTERS);
+	cart.write(0xA000, 0x01);
+
+	let mut t_states = 0;
+	while cart.read(0xA000) & 1 != 0 {
+		cart.tick(4);
+		t_states += 4;
+	}
+	cart.write(0x4000, 0x00);
+	t_states
+}
+
+fn read_image(cart: &Cartridge) -> Vec<u8> {
+	(0xA100..0xAF00).map(|addr| cart.read(addr)).collect()
+}
+
+#[test]
+fn camera_capture_timing() {
+	let mut cart = camera_cartridge();
+	assert_eq!(capture(&mut cart), (32446 + 512 + 16 * 0x0800) * 4);
+
+	cart.write(0x4000, REGISTERS);
+	cart.write(0xA001, 0x80);
+	assert_eq!(capture(&mut cart), (32446 + 16 * 0x0800) * 4);
+}
+
+#[test]
+fn camera_ram_inaccessible_while_busy() {
+	let mut cart = camera_cartridge();
+	cart.write(0x4000, 0x00);
+	cart.write(0xA100, 0x12);
+
+	cart.write(0x4000, REGISTERS);
+	cart.write(0xA000, 0x01);
+	cart.write(0x4000, 0x00);
+	assert_eq!(cart.read(0xA100), 0x00);
+
+	cart.tick(u32::MAX);
+	assert_ne!(cart.read(0xA100), 0x12);
+}
+
+#[test]
+fn camera_dithers_solid_images() {
+	for (value, color) in [(0xFF, 0), (0xA0, 1), (0x60, 2), (0x00, 3)] {
+		let mut cart = camera_cartridge();
+		cart.set_image_provider(solid_image(value));
+		capture(&mut cart);
+
+		let low = if color & 1 != 0 { 0xFF } else { 0x00 };
+		let high = if color & 2 != 0 { 0xFF } else { 0x00 };
+		let image = read_image(&cart);
+		assert!(image.chunks(2).all(|row| row == [low, high]));
+	}
+}
+
+#[test]
+fn camera_invert_and_exposure() {
+	let mut cart = camera_cartridge();
+	cart.set_image_provider(solid_image(0xFF));
+	cart.write(0xA004, 0x08);
+	capture(&mut cart);
+	assert!(read_image(&cart).iter().all(|byte| *byte == 0xFF));
+
+	// Halving the exposure darkens the image
+	let mut cart = camera_cartridge();
+	cart.set_image_provider(solid_image(0xA0));
+	cart.write(0xA002, 0x04);
+	capture(&mut cart);
+	assert!(read_image(&cart).chunks(2).all(|row| row == [0x00, 0xFF]));
+}
+
+#[test]
+fn camera_frontend_callback() {
+	let mut cart = camera_cartridge();
+	// Left half black, right half white
+	cart.set_image_provider(|| {
+		(0..SENSOR_WIDTH * SENSOR_HEIGHT)
+			.map(|i| {
+				if i % SENSOR_WIDTH < SENSOR_WIDTH / 2 {
+					0
+				} else {
+					255
+				}
+			})
+			.collect::<Vec<u8>>()
+	});
+	capture(&mut cart);
+
+	let image = read_image(&cart);
+	let tile_row = |tile: usize| &image[tile * 16..tile * 16 + 2];
+	assert_eq!(tile_row(0), [0xFF, 0xFF]);
+	assert_eq!(tile_row(15), [0x00, 0x00]);
+}
+
+#[test]
+fn camera_test_pattern_changes_between_captures() {
+	let mut cart = camera_cartridge();
+	cart.set_image_provider(TestPattern::default());
+	capture(&mut cart);
+	let first = read_image(&cart);
+	capture(&mut cart);
+	assert_ne!(first, read_image(&cart));
+}
@@ -0,0 +1,130 @@
+use crate::{
+	cartridge::{
+		header::{RomFingerprint, NINTENDO_LOGO},
+		Cartridge,
+	},
+	test::util::rom_loader::blank_rom,
+	util::{crc32::crc32, sha1::sha1},
+};
+
+// Blank rom with a valid logo and checksums
+fn valid_rom(cartridge_type: u8) -> Vec<u8> {
+	let mut rom = blank_rom(cartridge_type, 1, 3);
+	rom[0x0104..0x0134].copy_from_slice(&NINTENDO_LOGO);
+	rom[0x0144..0x0146].copy_from_slice(b"01");
+	rom[0x014B] = 0x33;
+
+	rom[0x014D] = rom[0x0134..=0x014C]
+		.iter()
+		.fold(0u8, |sum, byte| sum.wrapping_sub(*byte).wrapping_sub(1));
+
+	let global = rom
+		.iter()
+		.fold(0u16, |sum, byte| sum.wrapping_add(*byte as u16));
+	rom[0x014E..0x0150].copy_from_slice(&global.to_be_bytes());
+	rom
+}
+
+#[test]
+fn integrity_checks() {
+	let info = Cartridge::try_new(&valid_rom(0x1B), None).unwrap().info;
+	assert!(info.logo_valid);
+	assert!(info.header_checksum_valid);
+	assert!(info.global_checksum_valid);
+
+	let mut rom = valid_rom(0x1B);
+	rom[0x4000] = 0x01;
+	let info = Cartridge::try_new(&rom, None).unwrap().info;
+	assert!(info.header_checksum_valid);
+	assert!(!info.global_checksum_valid);
+
+	let mut rom = valid_rom(0x1B);
+	rom[0x0104] = 0x00;
+	rom[0x0149] = 0x02;
+	let info = Cartridge::try_new(&rom, None).unwrap().info;
+	assert!(!info.logo_valid);
+	assert!(!info.header_checksum_valid);
+}
+
+#[test]
+fn hardware_flags() {
+	let info = |cartridge_type| {
+		Cartridge::try_new(&valid_rom(cartridge_type), None)
+			.unwrap()
+			.info
+	};
+
+	let mbc5 = info(0x19);
+	assert!(!mbc5.has_battery && !mbc5.has_rumble);
+
+	let rumble = info(0x1E);
+	assert!(rumble.has_battery && rumble.has_rumble && !rumble.has_rtc);
+
+	let rtc = info(0x10);
+	assert!(rtc.has_battery && rtc.has_rtc);
+	assert!(!info(0x13).has_rtc);
+
+	let mbc7 = info(0x22);
+	assert!(mbc7.has_sensor && mbc7.has_rumble && mbc7.has_battery);
+
+	let camera = info(0xFC);
+	assert!(camera.has_camera && camera.has_battery && !camera.has_sensor);
+}
+
+#[test]
+fn licensee_names() {
+	let info = Cartridge::try_new(&valid_rom(0x00), None).unwrap().info;
+	assert_eq!(
+		info.licensee.as_deref(),
+		Some("Nintendo Research & Development 1")
+	);
+
+	let mut rom = valid_rom(0x00);
+	rom[0x014B] = 0x01;
+	let info = Cartridge::try_new(&rom, None).unwrap().info;
+	assert_eq!(info.licensee.as_deref(), Some("Nintendo"));
+
+	rom[0x014B] = 0x02;
+	let info = Cartridge::try_new(&rom, None).unwrap().info;
+	assert_eq!(info.licensee, None);
+}
+
+#[test]
+fn sha1_test_vectors() {
+	let hex = |digest: [u8; 20]| {
+		digest
+			.iter()
+			.map(|byte| format!("{byte:02x}"))
+			.collect::<String>()
+	};
+
+	assert_eq!(hex(sha1(b"")), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+	assert_eq!(
+		hex(sha1(b"abc")),
+		"a9993e364706816aba3e25717850c26c9cd0d89d"
+	);
+	// Padding spills into a second block
+	assert_eq!(
+		hex(sha1(
+			b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+		)),
+		"84983e441c3bd26ebaae4aa1f95129e5e54670f1"
+	);
+}
+
+#[test]
+fn rom_fingerprint() {
+	let rom = valid_rom(0x1B);
+	let info = Cartridge::try_new(&rom, None).unwrap().info;
+	assert_eq!(info.fingerprint.crc32, crc32(&rom));
+	assert_eq!(info.fingerprint.sha1, sha1(&rom));
+	assert_eq!(info.fingerprint, RomFingerprint::new(&rom));
+	assert_eq!(info.fingerprint.to_string().len(), 40);
+
+	// A change outside the header changes the fingerprint, but not the header checksum
+	let mut revision = rom.clone();
+	revision[0x4000] = 0x01;
+	let other = Cartridge::try_new(&revision, None).unwrap().info;
+	assert_eq!(other.header_checksum, info.header_checksum);
+	assert_ne!(other.fingerprint, info.fingerprint);
+}
@@ -0,0 +1,171 @@
+use sm83::memory_mapper::MemoryMapper;
+
+use crate::{
+	cheats::{Cheat, CheatCode, CheatParseError, Cheats, GameGenieCode, GameSharkCode},
+	test::{boot::cgb_test_instance, util::rom_loader::blank_rom},
+	Gameboy,
+};
+
+// One frame is 70224 t-states
+const FRAME_M_CYCLES: u32 = 17556;
+
+fn gameboy() -> Gameboy {
+	let mut rom = blank_rom(0x00, 0, 0);
+	rom[0x4A17] = 0xC8;
+	rom[0x1234] = 0x11;
+
+	let mut gameboy = cgb_test_instance();
+	gameboy.load_rom(&rom, None).unwrap();
+	gameboy
+}
+
+#[test]
+fn game_genie_parse() {
+	let code = GameGenieCode::parse("00A-17B-C49").unwrap();
+	assert_eq!(code.address, 0x4A17);
+	assert_eq!(code.value, 0x00);
+	assert_eq!(code.compare, Some(0xC8));
+
+	let code = GameGenieCode::parse("3E0-6CF").unwrap();
+	assert_eq!(code.address, 0x006C);
+	assert_eq!(code.value, 0x3E);
+	assert_eq!(code.compare, None);
+}
+
+#[test]
+fn game_shark_parse() {
+	let code = GameSharkCode::parse("914200D0").unwrap();
+	assert_eq!(code.bank, 0x91);
+	assert_eq!(code.value, 0x42);
+	assert_eq!(code.address, 0xD000);
+	assert_eq!(code.work_ram_bank(), Some(1));
+
+	assert!(matches!(
+		CheatCode::parse("010F00C1"),
+		Ok(CheatCode::GameShark(_))
+	));
+}
+
+#[test]
+fn invalid_codes() {
+	assert_eq!(
+		CheatCode::parse("00A-17B-C"),
+		Err(CheatParseError::InvalidLength(7))
+	);
+	assert_eq!(
+		CheatCode::parse("00A-17X"),
+		Err(CheatParseError::InvalidCharacter('X'))
+	);
+	// Digit F is inverted, 7 maps to address 0x8000
+	assert_eq!(
+		CheatCode::parse("000-007"),
+		Err(CheatParseError::InvalidAddress(0x8000))
+	);
+	// GameShark codes can't write the MBC or IO registers
+	assert_eq!(
+		CheatCode::parse("01010020"),
+		Err(CheatParseError::InvalidAddress(0x2000))
+	);
+	assert_eq!(
+		CheatCode::parse("01C046FF"),
+		Err(CheatParseError::InvalidAddress(0xFF46))
+	);
+	assert_eq!(
+		Cheat::parse("Empty", " + ").err(),
+		Some(CheatParseError::Empty)
+	);
+}
+
+#[test]
+fn game_genie_substitutes_rom_reads() {
+	let mut gameboy = gameboy();
+	let index = gameboy.add_cheat(Cheat::parse("Lives", "FF2-34E").unwrap());
+	assert_eq!(gameboy.read(0x1234), 0xFF);
+	assert_eq!(gameboy.read(0x1235), 0x00);
+
+	gameboy.set_cheat_enabled(index, false);
+	assert_eq!(gameboy.read(0x1234), 0x11);
+
+	gameboy.set_cheat_enabled(index, true);
+	gameboy.remove_cheat(index);
+	assert_eq!(gameboy.read(0x1234), 0x11);
+}
+
+#[test]
+fn game_genie_compare() {
+	let mut gameboy = gameboy();
+	gameboy.add_cheat(Cheat::parse("Matching", "00A-17B-C49").unwrap());
+	assert_eq!(gameboy.read(0x4A17), 0x00);
+
+	let mut gameboy = self::gameboy();
+	gameboy.add_cheat(Cheat::parse("Other bank", "002-34E-C49").unwrap());
+	assert_eq!(gameboy.read(0x1234), 0x11);
+}
+
+#[test]
+fn game_shark_writes_at_vblank() {
+	let mut gameboy = gameboy();
+	gameboy.add_cheat(Cheat::parse("Health", "014200C1+019902D0").unwrap());
+
+	gameboy.write(0xC100, 0x00);
+	gameboy.tick_m_cycles(FRAME_M_CYCLES);
+	assert_eq!(gameboy.read(0xC100), 0x42);
+
+	// Codes are reapplied every frame
+	gameboy.write(0xC100, 0x00);
+	gameboy.tick_m_cycles(FRAME_M_CYCLES);
+	assert_eq!(gameboy.read(0xC100), 0x42);
+	assert_eq!(gameboy.read(0xD002), 0x99);
+}
+
+#[test]
+fn game_shark_banked_work_ram() {
+	let mut gameboy = gameboy();
+	gameboy.add_cheat(Cheat::parse("Banked", "925500D0").unwrap());
+	gameboy.write(0xFF70, 0x01);
+	gameboy.tick_m_cycles(FRAME_M_CYCLES);
+
+	// The selected bank is restored after the write
+	assert_eq!(gameboy.get_wram_bank(), 1);
+	assert_ne!(gameboy.read(0xD000), 0x55);
+
+	gameboy.write(0xFF70, 0x02);
+	assert_eq!(gameboy.read(0xD000), 0x55);
+}
+
+#[test]
+fn game_shark_external_ram_bank() {
+	// MBC1 with 4 RAM banks
+	let mut gameboy = cgb_test_instance();
+	gameboy.load_rom(&blank_rom(0x03, 0, 3), None).unwrap();
+	gameboy.add_cheat(Cheat::parse("Banked", "02770CA0").unwrap());
+	gameboy.tick_m_cycles(FRAME_M_CYCLES);
+
+	let cart = gameboy.cartridge_state.as_mut().unwrap();
+	assert_eq!(cart.data.ram_banks[2][0x0C], 0x77);
+	assert_eq!(cart.data.ram_banks[0][0x0C], 0x00);
+	assert!(cart.is_save_dirty());
+
+	// The write bypasses the MBC, RAM stays disabled and bank 0 mapped
+	assert_eq!(gameboy.read(0xA00C), 0xFF);
+	gameboy.write(0x0000, 0x0A);
+	assert_eq!(gameboy.read(0xA00C), 0x00);
+}
+
+#[test]
+fn cheats_are_serialized() {
+	let mut gameboy = gameboy();
+	gameboy.add_cheat(Cheat::parse("Lives", "FF2-34E").unwrap());
+	let index = gameboy.add_cheat(Cheat::parse("Health", "014200C1").unwrap());
+	gameboy.set_cheat_enabled(index, false);
+
+	let json = serde_json::to_string(gameboy.cheats()).unwrap();
+	let cheats: Cheats = serde_json::from_str(&json).unwrap();
+	let restored: Vec<_> = cheats.iter().map(|cheat| cheat.enabled).collect();
+	assert_eq!(restored, vec![true, false]);
+
+	// Cheats are part of the serialized emulator state
+	let state = serde_json::to_string(&gameboy).unwrap();
+	let restored: Gameboy = serde_json::from_str(&state).unwrap();
+	assert_eq!(restored.cheats().iter().count(), 2);
+}
@@ -0,0 +1,129 @@
+use std::{
+	env, fs,
+	path::PathBuf,
+	sync::atomic::{AtomicUsize, Ordering},
+	time::Duration,
+};
+
+use sm83::memory_mapper::MemoryMapper;
+
+use crate::{
+	save_state::{
+		file_save_manager::FileSaveManager, SaveError, SaveManager, SaveState, SAVE_SLOTS,
+	},
+	test::{boot::cgb_test_instance, util::rom_loader::blank_rom},
+	Gameboy,
+};
+
+// Empty directory, unique to each test
+fn save_directory() -> PathBuf {
+	static COUNTER: AtomicUsize = AtomicUsize::new(0);
+	let directory = env::temp_dir().join(format!(
+		"gbc-saves-{}-{}",
+		std::process::id(),
+		COUNTER.fetch_add(1, Ordering::Relaxed)
+	));
+	_ = fs::remove_dir_all(&directory);
+	directory
+}
+
+fn gameboy(rom_revision: u8) -> Gameboy {
+	let mut rom = blank_rom(0x1B, 0, 2);
+	rom[0x4000] = rom_revision;
+
+	let mut gameboy = cgb_test_instance();
+	gameboy.load_rom(&rom, None).unwrap();
+	gameboy.write(0x0000, 0x0A);
+	gameboy
+}
+
+fn save_manager(directory: &PathBuf, gameboy: &Gameboy) -> FileSaveManager {
+	FileSaveManager::new(directory, &gameboy.cartridge_state.as_ref().unwrap().info)
+}
+
+#[test]
+fn save_state_slots() {
+	let directory = save_directory();
+	let mut gameboy = gameboy(0);
+	let mut manager = save_manager(&directory, &gameboy);
+	assert_eq!(manager.get_save_states(), vec![None; SAVE_SLOTS]);
+
+	gameboy.write(0xC000, 0x42);
+	let save = SaveState::try_from(&gameboy).unwrap();
+	manager.save_save_state(save, 3).unwrap();
+
+	let states = manager.get_save_states();
+	assert!(states[3].as_ref().unwrap().game_title.starts_with("TEST"));
+	assert_eq!(states.iter().flatten().count(), 1);
+
+	// A new manager reads the index and slots from disk
+	let manager = save_manager(&directory, &gameboy);
+	assert_eq!(manager.get_save_states(), states);
+	let save = manager.load_save_state(3).unwrap();
+	gameboy.write(0xC000, 0x00);
+	let restored = gameboy.try_load_save_state(&save).unwrap();
+	assert_eq!(restored.read(0xC000), 0x42);
+
+	assert!(matches!(
+		manager.load_save_state(4),
+		Err(SaveError::NoSource)
+	));
+	assert!(matches!(
+		manager.load_save_state(SAVE_SLOTS),
+		Err(SaveError::IndexOutOfBounds(_))
+	));
+
+	fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn slots_are_separated_by_rom() {
+	let directory = save_directory();
+	let gameboy = gameboy(0);
+	let mut manager = save_manager(&directory, &gameboy);
+	let save = SaveState::try_from(&gameboy).unwrap();
+	manager.save_save_state(save, 0).unwrap();
+
+	// Another revision of the rom has its own slots
+	let other = save_manager(&directory, &self::gameboy(1));
+	assert_ne!(other.directory(), manager.directory());
+	assert_eq!(other.get_save_states(), vec![None; SAVE_SLOTS]);
+
+	fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn battery_flush() {
+	let directory = save_directory();
+	let mut gameboy = gameboy(0);
+	let mut manager = save_manager(&directory, &gameboy);
+
+	// Nothing is written until battery backed memory changes
+	assert!(!manager.flush_battery(&mut gameboy).unwrap());
+	gameboy.write(0xA010, 0x42);
+	assert!(manager.flush_battery(&mut gameboy).unwrap());
+	assert!(!manager.flush_battery(&mut gameboy).unwrap());
+
+	let mut new_gameboy = self::gameboy(0);
+	assert!(manager.load_battery(&mut new_gameboy).unwrap());
+	assert_eq!(new_gameboy.read(0xA010), 0x42);
+
+	fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn battery_flush_periodically() {
+	let directory = save_directory();
+	let mut gameboy = gameboy(0);
+	let mut manager =
+		save_manager(&directory, &gameboy).with_flush_interval(Duration::from_secs(3600));
+	assert!(!manager.load_battery(&mut gameboy).unwrap());
+
+	gameboy.write(0xA010, 0x42);
+	assert!(!manager.flush_battery_periodically(&mut gameboy).unwrap());
+
+	let mut manager = manager.with_flush_interval(Duration::ZERO);
+	assert!(manager.flush_battery_periodically(&mut gameboy).unwrap());
+
+	fs::remove_dir_all(directory).unwrap();
+}
@@ -0,0 +1,88 @@
+use sm83::memory_mapper::MemoryMapper;
+
+use crate::{cartridge::Cartridge, test::util::rom_loader::blank_rom};
+
+const T_STATES_PER_MINUTE: u64 = 4194304 * 60;
+
+fn numbered_rom(cartridge_type: u8) -> Vec<u8> {
+	let mut rom = blank_rom(cartridge_type, 2, 3);
+	for (bank, data) in rom.chunks_mut(0x4000).enumerate().skip(1) {
+		data[0] = bank as u8;
+	}
+	rom
+}
+
+fn huc3_command(cart: &mut Cartridge, command: u8) -> u8 {
+	cart.write(0x0000, 0x0B);
+	cart.write(0xA000, command);
+	cart.write(0x0000, 0x0D);
+	cart.write(0xA000, 0xFE);
+	cart.write(0x0000, 0x0C);
+	cart.read(0xA000) & 0x0F
+}
+
+#[test]
+fn huc1_banking_and_infrared() {
+	let mut cart = Cartridge::try_new(&numbered_rom(0xFF), None).unwrap();
+
+	cart.write(0x2000, 0x05);
+	assert_eq!(cart.read(0x4000), 5);
+
+	cart.write(0x4000, 0x02);
+	cart.write(0xA123, 0x42);
+	cart.write(0x4000, 0x00);
+	assert_ne!(cart.read(0xA123), 0x42);
+	cart.write(0x4000, 0x02);
+	assert_eq!(cart.read(0xA123), 0x42);
+
+	cart.write(0x0000, 0x0E);
+	assert_eq!(cart.read(0xA000), 0xC0);
+	cart.write(0x0000, 0x00);
+	assert_eq!(cart.read(0xA123), 0x42);
+}
+
+#[test]
+fn huc3_ram_write_protection() {
+	let mut cart = Cartridge::try_new(&numbered_rom(0xFE), None).unwrap();
+
+	cart.write(0x0000, 0x00);
+	cart.write(0xA000, 0x12);
+	assert_eq!(cart.read(0xA000), 0x00);
+
+	cart.write(0x0000, 0x0A);
+	cart.write(0xA000, 0x12);
+	assert_eq!(cart.read(0xA000), 0x12);
+}
+
+#[test]
+fn huc3_reads_clock_through_commands() {
+	let mut cart = Cartridge::try_new(&numbered_rom(0xFE), None).unwrap();
+	cart.sync_clock(0);
+	cart.sync_clock(T_STATES_PER_MINUTE * (60 * 24 * 2 + 0x123));
+
+	// Copy time to memory, then read it back from address 0
+	huc3_command(&mut cart, 0x60);
+	huc3_command(&mut cart, 0x40);
+	huc3_command(&mut cart, 0x50);
+
+	let minutes: Vec<u8> = (0..3).map(|_| huc3_command(&mut cart, 0x10)).collect();
+	let days: Vec<u8> = (0..3).map(|_| huc3_command(&mut cart, 0x10)).collect();
+
+	assert_eq!(minutes, [0x3, 0x2, 0x1]);
+	assert_eq!(days, [0x2, 0x0, 0x0]);
+}
+
+#[test]
+fn huc3_speaker_tone() {
+	let mut cart = Cartridge::try_new(&numbered_rom(0xFE), None).unwrap();
+
+	// Write 1 and 3 to addresses 0x26 and 0x27
+	huc3_command(&mut cart, 0x46);
+	huc3_command(&mut cart, 0x52);
+	huc3_command(&mut cart, 0x31);
+	huc3_command(&mut cart, 0x33);
+	huc3_command(&mut cart, 0x6E);
+
+	assert_eq!(cart.take_speaker_tone(), Some(3));
+	assert_eq!(cart.take_speaker_tone(), None);
+}
@@ -0,0 +1,110 @@
+use sm83::{
+	assembler::assemble,
+	memory_mapper::MemoryMapper,
+	registers::{Addressable, CPURegister16::PC, CPURegister8::A},
+	CPULock, SM83,
+};
+
+use crate::{
+	io_registers::{DIV, IE, IF},
+	save_state::SaveState,
+	test::boot::cgb_test_instance,
+	Gameboy,
+};
+
+fn load_program(source: &str) -> Gameboy {
+	let mut gameboy = cgb_test_instance();
+	let program = assemble(source, 0x0100).unwrap();
+	if let Some(cart) = &mut gameboy.cartridge_state {
+		for (i, byte) in program.bytes.iter().enumerate() {
+			cart.data.rom_banks[0][0x100 + i] = *byte;
+		}
+	}
+	gameboy
+}
+
+// Runs until the illegal opcode at $0102 locks up the CPU
+fn locked_gameboy() -> Gameboy {
+	let mut gameboy = load_program("ld a, 1\n db $D3\n inc a");
+	gameboy.step();
+	gameboy.step();
+	gameboy
+}
+
+#[test]
+fn illegal_opcode_locks_cpu() {
+	let gameboy = locked_gameboy();
+	assert_eq!(
+		gameboy.cpu_state.locked,
+		Some(CPULock {
+			pc: 0x0102,
+			opcode: 0xD3
+		})
+	);
+	assert_eq!(
+		gameboy.cpu_state.locked.unwrap().to_string(),
+		"CPU locked by illegal opcode $D3 at $0102"
+	);
+}
+
+#[test]
+fn illegal_opcode_after_halt_bug() {
+	let mut gameboy = load_program("halt\n db $D3");
+	gameboy.disable_interrupts();
+	gameboy.write(IE, 0x1F);
+	gameboy.write(IF, 0x1F);
+
+	gameboy.step();
+	assert!(gameboy.cpu_state.halt_bug);
+	gameboy.step();
+
+	// PC never moved past the opcode
+	assert_eq!(
+		gameboy.cpu_state.locked,
+		Some(CPULock {
+			pc: 0x0101,
+			opcode: 0xD3
+		})
+	);
+	assert_eq!(gameboy.cpu_state.read(PC), 0x0101);
+}
+
+#[test]
+fn system_keeps_running_while_locked() {
+	let mut gameboy = locked_gameboy();
+	let frame = gameboy.ppu.frame;
+	let div = gameboy.read(DIV);
+
+	for _ in 0..100_000 {
+		assert!(gameboy.step().is_none());
+	}
+
+	assert_eq!(gameboy.cpu_state.read(PC), 0x0103);
+	assert_eq!(gameboy.cpu_state.read(A), 1);
+	assert!(gameboy.ppu.frame > frame);
+	assert_ne!(gameboy.read(DIV), div);
+}
+
+#[test]
+fn interrupts_do_not_unlock() {
+	let mut gameboy = locked_gameboy();
+	gameboy.enable_interrupts();
+	gameboy.write(IE, 0x1F);
+	gameboy.write(IF, 0x1F);
+
+	for _ in 0..1000 {
+		gameboy.step();
+	}
+
+	assert!(gameboy.cpu_state.locked.is_some());
+	assert_eq!(gameboy.cpu_state.read(PC), 0x0103);
+}
+
+#[test]
+fn lock_is_saved() {
+	let gameboy = locked_gameboy();
+	let save = SaveState::try_from(&gameboy).unwrap();
+
+	let restored = gameboy.try_load_save_state(&save).unwrap();
+	assert_eq!(restored.cpu_state.locked, gameboy.cpu_state.locked);
+}
@@ -1,6 +1,6 @@
 use sm83::flags::cpu::{C, Z};
 
-use super::util::instruction_timing::expect_instr_timing;
+use super::util::instruction_timing::{expect_asm_timing, expect_instr_timing};
 
 #[test]
 fn individual_instruction_timing() {
@@ -258,3 +258,31 @@ fn individual_instruction_timing() {
 	expect_instr_timing("CP A,u8", &[254], 1, 2, 0);
 	expect_instr_timing("RST 38h", &[255], 1, 4, 0);
 }
+
+#[test]
+fn program_timing() {
+	let countdown = "
+	Start:
+		ld b, 3
+	.loop:
+		dec b
+		jr nz, .loop
+	";
+	expect_asm_timing("Countdown", countdown, 7, 13, 0);
+
+	let call = "
+		call Function
+		nop
+	Function:
+		ret
+	";
+	expect_asm_timing("CALL, RET", call, 2, 10, 0);
+
+	let conditional_call = "
+		call z, Function
+	Function:
+		ret z
+	";
+	expect_asm_timing("CALL Z, RET Z", conditional_call, 2, 5, 0);
+	expect_asm_timing("CALL Z, RET Z", conditional_call, 2, 11, Z);
+}
@@ -0,0 +1,53 @@
+use sm83::memory_mapper::MemoryMapper;
+
+use crate::{
+	cartridge::{header::NINTENDO_LOGO, Cartridge},
+	test::util::rom_loader::blank_rom,
+};
+
+fn numbered_rom(multicart: bool) -> Vec<u8> {
+	let mut rom = blank_rom(0x01, 5, 0);
+	for (bank, data) in rom.chunks_mut(0x4000).enumerate() {
+		data[0x1000] = bank as u8;
+		if bank % 0x10 == 0 && (bank == 0 || multicart) {
+			data[0x0104..0x0134].copy_from_slice(&NINTENDO_LOGO);
+		}
+	}
+	rom
+}
+
+#[test]
+fn mbc1m_simple_mode_banking() {
+	let mut cart = Cartridge::try_new(&numbered_rom(true), None).unwrap();
+
+	cart.write(0x4000, 0x01);
+	cart.write(0x2000, 0x03);
+	assert_eq!(cart.read(0x5000), 0x13);
+	assert_eq!(cart.read(0x1000), 0x00);
+
+	// Bit 4 of the rom bank register is ignored, but still counts for the zero check
+	cart.write(0x2000, 0x10);
+	assert_eq!(cart.read(0x5000), 0x10);
+	cart.write(0x2000, 0x00);
+	assert_eq!(cart.read(0x5000), 0x11);
+}
+
+#[test]
+fn mbc1m_complex_mode_banking() {
+	let mut cart = Cartridge::try_new(&numbered_rom(true), None).unwrap();
+
+	cart.write(0x6000, 0x01);
+	cart.write(0x4000, 0x02);
+	cart.write(0x2000, 0x05);
+	assert_eq!(cart.read(0x1000), 0x20);
+	assert_eq!(cart.read(0x5000), 0x25);
+}
+
+#[test]
+fn mbc1_without_logos_is_not_multicart() {
+	let mut cart = Cartridge::try_new(&numbered_rom(false), None).unwrap();
+
+	cart.write(0x4000, 0x01);
+	cart.write(0x2000, 0x03);
+	assert_eq!(cart.read(0x5000), 0x23);
+}
@@ -0,0 +1,113 @@
+use sm83::memory_mapper::MemoryMapper;
+
+use crate::{
+	cartridge::{rtc::RtcClock, Cartridge},
+	test::util::rom_loader::blank_rom,
+};
+
+const T_STATES_PER_SECOND: u64 = 4194304;
+
+fn rtc_cartridge() -> Cartridge {
+	let mut cart = Cartridge::try_new(&blank_rom(0x10, 0, 3), None).unwrap();
+	cart.sync_clock(0);
+	cart.write(0x0000, 0x0A);
+	cart
+}
+
+fn latch(cart: &mut Cartridge, t_states: u64) {
+	cart.sync_clock(t_states);
+	cart.write(0x6000, 0);
+	cart.write(0x6000, 1);
+}
+
+fn read_rtc(cart: &mut Cartridge, register: u8) -> u8 {
+	cart.write(0x4000, register);
+	cart.read(0xA000)
+}
+
+fn write_rtc(cart: &mut Cartridge, register: u8, value: u8) {
+	cart.write(0x4000, register);
+	cart.write(0xA000, value);
+}
+
+#[test]
+fn counts_emulated_time() {
+	let mut cart = rtc_cartridge();
+	latch(&mut cart, T_STATES_PER_SECOND * (60 * 60 * 25 + 61));
+
+	assert_eq!(read_rtc(&mut cart, 0x08), 1);
+	assert_eq!(read_rtc(&mut cart, 0x09), 1);
+	assert_eq!(read_rtc(&mut cart, 0x0A), 1);
+	assert_eq!(read_rtc(&mut cart, 0x0B), 1);
+	assert_eq!(read_rtc(&mut cart, 0x0C), 0);
+}
+
+#[test]
+fn reads_return_latched_values() {
+	let mut cart = rtc_cartridge();
+	latch(&mut cart, T_STATES_PER_SECOND * 5);
+	cart.sync_clock(T_STATES_PER_SECOND * 10);
+
+	assert_eq!(read_rtc(&mut cart, 0x08), 5);
+
+	// Writing 1 again without a preceding 0 does not latch
+	cart.write(0x6000, 1);
+	assert_eq!(read_rtc(&mut cart, 0x08), 5);
+
+	latch(&mut cart, T_STATES_PER_SECOND * 10);
+	assert_eq!(read_rtc(&mut cart, 0x08), 10);
+}
+
+#[test]
+fn halt_stops_the_clock() {
+	let mut cart = rtc_cartridge();
+	write_rtc(&mut cart, 0x0C, 0b0100_0000);
+	latch(&mut cart, T_STATES_PER_SECOND * 100);
+
+	assert_eq!(read_rtc(&mut cart, 0x08), 0);
+	assert_eq!(read_rtc(&mut cart, 0x0C), 0b0100_0000);
+}
+
+#[test]
+fn day_counter_overflow_sets_carry() {
+	let mut cart = rtc_cartridge();
+	write_rtc(&mut cart, 0x08, 59);
+	write_rtc(&mut cart, 0x09, 59);
+	write_rtc(&mut cart, 0x0A, 23);
+	write_rtc(&mut cart, 0x0B, 0xFF);
+	write_rtc(&mut cart, 0x0C, 0x01);
+	latch(&mut cart, T_STATES_PER_SECOND);
+
+	assert_eq!(read_rtc(&mut cart, 0x0A), 0);
+	assert_eq!(read_rtc(&mut cart, 0x0B), 0);
+	assert_eq!(read_rtc(&mut cart, 0x0C), 0b1000_0000);
+}
+
+#[test]
+fn disabled_ram_blocks_rtc_access() {
+	let mut cart = rtc_cartridge();
+	cart.write(0x0000, 0x00);
+	write_rtc(&mut cart, 0x08, 30);
+	assert_eq!(read_rtc(&mut cart, 0x08), 0xFF);
+
+	cart.write(0x0000, 0x0A);
+	assert_eq!(read_rtc(&mut cart, 0x08), 0);
+}
+
+#[test]
+fn save_footer_round_trip() {
+	let mut cart = rtc_cartridge();
+	latch(&mut cart, T_STATES_PER_SECOND * (60 * 60 * 24 * 300 + 42));
+	let footer = cart.rtc().unwrap().to_save_footer();
+
+	let mut restored = rtc_cartridge();
+	restored.set_rtc_clock(RtcClock::Emulated);
+	assert!(restored.rtc_mut().unwrap().load_save_footer(&footer));
+
+	assert_eq!(
+		cart.rtc().unwrap().registers(),
+		restored.rtc().unwrap().registers()
+	);
+	assert_eq!(read_rtc(&mut restored, 0x08), 42);
+	assert_eq!(read_rtc(&mut restored, 0x0C), 0x01);
+}
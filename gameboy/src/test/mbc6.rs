@@ -0,0 +1,106 @@
+use sm83::memory_mapper::MemoryMapper;
+
+use crate::{
+	cartridge::{memory_bank_controller::Mbc, Cartridge},
+	test::util::rom_loader::blank_rom,
+};
+
+fn mbc6_cartridge() -> Cartridge {
+	let mut rom = blank_rom(0x20, 3, 3);
+	for (bank, data) in rom.chunks_mut(0x2000).enumerate() {
+		data[0x100] = bank as u8;
+	}
+	Cartridge::try_new(&rom, None).unwrap()
+}
+
+fn map_flash(cart: &mut Cartridge, bank_a: u8, bank_b: u8) {
+	cart.write(0x0C00, 0x01);
+	cart.write(0x1000, 0x01);
+	cart.write(0x2000, bank_a);
+	cart.write(0x2800, 0x08);
+	cart.write(0x3000, bank_b);
+	cart.write(0x3800, 0x08);
+}
+
+// Flash bank 2 is mapped at 0x4000 and bank 1 at 0x6000
+fn flash_command(cart: &mut Cartridge, command: u8) {
+	cart.write(0x5555, 0xAA);
+	cart.write(0x6AAA, 0x55);
+	cart.write(0x5555, command);
+}
+
+fn flash_data(cart: &Cartridge) -> &[u8] {
+	match &cart.mbc {
+		Mbc::MBC6(state) => &state.flash.data,
+		_ => unreachable!(),
+	}
+}
+
+#[test]
+fn mbc6_independent_rom_windows() {
+	let mut cart = mbc6_cartridge();
+	cart.write(0x2000, 0x05);
+	cart.write(0x3000, 0x0C);
+	assert_eq!(cart.read(0x4100), 5);
+	assert_eq!(cart.read(0x6100), 12);
+}
+
+#[test]
+fn mbc6_split_ram_banks() {
+	let mut cart = mbc6_cartridge();
+	cart.write(0x0000, 0x0A);
+	cart.write(0x0400, 0x03);
+	cart.write(0x0800, 0x03);
+	cart.write(0xA010, 0x42);
+	assert_eq!(cart.read(0xB010), 0x42);
+
+	cart.write(0x0800, 0x04);
+	assert_ne!(cart.read(0xB010), 0x42);
+	cart.write(0xB010, 0x24);
+	assert_eq!(cart.read(0xA010), 0x42);
+}
+
+#[test]
+fn mbc6_flash_id_mode() {
+	let mut cart = mbc6_cartridge();
+	map_flash(&mut cart, 2, 1);
+	flash_command(&mut cart, 0x90);
+	assert_eq!(cart.read(0x4000), 0xC2);
+	assert_eq!(cart.read(0x4001), 0x81);
+
+	cart.write(0x4000, 0xF0);
+	assert_eq!(cart.read(0x4000), 0xFF);
+}
+
+#[test]
+fn mbc6_flash_program_and_erase() {
+	let mut cart = mbc6_cartridge();
+	map_flash(&mut cart, 2, 1);
+
+	flash_command(&mut cart, 0xA0);
+	cart.write(0x4123, 0x5A);
+	assert_eq!(cart.read(0x4123), 0x5A);
+
+	// Programming can't set bits that are already cleared
+	flash_command(&mut cart, 0xA0);
+	cart.write(0x4123, 0xA5);
+	assert_eq!(cart.read(0x4123), 0x00);
+	assert_eq!(flash_data(&cart)[0x4123], 0x00);
+
+	flash_command(&mut cart, 0x80);
+	cart.write(0x5555, 0xAA);
+	cart.write(0x6AAA, 0x55);
+	cart.write(0x4000, 0x30);
+	assert_eq!(cart.read(0x4123), 0xFF);
+}
+
+#[test]
+fn mbc6_flash_write_protect() {
+	let mut cart = mbc6_cartridge();
+	map_flash(&mut cart, 2, 1);
+	cart.write(0x1000, 0x00);
+
+	flash_command(&mut cart, 0xA0);
+	cart.write(0x4123, 0x5A);
+	assert_eq!(cart.read(0x4123), 0xFF);
+}
@@ -0,0 +1,103 @@
+use sm83::memory_mapper::MemoryMapper;
+
+use crate::{cartridge::Cartridge, joypad::TiltState, test::util::rom_loader::blank_rom};
+
+const EEPROM: u16 = 0xA080;
+const CS: u8 = 0x80;
+const CLK: u8 = 0x40;
+const DI: u8 = 0x02;
+
+fn mbc7_cartridge() -> Cartridge {
+	let mut cart = Cartridge::try_new(&blank_rom(0x22, 1, 0), None).unwrap();
+	cart.write(0x0000, 0x0A);
+	cart.write(0x4000, 0x40);
+	cart
+}
+
+// Clocks a single bit into the EEPROM and returns DO after the rising edge
+fn clock_bit(cart: &mut Cartridge, bit: bool) -> bool {
+	let di = if bit { DI } else { 0 };
+	cart.write(EEPROM, CS | di);
+	cart.write(EEPROM, CS | CLK | di);
+	cart.read(EEPROM) & 1 != 0
+}
+
+fn send_bits(cart: &mut Cartridge, value: u16, count: u8) {
+	for i in (0..count).rev() {
+		clock_bit(cart, (value >> i) & 1 != 0);
+	}
+}
+
+fn send_command(cart: &mut Cartridge, command: u16) {
+	cart.write(EEPROM, 0);
+	cart.write(EEPROM, CS);
+	// Start bit, followed by the opcode and address
+	send_bits(cart, 1, 1);
+	send_bits(cart, command, 10);
+}
+
+fn read_word(cart: &mut Cartridge, address: u8) -> u16 {
+	send_command(cart, 0b10_0000_0000 | address as u16);
+	assert_eq!(cart.read(EEPROM) & 1, 0, "Expected dummy zero bit");
+
+	(0..16).fold(0, |word, _| (word << 1) | clock_bit(cart, false) as u16)
+}
+
+#[test]
+fn eeprom_write_requires_enable() {
+	let mut cart = mbc7_cartridge();
+
+	send_command(&mut cart, 0b01_0000_0011);
+	send_bits(&mut cart, 0x1234, 16);
+	assert_eq!(read_word(&mut cart, 3), 0xFFFF);
+
+	// EWEN
+	send_command(&mut cart, 0b00_1100_0000);
+	send_command(&mut cart, 0b01_0000_0011);
+	send_bits(&mut cart, 0x1234, 16);
+	assert_eq!(read_word(&mut cart, 3), 0x1234);
+
+	// ERASE
+	send_command(&mut cart, 0b11_0000_0011);
+	assert_eq!(read_word(&mut cart, 3), 0xFFFF);
+}
+
+#[test]
+fn eeprom_write_all() {
+	let mut cart = mbc7_cartridge();
+	send_command(&mut cart, 0b00_1100_0000);
+	send_command(&mut cart, 0b00_0100_0000);
+	send_bits(&mut cart, 0xBEEF, 16);
+
+	assert_eq!(read_word(&mut cart, 0), 0xBEEF);
+	assert_eq!(read_word(&mut cart, 127), 0xBEEF);
+}
+
+#[test]
+fn accelerometer_latch() {
+	let mut cart = mbc7_cartridge();
+	cart.set_tilt(&TiltState { x: 1.0, y: -0.5 });
+
+	// Latching without erasing first has no effect
+	cart.write(0xA010, 0xAA);
+	assert_eq!(cart.read(0xA020), 0x00);
+	assert_eq!(cart.read(0xA030), 0x80);
+
+	cart.write(0xA000, 0x55);
+	cart.write(0xA010, 0xAA);
+
+	let x = u16::from_le_bytes([cart.read(0xA020), cart.read(0xA030)]);
+	let y = u16::from_le_bytes([cart.read(0xA040), cart.read(0xA050)]);
+	assert_eq!(x, 0x81D0 + 0x70);
+	assert_eq!(y, 0x81D0 - 0x38);
+}
+
+#[test]
+fn registers_require_both_enables() {
+	let mut cart = mbc7_cartridge();
+	cart.write(0x4000, 0x00);
+	assert_eq!(cart.read(0xA060), 0xFF);
+
+	cart.write(0x4000, 0x40);
+	assert_eq!(cart.read(0xA060), 0x00);
+}
@@ -0,0 +1,86 @@
+use sm83::memory_mapper::MemoryMapper;
+
+use crate::{
+	cartridge::{memory_bank_controller::Mbc, Cartridge},
+	test::util::rom_loader::blank_rom,
+};
+
+// 256KiB multicart, the menu header sits at the start of the last 32KiB
+fn mmm01_rom() -> Vec<u8> {
+	let mut rom = blank_rom(0x01, 3, 3);
+	for (bank, data) in rom.chunks_mut(0x4000).enumerate() {
+		data[0x1000] = bank as u8;
+	}
+
+	let menu = rom.len() - 0x8000;
+	rom[menu + 0x0134..menu + 0x0138].copy_from_slice(b"MENU");
+	rom[menu + 0x0147] = 0x0D;
+	rom[menu + 0x0148] = 3;
+	rom[menu + 0x0149] = 3;
+	rom[menu + 0x014D] = rom[menu + 0x0134..=menu + 0x014C]
+		.iter()
+		.fold(0u8, |sum, byte| sum.wrapping_sub(*byte).wrapping_sub(1));
+	rom
+}
+
+#[test]
+fn mmm01_header_at_end_of_rom() {
+	let cart = Cartridge::try_new(&mmm01_rom(), None).unwrap();
+	assert!(cart.info.title.starts_with("MENU"));
+	assert_eq!(cart.info.rom_banks, 16);
+	assert!(matches!(cart.mbc, Mbc::MMM01(_)));
+}
+
+#[test]
+fn mmm01_boots_from_last_banks() {
+	let mut cart = Cartridge::try_new(&mmm01_rom(), None).unwrap();
+	assert_eq!(cart.read(0x1000), 14);
+	assert_eq!(cart.read(0x5000), 15);
+
+	// Bank selection is ignored until the game is mapped
+	cart.write(0x2000, 0x03);
+	assert_eq!(cart.read(0x1000), 14);
+}
+
+#[test]
+fn mmm01_maps_game_and_locks_registers() {
+	let mut cart = Cartridge::try_new(&mmm01_rom(), None).unwrap();
+
+	// Select the game starting at bank 8, 8 banks in size
+	cart.write(0x2000, 0x08);
+	// Lock bit 3 of the rom bank so the game only sees its own 8 banks
+	cart.write(0x6000, 0b0001_0000);
+	cart.write(0x0000, 0x40);
+
+	assert_eq!(cart.read(0x1000), 8);
+	assert_eq!(cart.read(0x5000), 9);
+
+	cart.write(0x2000, 0x03);
+	assert_eq!(cart.read(0x5000), 11);
+
+	// Bit 3 can no longer be cleared by the game
+	cart.write(0x2000, 0x00);
+	assert_eq!(cart.read(0x5000), 9);
+	assert_eq!(cart.read(0x1000), 8);
+
+	// Writing the lock bit again does not unmap the game
+	cart.write(0x0000, 0x00);
+	assert_eq!(cart.read(0x1000), 8);
+}
+
+#[test]
+fn mmm01_ram_banking() {
+	let mut cart = Cartridge::try_new(&mmm01_rom(), None).unwrap();
+	cart.write(0x0000, 0x4A);
+
+	cart.write(0x4000, 0x01);
+	cart.write(0xA000, 0x11);
+	cart.write(0x4000, 0x02);
+	cart.write(0xA000, 0x22);
+
+	cart.write(0x4000, 0x01);
+	assert_eq!(cart.read(0xA000), 0x11);
+
+	cart.write(0x0000, 0x00);
+	assert_eq!(cart.read(0xA000), 0xFF);
+}
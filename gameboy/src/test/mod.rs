@@ -2,9 +2,27 @@ pub mod boot;
 pub mod util;
 
 mod age;
+mod battery_save;
+mod bess;
 mod blarggs;
+mod camera;
+mod cartridge_info;
+mod cheats;
+mod file_save_manager;
 mod gambatte;
+mod huc;
+mod illegal_opcode;
 mod instr_timing;
+mod mbc1m;
+mod mbc3_rtc;
+mod mbc6;
+mod mbc7;
 mod microtest;
+mod mmm01;
 mod mooneye;
+mod patch;
+mod rewind;
+mod rom_parsing;
 mod same_suite;
+mod save_state;
+mod thumbnail;
@@ -0,0 +1,161 @@
+use crate::{
+	patch::{apply_patch, PatchError},
+	util::crc32::crc32,
+};
+
+fn varint(mut value: usize) -> Vec<u8> {
+	let mut bytes = vec![];
+	loop {
+		let byte = (value & 0x7F) as u8;
+		value >>= 7;
+		if value == 0 {
+			bytes.push(byte | 0x80);
+			return bytes;
+		}
+		bytes.push(byte);
+		value -= 1;
+	}
+}
+
+fn with_footer(mut patch: Vec<u8>, source: &[u8], target: &[u8]) -> Vec<u8> {
+	patch.extend(crc32(source).to_le_bytes());
+	patch.extend(crc32(target).to_le_bytes());
+	patch.extend(crc32(&patch).to_le_bytes());
+	patch
+}
+
+fn source_rom() -> Vec<u8> {
+	(0..0x400).map(|i| i as u8).collect()
+}
+
+#[test]
+fn crc32_check_value() {
+	assert_eq!(crc32(b"123456789"), 0xCBF43926);
+}
+
+#[test]
+fn ips_records() {
+	let rom = source_rom();
+	let mut patch = b"PATCH".to_vec();
+	// Plain record
+	patch.extend([0x00, 0x00, 0x10, 0x00, 0x02, 0xAA, 0xBB]);
+	// Run length encoded record, extending the rom
+	patch.extend([0x00, 0x03, 0xFE, 0x00, 0x00, 0x00, 0x04, 0xCC]);
+	patch.extend(b"EOF");
+
+	let patched = apply_patch(&rom, &patch).unwrap();
+	assert_eq!(patched.len(), 0x402);
+	assert_eq!(&patched[0x10..0x12], [0xAA, 0xBB]);
+	assert_eq!(&patched[0x3FE..], [0xCC; 4]);
+	assert_eq!(patched[0x12], 0x12);
+
+	// Truncation extension
+	patch.extend([0x00, 0x01, 0x00]);
+	assert_eq!(apply_patch(&rom, &patch).unwrap().len(), 0x100);
+}
+
+#[test]
+fn ips_unexpected_end() {
+	let patch = b"PATCH\x00\x00\x10\x00\x04\xAA".to_vec();
+	assert_eq!(
+		apply_patch(&source_rom(), &patch),
+		Err(PatchError::UnexpectedEnd)
+	);
+}
+
+#[test]
+fn ups_patch() {
+	let rom = source_rom();
+	let mut target = rom.clone();
+	target[0x20] = 0xFF;
+	target[0x21] = 0xEE;
+	target.extend([0x11, 0x22]);
+
+	let mut patch = b"UPS1".to_vec();
+	patch.extend(varint(rom.len()));
+	patch.extend(varint(target.len()));
+	patch.extend(varint(0x20));
+	patch.extend([0xFF ^ 0x20, 0xEE ^ 0x21, 0x00]);
+	patch.extend(varint(0x400 - 0x23));
+	patch.extend([0x11, 0x22, 0x00]);
+	let patch = with_footer(patch, &rom, &target);
+
+	assert_eq!(apply_patch(&rom, &patch).unwrap(), target);
+
+	// Wrong source rom
+	let mut other = rom.clone();
+	other[0] = 0xFF;
+	assert!(matches!(
+		apply_patch(&other, &patch),
+		Err(PatchError::SourceChecksum { .. })
+	));
+}
+
+#[test]
+fn bps_patch() {
+	let rom = source_rom();
+	let mut target = rom[..0x100].to_vec();
+	target.extend(b"HELLOLOLOLO");
+	target.extend_from_slice(&rom[0x200..0x210]);
+
+	let mut patch = b"BPS1".to_vec();
+	patch.extend(varint(rom.len()));
+	patch.extend(varint(target.len()));
+	patch.extend(varint(4));
+	patch.extend(b"meta");
+
+	// Source read
+	patch.extend(varint((0x100 - 1) << 2));
+	// Target read
+	patch.extend(varint(((5 - 1) << 2) | 1));
+	patch.extend(b"HELLO");
+	// Target copy from "LO" in "HELLO", overlapping with the output
+	patch.extend(varint(((6 - 1) << 2) | 3));
+	patch.extend(varint(0x103 << 1));
+	// Source copy, relative offset +0x200
+	patch.extend(varint(((0x10 - 1) << 2) | 2));
+	patch.extend(varint(0x200 << 1));
+	let patch = with_footer(patch, &rom, &target);
+
+	assert_eq!(apply_patch(&rom, &patch).unwrap(), target);
+
+	// Corrupted patch
+	let mut corrupted = patch.clone();
+	corrupted[10] ^= 0xFF;
+	assert!(matches!(
+		apply_patch(&rom, &corrupted),
+		Err(PatchError::PatchChecksum { .. })
+	));
+}
+
+#[test]
+fn unknown_format() {
+	assert_eq!(
+		apply_patch(&source_rom(), b"NOTAPATCH"),
+		Err(PatchError::UnknownFormat)
+	);
+}
+
+#[test]
+fn bps_hostile_sizes() {
+	let rom = source_rom();
+	let target = rom.clone();
+
+	// Metadata size close to the integer limit
+	let mut patch = b"BPS1".to_vec();
+	patch.extend(varint(rom.len()));
+	patch.extend(varint(target.len()));
+	patch.extend(varint(usize::MAX - 4));
+	let patch = with_footer(patch, &rom, &target);
+	assert_eq!(apply_patch(&rom, &patch), Err(PatchError::OutOfBounds));
+
+	// Huge target size with a source copy far past the end of the rom
+	let mut patch = b"BPS1".to_vec();
+	patch.extend(varint(rom.len()));
+	patch.extend(varint(usize::MAX));
+	patch.extend(varint(0));
+	patch.extend(varint(((0x10 - 1) << 2) | 2));
+	patch.extend(varint((usize::MAX >> 1) << 1));
+	let patch = with_footer(patch, &rom, &target);
+	assert_eq!(apply_patch(&rom, &patch), Err(PatchError::OutOfBounds));
+}
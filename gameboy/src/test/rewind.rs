@@ -0,0 +1,120 @@
+use sm83::memory_mapper::MemoryMapper;
+
+use crate::{
+	rewind::{apply_delta, encode_delta, Rewind},
+	test::{boot::cgb_test_instance, util::rom_loader::blank_rom},
+	Gameboy,
+};
+
+fn gameboy() -> Gameboy {
+	let mut gameboy = cgb_test_instance();
+	gameboy.load_rom(&blank_rom(0x03, 0, 2), None).unwrap();
+	gameboy
+}
+
+fn run_frame(gameboy: &mut Gameboy) {
+	let start_frame = gameboy.ppu.frame;
+	while gameboy.ppu.frame == start_frame {
+		gameboy.step();
+	}
+}
+
+// Runs a frame, marking it in work RAM
+fn run_marked_frames(gameboy: &mut Gameboy, rewind: &mut Rewind, frames: u8) {
+	for frame in 0..frames {
+		gameboy.write(0xC000, frame);
+		run_frame(gameboy);
+		rewind.capture(gameboy);
+	}
+}
+
+#[test]
+fn delta_round_trip() {
+	let base = [1, 2, 3, 4, 5, 6, 7, 8];
+	let cases: [&[u8]; 5] = [
+		&[1, 2, 3, 4, 5, 6, 7, 8],
+		&[1, 9, 3, 4, 9, 9, 7, 8],
+		&[1, 2, 3],
+		&[1, 2, 3, 4, 5, 6, 7, 8, 0, 0, 9],
+		&[],
+	];
+
+	for target in cases {
+		let delta = encode_delta(&base, target);
+		assert_eq!(apply_delta(&base, &delta).unwrap(), target);
+	}
+
+	// Identical data only stores the length
+	assert_eq!(encode_delta(&base, &base), vec![8]);
+}
+
+#[test]
+fn rewind_steps_back_frame_by_frame() {
+	let mut gameboy = gameboy();
+	let mut rewind = Rewind::new(60, 1);
+	run_marked_frames(&mut gameboy, &mut rewind, 10);
+	assert_eq!(rewind.len(), 9);
+
+	for frame in (0..9).rev() {
+		gameboy = rewind.step_back(&gameboy).unwrap();
+		assert_eq!(gameboy.read(0xC000), frame);
+	}
+	assert!(rewind.step_back(&gameboy).is_none());
+
+	// The rom is kept when rewinding
+	assert_eq!(gameboy.read(0x0134), b'T');
+}
+
+#[test]
+fn rewind_restores_screen() {
+	let mut gameboy = gameboy();
+	let mut rewind = Rewind::default();
+	run_marked_frames(&mut gameboy, &mut rewind, 2);
+	let screen = gameboy.ppu.lcd.front_buffer().to_vec();
+	run_marked_frames(&mut gameboy, &mut rewind, 1);
+
+	let restored = rewind.step_back(&gameboy).unwrap();
+	assert_eq!(restored.ppu.lcd.front_buffer(), screen);
+}
+
+#[test]
+fn rewind_is_bounded() {
+	let mut gameboy = gameboy();
+	let mut rewind = Rewind::new(3, 1);
+	run_marked_frames(&mut gameboy, &mut rewind, 10);
+	assert_eq!(rewind.len(), 3);
+
+	for _ in 0..3 {
+		gameboy = rewind.step_back(&gameboy).unwrap();
+	}
+	assert_eq!(gameboy.read(0xC000), 6);
+	assert!(rewind.is_empty());
+}
+
+#[test]
+fn rewind_interval() {
+	let mut gameboy = gameboy();
+	let mut rewind = Rewind::new(60, 4);
+	run_marked_frames(&mut gameboy, &mut rewind, 9);
+	assert_eq!(rewind.len(), 2);
+
+	// Snapshots were taken after frames 0, 4 and 8
+	gameboy = rewind.step_back(&gameboy).unwrap();
+	assert_eq!(gameboy.read(0xC000), 4);
+	gameboy = rewind.step_back(&gameboy).unwrap();
+	assert_eq!(gameboy.read(0xC000), 0);
+}
+
+#[test]
+fn rewind_snapshots_are_delta_compressed() {
+	let mut gameboy = gameboy();
+	let mut rewind = Rewind::new(60, 1);
+	run_marked_frames(&mut gameboy, &mut rewind, 1);
+	let snapshot_size = rewind.size();
+
+	run_marked_frames(&mut gameboy, &mut rewind, 30);
+	assert!(rewind.size() < snapshot_size * 2);
+
+	rewind.clear();
+	assert_eq!(rewind.size(), 0);
+}
@@ -0,0 +1,80 @@
+use sm83::memory_mapper::MemoryMapper;
+
+use crate::{
+	cartridge::{header::CartridgeParseError, Cartridge},
+	test::util::rom_loader::blank_rom,
+	Gameboy,
+};
+
+#[test]
+fn rejects_roms_without_header() {
+	for size in [0, 0x100, 0x014F] {
+		assert_eq!(
+			Cartridge::try_new(&vec![0; size], None).err(),
+			Some(CartridgeParseError::Truncated(size))
+		);
+	}
+}
+
+#[test]
+fn rejects_invalid_header_values() {
+	let mut rom = blank_rom(0x00, 0, 0);
+	rom[0x0147] = 0xEE;
+	assert_eq!(
+		Cartridge::try_new(&rom, None).err(),
+		Some(CartridgeParseError::MBCType(0xEE))
+	);
+
+	let mut rom = blank_rom(0x00, 0, 0);
+	rom[0x0148] = 0x20;
+	assert_eq!(
+		Cartridge::try_new(&rom, None).err(),
+		Some(CartridgeParseError::RomSize(0x20))
+	);
+
+	let mut rom = blank_rom(0x00, 0, 0);
+	rom[0x0149] = 0x07;
+	assert_eq!(
+		Cartridge::try_new(&rom, None).err(),
+		Some(CartridgeParseError::RamSize(0x07))
+	);
+}
+
+#[test]
+fn truncated_roms_are_padded_and_mirrored() {
+	// Header claims 8 banks, only 2.5 were dumped
+	let mut rom = blank_rom(0x19, 2, 0);
+	rom.truncate(0xA000);
+	rom[0x4000] = 0x11;
+	rom[0x8000] = 0x22;
+
+	let mut cart = Cartridge::try_new(&rom, None).unwrap();
+	cart.write(0x2000, 0x02);
+	assert_eq!(cart.read(0x4000), 0x22);
+	assert_eq!(cart.read(0x6000), 0xFF);
+
+	cart.write(0x2000, 0x04);
+	assert_eq!(cart.read(0x4000), 0x11);
+
+	// Only the header itself
+	let header_only = &blank_rom(0x00, 0, 0)[..0x0150];
+	let cart = Cartridge::try_new(header_only, None).unwrap();
+	assert_eq!(cart.read(0x0150), 0xFF);
+}
+
+#[test]
+fn overdumps_are_accepted() {
+	let mut rom = blank_rom(0x00, 0, 0);
+	rom.resize(0x20000, 0xAA);
+	let cart = Cartridge::try_new(&rom, None).unwrap();
+	assert_eq!(cart.data.rom_banks.len(), 2);
+}
+
+#[test]
+fn load_rom_keeps_cartridge_on_error() {
+	let mut gb = Gameboy::default();
+	gb.load_rom(&blank_rom(0x00, 0, 0), None).unwrap();
+
+	assert!(gb.load_rom(&[0; 0x20], None).is_err());
+	assert!(gb.cartridge_state.is_some());
+}
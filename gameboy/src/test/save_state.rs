@@ -0,0 +1,423 @@
+use std::time::SystemTime;
+
+use sm83::{
+	memory_mapper::MemoryMapper,
+	registers::{Addressable, CPURegister16},
+};
+
+use serde::Serialize;
+
+use crate::{
+	cartridge::header::CartridgeInfo,
+	patch::apply_patch,
+	save_state::{
+		format::{Chunk, Container, CARTRIDGE_CHUNK, CPU_CHUNK, FORMAT_VERSION, MAGIC, ROM_CHUNK},
+		RomSource, SaveError, SaveState, SaveStateEntry,
+	},
+	test::{boot::cgb_test_instance, util::rom_loader::blank_rom},
+	util::base64,
+	Gameboy,
+};
+
+fn rom() -> Vec<u8> {
+	let mut rom = blank_rom(0x03, 0, 2);
+	rom[0x014D] = 0x12;
+	rom
+}
+
+fn gameboy() -> Gameboy {
+	let mut gameboy = cgb_test_instance();
+	gameboy.load_rom(&rom(), None).unwrap();
+	gameboy.write(0x0000, 0x0A);
+	gameboy.tick_m_cycles(1000);
+	gameboy
+}
+
+fn legacy_save_state(gameboy: &Gameboy) -> SaveState {
+	SaveState {
+		data: serde_json::to_vec(gameboy).unwrap(),
+		info: SaveStateEntry {
+			date: SystemTime::now(),
+			game_title: "TEST".to_owned(),
+			rom_fingerprint: None,
+			thumbnail: None,
+		},
+		rom_source: None,
+		rom_patch: None,
+	}
+}
+
+#[test]
+fn save_state_round_trip() {
+	let mut gameboy = gameboy();
+	gameboy.write(0xC123, 0x45);
+	gameboy.write(0xA010, 0x67);
+	gameboy.write(0xFF80, 0x89);
+	let save = SaveState::try_from(&gameboy).unwrap();
+
+	let mut modified = gameboy.clone();
+	modified.write(0xC123, 0x00);
+	modified.write(0xA010, 0x00);
+	modified.write(0xFF80, 0x00);
+	modified.tick_m_cycles(1000);
+
+	let restored = modified.try_load_save_state(&save).unwrap();
+	assert_eq!(restored.read(0xC123), 0x45);
+	assert_eq!(restored.read(0xA010), 0x67);
+	assert_eq!(restored.read(0xFF80), 0x89);
+	assert_eq!(restored.t_states, gameboy.t_states);
+	assert_eq!(
+		restored.cpu_state.read(CPURegister16::PC),
+		gameboy.cpu_state.read(CPURegister16::PC)
+	);
+
+	// The rom is not part of the save state
+	assert_eq!(restored.read(0x014D), 0x12);
+}
+
+#[test]
+fn save_state_is_compact() {
+	let gameboy = gameboy();
+	let binary = SaveState::try_from(&gameboy).unwrap().data;
+	let json = serde_json::to_vec(&gameboy).unwrap();
+
+	assert!(binary.starts_with(&MAGIC));
+	assert!(binary.len() * 2 < json.len());
+}
+
+#[test]
+fn save_state_rejects_other_games() {
+	let save = SaveState::try_from(&gameboy()).unwrap();
+
+	let mut other = cgb_test_instance();
+	let mut rom = blank_rom(0x03, 0, 2);
+	rom[0x014D] = 0x34;
+	other.load_rom(&rom, None).unwrap();
+
+	assert!(matches!(
+		other.try_load_save_state(&save),
+		Err(SaveError::InvalidGame)
+	));
+}
+
+// Same header, but a different rom image
+fn revised_gameboy() -> Gameboy {
+	let mut rom = rom();
+	rom[0x4000] = 0x01;
+
+	let mut gameboy = cgb_test_instance();
+	gameboy.load_rom(&rom, None).unwrap();
+	gameboy
+}
+
+#[test]
+fn save_state_rejects_other_revisions() {
+	let mut save = SaveState::try_from(&gameboy()).unwrap();
+	let other = revised_gameboy();
+	assert!(matches!(
+		other.try_load_save_state(&save),
+		Err(SaveError::InvalidGame)
+	));
+
+	// The fingerprint in the save data is checked without the entry as well
+	save.info.rom_fingerprint = None;
+	assert!(matches!(
+		other.try_load_save_state(&save),
+		Err(SaveError::InvalidGame)
+	));
+}
+
+#[test]
+fn save_state_force_load() {
+	let mut gameboy = gameboy();
+	gameboy.write(0xC123, 0x45);
+	let save = SaveState::try_from(&gameboy).unwrap();
+
+	let restored = revised_gameboy().force_load_save_state(&save).unwrap();
+	assert_eq!(restored.read(0xC123), 0x45);
+	// The rom of the running game is kept
+	assert_eq!(restored.read(0x4000), 0x01);
+}
+
+#[test]
+fn save_state_entry_has_fingerprint() {
+	let gameboy = gameboy();
+	let save = SaveState::try_from(&gameboy).unwrap();
+	assert_eq!(
+		save.info.rom_fingerprint,
+		Some(gameboy.cartridge_state.unwrap().info.fingerprint)
+	);
+}
+
+#[test]
+fn version_1_save_state_is_migrated() {
+	let mut gameboy = gameboy();
+	gameboy.write(0xC123, 0x45);
+	gameboy.write(0xA123, 0x67);
+	let mut save = SaveState::try_from(&gameboy).unwrap();
+
+	// Version 1 identified the rom with its title and header checksums
+	let mut container = Container::from_bytes(&save.data).unwrap();
+	let rom = container
+		.chunks
+		.iter_mut()
+		.find(|chunk| chunk.id == ROM_CHUNK)
+		.unwrap();
+	let info = &gameboy.cartridge_state.as_ref().unwrap().info;
+	rom.data =
+		bincode::serialize(&(&info.title, info.header_checksum, info.global_checksum)).unwrap();
+	downgrade(&mut container, &gameboy, 1);
+	save.data = container.to_bytes();
+	save.data[4..6].copy_from_slice(&1u16.to_le_bytes());
+	save.info.rom_fingerprint = None;
+
+	let restored = gameboy.try_load_save_state(&save).unwrap();
+	assert_eq!(restored.read(0xC123), 0x45);
+	assert_eq!(restored.read(0xA123), 0x67);
+}
+
+// Rewrites the chunks of the current version in the layout of an older `version`
+fn downgrade(container: &mut Container, gameboy: &Gameboy, version: u16) {
+	downgrade_cart_chunk(container, gameboy, version);
+	downgrade_cpu_chunk(container, version);
+}
+
+// Versions 1 to 4 saved the cartridge info after the cartridge state,
+// version 1 without the fingerprint
+fn downgrade_cart_chunk(container: &mut Container, gameboy: &Gameboy, version: u16) {
+	let cart = container
+		.chunks
+		.iter_mut()
+		.find(|chunk| chunk.id == CARTRIDGE_CHUNK)
+		.unwrap();
+	let info = &gameboy.cartridge_state.as_ref().unwrap().info;
+	let old_info = match version {
+		1 => bincode::serialize(&CartridgeInfoV1::from(info)),
+		_ => bincode::serialize(info),
+	};
+	cart.data.extend(old_info.unwrap());
+}
+
+#[derive(Serialize)]
+struct CartridgeInfoV1 {
+	rom_source: Option<RomSource>,
+	title: String,
+	licensee: Option<String>,
+	cartridge_type: u8,
+	cgb: bool,
+	sgb: bool,
+	rom_banks: u16,
+	ram_banks: u16,
+	has_battery: bool,
+	has_rtc: bool,
+	has_rumble: bool,
+	has_sensor: bool,
+	has_camera: bool,
+	header_checksum: u8,
+	global_checksum: u16,
+	logo_valid: bool,
+	header_checksum_valid: bool,
+	global_checksum_valid: bool,
+}
+
+impl From<&CartridgeInfo> for CartridgeInfoV1 {
+	fn from(info: &CartridgeInfo) -> Self {
+		Self {
+			rom_source: info.rom_source.clone(),
+			title: info.title.clone(),
+			licensee: info.licensee.clone(),
+			cartridge_type: info.cartridge_type,
+			cgb: info.cgb,
+			sgb: info.sgb,
+			rom_banks: info.rom_banks,
+			ram_banks: info.ram_banks,
+			has_battery: info.has_battery,
+			has_rtc: info.has_rtc,
+			has_rumble: info.has_rumble,
+			has_sensor: info.has_sensor,
+			has_camera: info.has_camera,
+			header_checksum: info.header_checksum,
+			global_checksum: info.global_checksum,
+			logo_valid: info.logo_valid,
+			header_checksum_valid: info.header_checksum_valid,
+			global_checksum_valid: info.global_checksum_valid,
+		}
+	}
+}
+
+// Removes the CPU fields added after `version`, all of them encode to a single zero byte
+fn downgrade_cpu_chunk(container: &mut Container, version: u16) {
+	let cpu = container
+		.chunks
+		.iter_mut()
+		.find(|chunk| chunk.id == CPU_CHUNK)
+		.unwrap();
+	// Version 3 added the HALT bug flag, version 4 the lock up
+	let added_fields = [3, 4].iter().filter(|added| **added > version).count();
+	for _ in 0..added_fields {
+		assert_eq!(cpu.data.pop(), Some(0));
+	}
+}
+
+#[test]
+fn version_2_save_state_is_migrated() {
+	let mut gameboy = gameboy();
+	gameboy.cpu_state.write(CPURegister16::PC, 0x1234);
+	let mut save = SaveState::try_from(&gameboy).unwrap();
+
+	let mut container = Container::from_bytes(&save.data).unwrap();
+	downgrade(&mut container, &gameboy, 2);
+	save.data = container.to_bytes();
+	save.data[4..6].copy_from_slice(&2u16.to_le_bytes());
+
+	let restored = gameboy.try_load_save_state(&save).unwrap();
+	assert_eq!(restored.cpu_state.read(CPURegister16::PC), 0x1234);
+	assert!(!restored.cpu_state.halt_bug);
+}
+
+#[test]
+fn version_3_save_state_is_migrated() {
+	let mut gameboy = gameboy();
+	gameboy.cpu_state.write(CPURegister16::PC, 0x1234);
+	let mut save = SaveState::try_from(&gameboy).unwrap();
+
+	let mut container = Container::from_bytes(&save.data).unwrap();
+	downgrade(&mut container, &gameboy, 3);
+	save.data = container.to_bytes();
+	save.data[4..6].copy_from_slice(&3u16.to_le_bytes());
+
+	let restored = gameboy.try_load_save_state(&save).unwrap();
+	assert_eq!(restored.cpu_state.read(CPURegister16::PC), 0x1234);
+	assert!(restored.cpu_state.locked.is_none());
+}
+
+#[test]
+fn version_4_save_state_is_migrated() {
+	let mut gameboy = gameboy();
+	gameboy.write(0xA123, 0x45);
+	let mut save = SaveState::try_from(&gameboy).unwrap();
+
+	let mut container = Container::from_bytes(&save.data).unwrap();
+	downgrade(&mut container, &gameboy, 4);
+	save.data = container.to_bytes();
+	save.data[4..6].copy_from_slice(&4u16.to_le_bytes());
+
+	let restored = gameboy.try_load_save_state(&save).unwrap();
+	assert_eq!(restored.read(0xA123), 0x45);
+	assert_eq!(
+		restored.cartridge_state.unwrap().info.fingerprint,
+		gameboy.cartridge_state.unwrap().info.fingerprint
+	);
+}
+
+#[test]
+fn save_state_rejects_unknown_versions() {
+	let gameboy = gameboy();
+	let mut save = SaveState::try_from(&gameboy).unwrap();
+	save.data[4..6].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+
+	assert!(matches!(
+		gameboy.try_load_save_state(&save),
+		Err(SaveError::UnsupportedVersion(version)) if version == FORMAT_VERSION + 1
+	));
+}
+
+#[test]
+fn save_state_rejects_truncated_data() {
+	let gameboy = gameboy();
+	let mut save = SaveState::try_from(&gameboy).unwrap();
+	save.data.truncate(save.data.len() - 1);
+
+	assert!(matches!(
+		gameboy.try_load_save_state(&save),
+		Err(SaveError::Deserialization)
+	));
+}
+
+#[test]
+fn save_state_skips_unknown_chunks() {
+	let mut gameboy = gameboy();
+	gameboy.write(0xC123, 0x45);
+	let mut save = SaveState::try_from(&gameboy).unwrap();
+
+	let mut container = Container::from_bytes(&save.data).unwrap();
+	container.chunks.insert(
+		0,
+		Chunk {
+			id: *b"NEW ",
+			data: vec![1, 2, 3],
+		},
+	);
+	save.data = container.to_bytes();
+
+	let restored = gameboy.try_load_save_state(&save).unwrap();
+	assert_eq!(restored.read(0xC123), 0x45);
+}
+
+#[test]
+fn legacy_json_save_state_is_migrated() {
+	let mut gameboy = gameboy();
+	gameboy.write(0xC123, 0x45);
+	let save = legacy_save_state(&gameboy);
+
+	// Legacy saves were stored with the emulator as a JSON string
+	let stored = serde_json::to_string(&save).unwrap();
+	let save: SaveState = serde_json::from_str(&stored).unwrap();
+
+	let restored = gameboy.try_load_save_state(&save).unwrap();
+	assert_eq!(restored.read(0xC123), 0x45);
+	assert_eq!(restored.read(0x014D), 0x12);
+}
+
+#[test]
+fn save_state_stored_as_base64() {
+	let save = SaveState::try_from(&gameboy()).unwrap();
+	let stored = serde_json::to_string(&save).unwrap();
+	let restored: SaveState = serde_json::from_str(&stored).unwrap();
+	assert_eq!(restored.data, save.data);
+}
+
+#[test]
+fn save_state_keeps_rom_patch() {
+	// IPS patch changing a byte at $4000
+	let patch = [&b"PATCH"[..], &[0x00, 0x40, 0x00, 0x00, 0x01, 0xAB], b"EOF"].concat();
+	let patched_rom = apply_patch(&rom(), &patch).unwrap();
+
+	let mut gameboy = cgb_test_instance();
+	gameboy.load_rom(&patched_rom, None).unwrap();
+	gameboy.write(0xC123, 0x45);
+	let mut save = SaveState::try_from(&gameboy).unwrap();
+	save.rom_patch = Some(patch.clone());
+
+	let stored = serde_json::to_string(&save).unwrap();
+	let save: SaveState = serde_json::from_str(&stored).unwrap();
+	assert_eq!(save.rom_patch.as_ref(), Some(&patch));
+
+	let mut unpatched = cgb_test_instance();
+	unpatched.load_rom(&rom(), None).unwrap();
+	assert!(matches!(
+		unpatched.try_load_save_state(&save),
+		Err(SaveError::InvalidGame)
+	));
+
+	let mut repatched = cgb_test_instance();
+	let rom = apply_patch(&rom(), save.rom_patch.as_ref().unwrap()).unwrap();
+	repatched.load_rom(&rom, None).unwrap();
+	let restored = repatched.try_load_save_state(&save).unwrap();
+	assert_eq!(restored.read(0xC123), 0x45);
+	assert_eq!(restored.read(0x4000), 0xAB);
+}
+
+#[test]
+fn base64_encoding() {
+	assert_eq!(base64::encode(b""), "");
+	assert_eq!(base64::encode(b"f"), "Zg==");
+	assert_eq!(base64::encode(b"fo"), "Zm8=");
+	assert_eq!(base64::encode(b"foobar"), "Zm9vYmFy");
+
+	assert_eq!(base64::decode("Zg==").unwrap(), b"f");
+	assert_eq!(base64::decode("Zm8=").unwrap(), b"fo");
+	assert_eq!(base64::decode("Zm9vYmFy").unwrap(), b"foobar");
+	assert_eq!(base64::decode("Z"), None);
+	assert_eq!(base64::decode("Zm9v!"), None);
+}
@@ -0,0 +1,107 @@
+use crate::{
+	lcd::GameboyLCD,
+	save_state::{
+		thumbnail::{Thumbnail, THUMBNAIL_HEIGHT, THUMBNAIL_WIDTH},
+		MemorySaveManager, SaveError, SaveManager, SaveState, SaveStateEntry, SAVE_SLOTS,
+	},
+	test::{boot::cgb_test_instance, util::rom_loader::blank_rom},
+	Gameboy,
+};
+
+fn gameboy() -> Gameboy {
+	let mut gameboy = cgb_test_instance();
+	gameboy.load_rom(&blank_rom(0x03, 0, 2), None).unwrap();
+	gameboy
+}
+
+// Left half red, right half blue, one green pixel in the top left corner
+fn test_lcd() -> GameboyLCD {
+	let mut lcd = GameboyLCD::default();
+	for y in 0..144 {
+		for x in 0..160 {
+			let color = if x < 80 {
+				(0xF8, 0, 0, 0xFF)
+			} else {
+				(0, 0, 0xF8, 0xFF)
+			};
+			lcd.put_pixel(x, y, color);
+		}
+	}
+	for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+		lcd.put_pixel(x, y, (0, 0xF8, 0, 0xFF));
+	}
+	lcd.swap_buffers();
+	lcd
+}
+
+fn pixel(image: &[u8], x: usize, y: usize) -> &[u8] {
+	let index = (y * THUMBNAIL_WIDTH + x) * 4;
+	&image[index..index + 4]
+}
+
+#[test]
+fn thumbnail_round_trip() {
+	let thumbnail = Thumbnail::capture(&test_lcd());
+	let image = thumbnail.to_rgba();
+
+	assert_eq!(image.len(), THUMBNAIL_WIDTH * THUMBNAIL_HEIGHT * 4);
+	assert_eq!(pixel(&image, 0, 0), [0, 0xFF, 0, 0xFF]);
+	assert_eq!(pixel(&image, 1, 0), [0xFF, 0, 0, 0xFF]);
+	assert_eq!(pixel(&image, 39, 71), [0xFF, 0, 0, 0xFF]);
+	assert_eq!(pixel(&image, 40, 0), [0, 0, 0xFF, 0xFF]);
+	assert_eq!(pixel(&image, 79, 71), [0, 0, 0xFF, 0xFF]);
+}
+
+#[test]
+fn thumbnail_is_compressed() {
+	let thumbnail = Thumbnail::capture(&test_lcd());
+	assert!(thumbnail.compressed_size() < THUMBNAIL_WIDTH * THUMBNAIL_HEIGHT);
+
+	let json = serde_json::to_string(&thumbnail).unwrap();
+	assert_eq!(serde_json::from_str::<Thumbnail>(&json).unwrap(), thumbnail);
+}
+
+#[test]
+fn save_state_entry_has_thumbnail() {
+	let mut gameboy = gameboy();
+	gameboy.ppu.lcd = test_lcd();
+
+	let save = SaveState::try_from(&gameboy).unwrap();
+	assert_eq!(save.info.thumbnail, Some(Thumbnail::capture(&test_lcd())));
+}
+
+#[test]
+fn entry_without_thumbnail_is_loaded() {
+	let mut entry = SaveState::try_from(&gameboy()).unwrap().info;
+	entry.thumbnail = None;
+
+	let mut json: serde_json::Value = serde_json::to_value(&entry).unwrap();
+	json.as_object_mut().unwrap().remove("thumbnail");
+	let loaded: SaveStateEntry = serde_json::from_value(json).unwrap();
+	assert_eq!(loaded, entry);
+}
+
+#[test]
+fn memory_save_manager_lists_thumbnails() {
+	let mut manager = MemorySaveManager::default();
+	let mut gameboy = gameboy();
+	gameboy.ppu.lcd = test_lcd();
+
+	assert!(manager.get_save_states().iter().all(Option::is_none));
+	assert!(matches!(
+		manager.load_save_state(2),
+		Err(SaveError::NoSource)
+	));
+
+	let save = SaveState::try_from(&gameboy).unwrap();
+	manager.save_save_state(save, 2).unwrap();
+	assert!(matches!(
+		manager.save_save_state(SaveState::try_from(&gameboy).unwrap(), SAVE_SLOTS),
+		Err(SaveError::IndexOutOfBounds(_))
+	));
+
+	let states = manager.get_save_states();
+	assert_eq!(states.len(), SAVE_SLOTS);
+	assert!(states[2].as_ref().unwrap().thumbnail.is_some());
+	assert!(manager.load_save_state(2).is_ok());
+}
@@ -1,4 +1,4 @@
-use sm83::{Flags, SM83};
+use sm83::{assembler::assemble, Flags, SM83};
 
 use crate::test::boot::cgb_test_instance;
 
@@ -11,6 +11,12 @@ pub fn expect_instr_timing(name: &str, instrs: &[u8], steps: usize, expected: u6
 	}
 }
 
+/// Like `expect_instr_timing`, with the instructions written as assembly
+pub fn expect_asm_timing(name: &str, source: &str, steps: usize, expected: u64, flag: u8) {
+	let program = assemble(source, 0x100).unwrap_or_else(|err| panic!("{name}: {err}"));
+	expect_instr_timing(name, &program.bytes, steps, expected, flag);
+}
+
 /// Computes the number of cycles taken
 /// Flags are used to force conditions
 pub fn get_cycles_taken(instrs: &[u8], steps: usize, flag: u8) -> u64 {
@@ -18,7 +18,7 @@ pub fn init_emulator_with_rom(src: &str) -> Gameboy {
 		dmg_test_instance()
 	};
 
-	state.load_rom(&rom, None);
+	state.load_rom(&rom, None).unwrap();
 	state
 }
 
@@ -29,3 +29,13 @@ pub fn init_emulator_with_rom_cgb(src: &str) -> Gameboy {
 pub fn init_emulator_with_rom_dmg(src: &str) -> Gameboy {
 	init_emulator_with_rom(src)
 }
+
+// Creates an empty rom image with a header describing the given cartridge hardware
+pub fn blank_rom(cartridge_type: u8, rom_size: u8, ram_size: u8) -> Vec<u8> {
+	let mut rom = vec![0; 0x8000 << rom_size];
+	rom[0x0134..0x0138].copy_from_slice(b"TEST");
+	rom[0x0147] = cartridge_type;
+	rom[0x0148] = rom_size;
+	rom[0x0149] = ram_size;
+	rom
+}
@@ -22,6 +22,11 @@ impl Timer {
 		self.system_clock = 0;
 	}
 
+	/// Restores the upper bits of the system clock, without the side effects of a DIV write
+	pub fn load_div(&mut self, value: u8) {
+		self.system_clock = (value as u16) << 8;
+	}
+
 	fn current_output_clock(&self) -> bool {
 		let bit = self.tac_freq() >> 1;
 		(self.system_clock & bit) == bit
@@ -0,0 +1,53 @@
+// Base64 with the standard alphabet and padding, used to store binary data in text formats
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn encode(data: &[u8]) -> String {
+	let mut output = String::with_capacity(data.len().div_ceil(3) * 4);
+
+	for chunk in data.chunks(3) {
+		let bytes = [
+			chunk[0],
+			*chunk.get(1).unwrap_or(&0),
+			*chunk.get(2).unwrap_or(&0),
+		];
+		let group = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+
+		for index in 0..4 {
+			if index <= chunk.len() {
+				let sextet = (group >> (18 - index * 6)) & 0x3F;
+				output.push(ALPHABET[sextet as usize] as char);
+			} else {
+				output.push('=');
+			}
+		}
+	}
+	output
+}
+
+fn decode_sextet(char: u8) -> Option<u32> {
+	ALPHABET
+		.iter()
+		.position(|&c| c == char)
+		.map(|position| position as u32)
+}
+
+/// Returns `None` if the input is not valid base64
+pub fn decode(data: &str) -> Option<Vec<u8>> {
+	let data = data.trim_end_matches('=').as_bytes();
+	if data.len() % 4 == 1 {
+		return None;
+	}
+
+	let mut output = Vec::with_capacity(data.len() * 3 / 4);
+	for chunk in data.chunks(4) {
+		let mut group = 0;
+		for (index, char) in chunk.iter().enumerate() {
+			group |= decode_sextet(*char)? << (18 - index * 6);
+		}
+
+		let bytes = group.to_be_bytes();
+		output.extend_from_slice(&bytes[1..chunk.len()]);
+	}
+	Some(output)
+}
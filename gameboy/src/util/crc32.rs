@@ -0,0 +1,29 @@
+// CRC-32 (IEEE 802.3), as used by zip, png, UPS and BPS
+
+const fn make_table() -> [u32; 256] {
+	let mut table = [0; 256];
+	let mut i = 0;
+	while i < 256 {
+		let mut crc = i as u32;
+		let mut bit = 0;
+		while bit < 8 {
+			crc = if crc & 1 != 0 {
+				(crc >> 1) ^ 0xEDB88320
+			} else {
+				crc >> 1
+			};
+			bit += 1;
+		}
+		table[i] = crc;
+		i += 1;
+	}
+	table
+}
+
+static TABLE: [u32; 256] = make_table();
+
+pub fn crc32(data: &[u8]) -> u32 {
+	!data.iter().fold(!0, |crc, byte| {
+		TABLE[((crc ^ *byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+	})
+}
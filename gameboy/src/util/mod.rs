@@ -1,3 +1,6 @@
+pub mod base64;
 pub mod bits;
+pub mod crc32;
 mod serde_big_array;
+pub mod sha1;
 pub use serde_big_array::BigArray;
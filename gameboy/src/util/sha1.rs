@@ -0,0 +1,66 @@
+// SHA-1 (FIPS 180-4), as used by No-Intro and other rom databases
+
+const INITIAL_STATE: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+fn compress(state: &mut [u32; 5], block: &[u8]) {
+	let mut words = [0u32; 80];
+	for (word, bytes) in words.iter_mut().zip(block.chunks_exact(4)) {
+		*word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+	}
+	for i in 16..80 {
+		words[i] = (words[i - 3] ^ words[i - 8] ^ words[i - 14] ^ words[i - 16]).rotate_left(1);
+	}
+
+	let [mut a, mut b, mut c, mut d, mut e] = *state;
+	for (i, word) in words.iter().enumerate() {
+		let (f, k) = match i {
+			0..=19 => ((b & c) | (!b & d), 0x5A827999),
+			20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+			40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+			_ => (b ^ c ^ d, 0xCA62C1D6),
+		};
+
+		let temp = a
+			.rotate_left(5)
+			.wrapping_add(f)
+			.wrapping_add(e)
+			.wrapping_add(k)
+			.wrapping_add(*word);
+		e = d;
+		d = c;
+		c = b.rotate_left(30);
+		b = a;
+		a = temp;
+	}
+
+	for (value, new) in state.iter_mut().zip([a, b, c, d, e]) {
+		*value = value.wrapping_add(new);
+	}
+}
+
+pub fn sha1(data: &[u8]) -> [u8; 20] {
+	let mut state = INITIAL_STATE;
+
+	let blocks = data.chunks_exact(64);
+	let remainder = blocks.remainder();
+	for block in blocks {
+		compress(&mut state, block);
+	}
+
+	// Pad with a single set bit, zeroes and the length in bits
+	let mut tail = remainder.to_vec();
+	tail.push(0x80);
+	while tail.len() % 64 != 56 {
+		tail.push(0);
+	}
+	tail.extend(((data.len() as u64) * 8).to_be_bytes());
+	for block in tail.chunks_exact(64) {
+		compress(&mut state, block);
+	}
+
+	let mut digest = [0; 20];
+	for (bytes, value) in digest.chunks_exact_mut(4).zip(state) {
+		bytes.copy_from_slice(&value.to_be_bytes());
+	}
+	digest
+}
@@ -0,0 +1,78 @@
+// Measures the decode throughput of `Fetch::fetch`
+// Usage: cargo run --release -p sm83 --example fetch_bench
+
+use std::{hint::black_box, time::Instant};
+
+use sm83::{
+	instruction::Fetch,
+	memory_mapper::{MemoryMapper, Source, SourcedMemoryMapper},
+	CPUState, SM83,
+};
+
+struct Decoder {
+	memory: Vec<u8>,
+	cpu_state: CPUState,
+}
+
+impl MemoryMapper for Decoder {
+	fn read(&self, addr: u16) -> u8 {
+		self.memory[addr as usize]
+	}
+
+	fn write(&mut self, _addr: u16, _value: u8) {}
+}
+
+impl SourcedMemoryMapper for Decoder {
+	fn read_from(&self, addr: u16, _source: Source) -> u8 {
+		self.read(addr)
+	}
+
+	fn write_from(&mut self, _addr: u16, _value: u8, _source: Source) {}
+}
+
+impl SM83 for Decoder {
+	fn cpu_state(&self) -> &CPUState {
+		&self.cpu_state
+	}
+
+	fn cpu_state_mut(&mut self) -> &mut CPUState {
+		&mut self.cpu_state
+	}
+}
+
+// Address space filled with xorshift noise, so every opcode shows up
+fn memory() -> Vec<u8> {
+	let mut state: u32 = 0x2545_F491;
+	(0..0x10000)
+		.map(|_| {
+			state ^= state << 13;
+			state ^= state >> 17;
+			state ^= state << 5;
+			state as u8
+		})
+		.collect()
+}
+
+pub fn main() {
+	let runs = 5;
+	let fetches_per_run = 50_000_000;
+
+	let mut decoder = Decoder {
+		memory: memory(),
+		cpu_state: CPUState::default(),
+	};
+
+	for run in 0..runs {
+		let start = Instant::now();
+
+		for _ in 0..fetches_per_run {
+			black_box(decoder.fetch());
+		}
+
+		let elapsed = start.elapsed();
+		let per_second = fetches_per_run as f64 / elapsed.as_secs_f64() / 1_000_000.0;
+		println!("run: #{}", run + 1);
+		println!("Elapsed: {elapsed:?}");
+		println!("Fetches/s: {per_second:.1}M");
+	}
+}
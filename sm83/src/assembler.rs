@@ -0,0 +1,859 @@
+// Assembler for RGBDS style source
+//
+// Supports every instruction, global and local labels (`Main:`, `.loop:`),
+// `db`/`dw` and constant expressions. The size of an instruction only depends on
+// its syntax, so labels are assigned addresses in a first pass and
+// everything is encoded in a second one.
+//
+// The `Debug` output of `Instruction` is accepted as well,
+// including hardware register names in memory operands like `[$LCDC]`.
+
+use std::{
+	collections::HashMap,
+	fmt::{self, Display},
+	iter::Peekable,
+	str::Chars,
+};
+
+use crate::values::hardware_register;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssemblerErrorKind {
+	Syntax(String),
+	UnknownMnemonic(String),
+	InvalidOperands(String),
+	UndefinedSymbol(String),
+	DuplicateLabel(String),
+	OutOfRange(i64),
+	DivisionByZero,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssemblerError {
+	// Line numbers start at 1
+	pub line: usize,
+	pub kind: AssemblerErrorKind,
+}
+
+impl Display for AssemblerErrorKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Syntax(message) => write!(f, "Syntax error: {message}"),
+			Self::UnknownMnemonic(mnemonic) => write!(f, "Unknown instruction \"{mnemonic}\""),
+			Self::InvalidOperands(mnemonic) => write!(f, "Invalid operands for \"{mnemonic}\""),
+			Self::UndefinedSymbol(symbol) => write!(f, "Undefined symbol \"{symbol}\""),
+			Self::DuplicateLabel(label) => write!(f, "Label \"{label}\" is already defined"),
+			Self::OutOfRange(value) => write!(f, "Value {value} is out of range"),
+			Self::DivisionByZero => write!(f, "Division by zero"),
+		}
+	}
+}
+
+impl Display for AssemblerError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "Line {}: {}", self.line, self.kind)
+	}
+}
+
+impl std::error::Error for AssemblerError {}
+
+use AssemblerErrorKind::*;
+
+pub struct Program {
+	pub bytes: Vec<u8>,
+	// Address of every label, local labels are named `Global.local`
+	pub symbols: HashMap<String, u16>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+	Number(i64),
+	Ident(String),
+	Str(String),
+	Punct(&'static str),
+}
+
+fn syntax(message: impl Into<String>) -> AssemblerErrorKind {
+	Syntax(message.into())
+}
+
+fn take_word(chars: &mut Peekable<Chars>) -> String {
+	let mut word = String::new();
+	while let Some(&c) = chars.peek() {
+		if !(c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '#')) {
+			break;
+		}
+		word.push(c);
+		chars.next();
+	}
+	word
+}
+
+fn parse_number(digits: &str, radix: u32) -> Result<i64, AssemblerErrorKind> {
+	let digits = digits.replace('_', "");
+	i64::from_str_radix(&digits, radix).or(Err(syntax(format!("invalid number \"{digits}\""))))
+}
+
+fn tokenize(line: &str) -> Result<Vec<Token>, AssemblerErrorKind> {
+	let mut tokens = vec![];
+	let mut chars = line.chars().peekable();
+
+	while let Some(&c) = chars.peek() {
+		// `%` is a binary number unless it follows an operand
+		let after_operand = matches!(
+			tokens.last(),
+			Some(Token::Number(_) | Token::Ident(_) | Token::Punct(")"))
+		);
+
+		let token = match c {
+			';' => break,
+			c if c.is_whitespace() => {
+				chars.next();
+				continue;
+			}
+			'"' => {
+				chars.next();
+				let mut string = String::new();
+				loop {
+					match chars.next() {
+						Some('"') => break,
+						Some('\\') => string.push(match chars.next() {
+							Some('n') => '\n',
+							Some('t') => '\t',
+							Some('0') => '\0',
+							Some(c) => c,
+							None => return Err(syntax("unterminated string")),
+						}),
+						Some(c) => string.push(c),
+						None => return Err(syntax("unterminated string")),
+					}
+				}
+				Token::Str(string)
+			}
+			'\'' => {
+				chars.next();
+				let (Some(c), Some('\'')) = (chars.next(), chars.next()) else {
+					return Err(syntax("invalid character literal"));
+				};
+				Token::Number(c as i64)
+			}
+			'$' => {
+				chars.next();
+				let word = take_word(&mut chars);
+				match hardware_register(&word) {
+					Some(address) if !word.chars().all(|c| c.is_ascii_hexdigit()) => {
+						Token::Number(address as i64)
+					}
+					_ => Token::Number(parse_number(&word, 16)?),
+				}
+			}
+			'%' if !after_operand => {
+				chars.next();
+				Token::Number(parse_number(&take_word(&mut chars), 2)?)
+			}
+			'0'..='9' => {
+				let word = take_word(&mut chars);
+				let lowercase = word.to_ascii_lowercase();
+				Token::Number(match lowercase.get(..2) {
+					Some("0x") => parse_number(&lowercase[2..], 16)?,
+					Some("0b") => parse_number(&lowercase[2..], 2)?,
+					_ => parse_number(&lowercase, 10)?,
+				})
+			}
+			'@' => {
+				chars.next();
+				Token::Ident("@".to_owned())
+			}
+			c if c.is_ascii_alphabetic() || c == '_' || c == '.' => {
+				Token::Ident(take_word(&mut chars))
+			}
+			_ => {
+				chars.next();
+				let punct = match (c, chars.peek()) {
+					('<', Some('<')) => "<<",
+					('>', Some('>')) => ">>",
+					(':', Some(':')) => "::",
+					(',', _) => ",",
+					('[', _) => "[",
+					(']', _) => "]",
+					('(', _) => "(",
+					(')', _) => ")",
+					('+', _) => "+",
+					('-', _) => "-",
+					('*', _) => "*",
+					('/', _) => "/",
+					('%', _) => "%",
+					('&', _) => "&",
+					('|', _) => "|",
+					('^', _) => "^",
+					('~', _) => "~",
+					(':', _) => ":",
+					_ => return Err(syntax(format!("unexpected character '{c}'"))),
+				};
+				if punct.len() == 2 {
+					chars.next();
+				}
+				Token::Punct(punct)
+			}
+		};
+		tokens.push(token);
+	}
+
+	Ok(tokens)
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+	Number(i64),
+	Symbol(String),
+	// Address of the current statement, `@`
+	Here,
+	Unary(&'static str, Box<Expr>),
+	Binary(&'static str, Box<Expr>, Box<Expr>),
+	High(Box<Expr>),
+	Low(Box<Expr>),
+}
+
+// Binary operators from the lowest to the highest precedence, as in RGBDS
+const PRECEDENCE: [&[&str]; 4] = [
+	&["+", "-"],
+	&["&", "|", "^"],
+	&["<<", ">>"],
+	&["*", "/", "%"],
+];
+
+struct ExprParser<'a> {
+	tokens: &'a [Token],
+	position: usize,
+	scope: &'a str,
+}
+
+impl ExprParser<'_> {
+	fn peek(&self) -> Option<&Token> {
+		self.tokens.get(self.position)
+	}
+
+	fn next(&mut self) -> Option<&Token> {
+		self.position += 1;
+		self.tokens.get(self.position - 1)
+	}
+
+	fn expect(&mut self, punct: &str) -> Result<(), AssemblerErrorKind> {
+		match self.next() {
+			Some(Token::Punct(p)) if *p == punct => Ok(()),
+			_ => Err(syntax(format!("expected '{punct}'"))),
+		}
+	}
+
+	fn binary(&mut self, level: usize) -> Result<Expr, AssemblerErrorKind> {
+		let Some(operators) = PRECEDENCE.get(level) else {
+			return self.unary();
+		};
+
+		let mut lhs = self.binary(level + 1)?;
+		while let Some(Token::Punct(punct)) = self.peek() {
+			let Some(&operator) = operators.iter().find(|operator| *operator == punct) else {
+				break;
+			};
+			self.position += 1;
+			let rhs = self.binary(level + 1)?;
+			lhs = Expr::Binary(operator, Box::new(lhs), Box::new(rhs));
+		}
+		Ok(lhs)
+	}
+
+	fn unary(&mut self) -> Result<Expr, AssemblerErrorKind> {
+		match self.peek() {
+			Some(Token::Punct(operator @ ("-" | "+" | "~"))) => {
+				let operator = *operator;
+				self.position += 1;
+				Ok(Expr::Unary(operator, Box::new(self.unary()?)))
+			}
+			_ => self.primary(),
+		}
+	}
+
+	fn primary(&mut self) -> Result<Expr, AssemblerErrorKind> {
+		let scope = self.scope;
+		match self.next().cloned() {
+			Some(Token::Number(value)) => Ok(Expr::Number(value)),
+			Some(Token::Punct("(")) => {
+				let expr = self.binary(0)?;
+				self.expect(")")?;
+				Ok(expr)
+			}
+			Some(Token::Ident(name)) if name == "@" => Ok(Expr::Here),
+			Some(Token::Ident(name))
+				if matches!(self.peek(), Some(Token::Punct("(")))
+					&& ["high", "low"].contains(&name.to_ascii_lowercase().as_str()) =>
+			{
+				self.position += 1;
+				let expr = Box::new(self.binary(0)?);
+				self.expect(")")?;
+				Ok(match name.to_ascii_lowercase().as_str() {
+					"high" => Expr::High(expr),
+					_ => Expr::Low(expr),
+				})
+			}
+			Some(Token::Ident(name)) => Ok(Expr::Symbol(qualify(&name, scope))),
+			Some(token) => Err(syntax(format!("unexpected {token:?}"))),
+			None => Err(syntax("expected an expression")),
+		}
+	}
+}
+
+fn parse_expr(tokens: &[Token], scope: &str) -> Result<Expr, AssemblerErrorKind> {
+	let mut parser = ExprParser {
+		tokens,
+		position: 0,
+		scope,
+	};
+	let expr = parser.binary(0)?;
+	match parser.peek() {
+		Some(token) => Err(syntax(format!("unexpected {token:?}"))),
+		None => Ok(expr),
+	}
+}
+
+// Local labels belong to the last global label
+fn qualify(name: &str, scope: &str) -> String {
+	if name.starts_with('.') {
+		format!("{scope}{name}")
+	} else {
+		name.to_owned()
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Register16 {
+	BC,
+	DE,
+	HL,
+	SP,
+	AF,
+}
+
+const A: u8 = 7;
+const HL_MEM: u8 = 6;
+
+#[derive(Debug, Clone)]
+enum Operand {
+	// Index of the register in the decode tables: b, c, d, e, h, l, [hl], a
+	Reg8(u8),
+	Reg16(Register16),
+	// nz, z, nc. `c` is parsed as a register
+	Condition(u8),
+	// [bc] or [de]
+	Mem(Register16),
+	HlIncrement,
+	HlDecrement,
+	// [c] or [$FF00+c]
+	MemC,
+	MemValue(Expr),
+	SpOffset(Expr),
+	Value(Expr),
+}
+
+fn parse_register(name: &str) -> Option<Operand> {
+	use Operand::*;
+	Some(match name.to_ascii_lowercase().as_str() {
+		"b" => Reg8(0),
+		"c" => Reg8(1),
+		"d" => Reg8(2),
+		"e" => Reg8(3),
+		"h" => Reg8(4),
+		"l" => Reg8(5),
+		"a" => Reg8(A),
+		"bc" => Reg16(Register16::BC),
+		"de" => Reg16(Register16::DE),
+		"hl" => Reg16(Register16::HL),
+		"sp" => Reg16(Register16::SP),
+		"af" => Reg16(Register16::AF),
+		"nz" => Condition(0),
+		"z" => Condition(1),
+		"nc" => Condition(2),
+		_ => return None,
+	})
+}
+
+fn parse_memory(tokens: &[Token], scope: &str) -> Result<Operand, AssemblerErrorKind> {
+	use Operand::*;
+
+	let ident = |token: &Token| match token {
+		Token::Ident(name) => name.to_ascii_lowercase(),
+		_ => String::new(),
+	};
+
+	Ok(match tokens {
+		[register] => match ident(register).as_str() {
+			"hl" => Reg8(HL_MEM),
+			"hli" => HlIncrement,
+			"hld" => HlDecrement,
+			"bc" => Mem(Register16::BC),
+			"de" => Mem(Register16::DE),
+			"c" => MemC,
+			_ => MemValue(parse_expr(tokens, scope)?),
+		},
+		[register, Token::Punct("+")] if ident(register) == "hl" => HlIncrement,
+		[register, Token::Punct("-")] if ident(register) == "hl" => HlDecrement,
+		[.., Token::Punct("+"), register] if ident(register) == "c" => MemC,
+		_ => MemValue(parse_expr(tokens, scope)?),
+	})
+}
+
+fn parse_operand(tokens: &[Token], scope: &str) -> Result<Operand, AssemblerErrorKind> {
+	match tokens {
+		[] => Err(syntax("expected an operand")),
+		[Token::Ident(name)] => match parse_register(name) {
+			Some(register) => Ok(register),
+			None => Ok(Operand::Value(parse_expr(tokens, scope)?)),
+		},
+		[Token::Ident(name), offset @ ..] if name.eq_ignore_ascii_case("sp") => {
+			Ok(Operand::SpOffset(parse_expr(offset, scope)?))
+		}
+		[Token::Punct("["), inner @ .., Token::Punct("]")] => parse_memory(inner, scope),
+		_ => Ok(Operand::Value(parse_expr(tokens, scope)?)),
+	}
+}
+
+// Splits on commas outside of parentheses
+fn split_operands(tokens: &[Token]) -> Vec<&[Token]> {
+	if tokens.is_empty() {
+		return vec![];
+	}
+
+	let mut operands = vec![];
+	let mut depth = 0;
+	let mut start = 0;
+	for (index, token) in tokens.iter().enumerate() {
+		match token {
+			Token::Punct("(" | "[") => depth += 1,
+			Token::Punct(")" | "]") => depth -= 1,
+			Token::Punct(",") if depth == 0 => {
+				operands.push(&tokens[start..index]);
+				start = index + 1;
+			}
+			_ => {}
+		}
+	}
+	operands.push(&tokens[start..]);
+	operands
+}
+
+enum DataItem {
+	Value(Expr),
+	Str(String),
+}
+
+enum Statement {
+	Instruction {
+		mnemonic: String,
+		operands: Vec<Operand>,
+	},
+	Bytes(Vec<DataItem>),
+	Words(Vec<Expr>),
+}
+
+// Values used while encoding a statement, in the first pass every value is zero
+struct Context<'a> {
+	address: u16,
+	symbols: Option<&'a HashMap<String, u16>>,
+}
+
+impl Context<'_> {
+	fn value(&self, expr: &Expr) -> Result<i64, AssemblerErrorKind> {
+		match self.symbols {
+			Some(symbols) => evaluate(expr, self.address, symbols),
+			None => Ok(0),
+		}
+	}
+
+	fn byte(&self, expr: &Expr) -> Result<u8, AssemblerErrorKind> {
+		let value = self.value(expr)?;
+		match value {
+			-128..=255 => Ok(value as u8),
+			_ => Err(OutOfRange(value)),
+		}
+	}
+
+	fn signed_byte(&self, expr: &Expr) -> Result<u8, AssemblerErrorKind> {
+		let value = self.value(expr)?;
+		match value {
+			-128..=127 => Ok(value as u8),
+			_ => Err(OutOfRange(value)),
+		}
+	}
+
+	fn word(&self, expr: &Expr) -> Result<[u8; 2], AssemblerErrorKind> {
+		let value = self.value(expr)?;
+		match value {
+			-32768..=65535 => Ok((value as u16).to_le_bytes()),
+			_ => Err(OutOfRange(value)),
+		}
+	}
+
+	// Offset of `ldh`, either the full address or just the low byte
+	fn high_page(&self, expr: &Expr) -> Result<u8, AssemblerErrorKind> {
+		let value = self.value(expr)?;
+		match value {
+			0..=0xFF | 0xFF00..=0xFFFF => Ok(value as u8),
+			_ => Err(OutOfRange(value)),
+		}
+	}
+
+	// Relative to the end of the two byte jump
+	fn displacement(&self, target: &Expr) -> Result<u8, AssemblerErrorKind> {
+		if self.symbols.is_none() {
+			return Ok(0);
+		}
+		let displacement = self.value(target)? - (self.address as i64 + 2);
+		match displacement {
+			-128..=127 => Ok(displacement as u8),
+			_ => Err(OutOfRange(displacement)),
+		}
+	}
+}
+
+fn evaluate(
+	expr: &Expr,
+	address: u16,
+	symbols: &HashMap<String, u16>,
+) -> Result<i64, AssemblerErrorKind> {
+	let evaluate = |expr: &Expr| evaluate(expr, address, symbols);
+
+	Ok(match expr {
+		Expr::Number(value) => *value,
+		Expr::Here => address as i64,
+		Expr::Symbol(name) => match symbols.get(name) {
+			Some(value) => *value as i64,
+			None => return Err(UndefinedSymbol(name.clone())),
+		},
+		Expr::High(expr) => (evaluate(expr)? >> 8) & 0xFF,
+		Expr::Low(expr) => evaluate(expr)? & 0xFF,
+		Expr::Unary(operator, expr) => {
+			let value = evaluate(expr)?;
+			match *operator {
+				"-" => value.wrapping_neg(),
+				"~" => !value,
+				_ => value,
+			}
+		}
+		Expr::Binary(operator, lhs, rhs) => {
+			let (lhs, rhs) = (evaluate(lhs)?, evaluate(rhs)?);
+			match *operator {
+				"+" => lhs.wrapping_add(rhs),
+				"-" => lhs.wrapping_sub(rhs),
+				"*" => lhs.wrapping_mul(rhs),
+				"/" => lhs.checked_div(rhs).ok_or(DivisionByZero)?,
+				"%" => lhs.checked_rem(rhs).ok_or(DivisionByZero)?,
+				"&" => lhs & rhs,
+				"|" => lhs | rhs,
+				"^" => lhs ^ rhs,
+				"<<" => lhs.checked_shl(rhs as u32).unwrap_or(0),
+				_ => lhs.checked_shr(rhs as u32).unwrap_or(0),
+			}
+		}
+	})
+}
+
+const ALU_OPERATIONS: [&str; 8] = ["add", "adc", "sub", "sbc", "and", "xor", "or", "cp"];
+const ROT_OPERATIONS: [&str; 8] = ["rlc", "rrc", "rl", "rr", "sla", "sra", "swap", "srl"];
+const BIT_OPERATIONS: [&str; 3] = ["bit", "res", "set"];
+
+fn condition(operand: &Operand) -> Option<u8> {
+	match operand {
+		Operand::Condition(condition) => Some(*condition),
+		Operand::Reg8(1) => Some(3),
+		_ => None,
+	}
+}
+
+// Index in the rp table, used by most 16 bit instructions
+fn register_pair(register: Register16) -> Option<u8> {
+	match register {
+		Register16::BC => Some(0),
+		Register16::DE => Some(1),
+		Register16::HL => Some(2),
+		Register16::SP => Some(3),
+		Register16::AF => None,
+	}
+}
+
+// Index in the rp2 table, used by push and pop
+fn stack_register_pair(register: Register16) -> Option<u8> {
+	match register {
+		Register16::AF => Some(3),
+		Register16::SP => None,
+		register => register_pair(register),
+	}
+}
+
+fn encode(
+	mnemonic: &str,
+	operands: &[Operand],
+	context: &Context,
+) -> Result<Vec<u8>, AssemblerErrorKind> {
+	use Operand::*;
+
+	let invalid = || InvalidOperands(mnemonic.to_owned());
+
+	if let Some(operation) = ALU_OPERATIONS.iter().position(|op| *op == mnemonic) {
+		let operation = operation as u8;
+		match operands {
+			[Reg8(A), source] | [source] => {
+				return match source {
+					Reg8(register) => Ok(vec![0x80 | operation << 3 | register]),
+					Value(value) => Ok(vec![0xC6 | operation << 3, context.byte(value)?]),
+					_ => Err(invalid()),
+				}
+			}
+			_ if mnemonic != "add" => return Err(invalid()),
+			_ => {}
+		}
+	}
+
+	if let Some(operation) = ROT_OPERATIONS.iter().position(|op| *op == mnemonic) {
+		let [Reg8(register)] = operands else {
+			return Err(invalid());
+		};
+		return Ok(vec![0xCB, (operation as u8) << 3 | register]);
+	}
+
+	if let Some(operation) = BIT_OPERATIONS.iter().position(|op| *op == mnemonic) {
+		let [Value(bit), Reg8(register)] = operands else {
+			return Err(invalid());
+		};
+		let bit = match context.value(bit)? {
+			bit @ 0..=7 => bit as u8,
+			bit => return Err(OutOfRange(bit)),
+		};
+		return Ok(vec![0xCB, (operation as u8 + 1) << 6 | bit << 3 | register]);
+	}
+
+	let bytes = match (mnemonic, operands) {
+		("nop", []) => vec![0x00],
+		// Stop is followed by a padding byte
+		("stop", []) => vec![0x10, 0x00],
+		("halt", []) => vec![0x76],
+		("di", []) => vec![0xF3],
+		("ei", []) => vec![0xFB],
+		("rlca", []) => vec![0x07],
+		("rrca", []) => vec![0x0F],
+		("rla", []) => vec![0x17],
+		("rra", []) => vec![0x1F],
+		("daa", []) => vec![0x27],
+		("cpl", []) => vec![0x2F],
+		("scf", []) => vec![0x37],
+		("ccf", []) => vec![0x3F],
+		("reti", []) => vec![0xD9],
+		("ret", []) => vec![0xC9],
+		("ret", [cc]) => vec![0xC0 | condition(cc).ok_or_else(invalid)? << 3],
+
+		("jp", [Reg16(Register16::HL) | Reg8(HL_MEM)]) => vec![0xE9],
+		("jp", [Value(target)]) => [vec![0xC3], context.word(target)?.to_vec()].concat(),
+		("jp", [cc, Value(target)]) => {
+			let cc = condition(cc).ok_or_else(invalid)?;
+			[vec![0xC2 | cc << 3], context.word(target)?.to_vec()].concat()
+		}
+		("call", [Value(target)]) => [vec![0xCD], context.word(target)?.to_vec()].concat(),
+		("call", [cc, Value(target)]) => {
+			let cc = condition(cc).ok_or_else(invalid)?;
+			[vec![0xC4 | cc << 3], context.word(target)?.to_vec()].concat()
+		}
+		("jr", [Value(target)]) => vec![0x18, context.displacement(target)?],
+		("jr", [cc, Value(target)]) => {
+			let cc = condition(cc).ok_or_else(invalid)?;
+			vec![0x20 | cc << 3, context.displacement(target)?]
+		}
+		("rst", [Value(target)]) => match context.value(target)? {
+			target if target & !0x38 == 0 => vec![0xC7 | target as u8],
+			target => return Err(OutOfRange(target)),
+		},
+
+		("push", [Reg16(register)]) => {
+			vec![0xC5 | stack_register_pair(*register).ok_or_else(invalid)? << 4]
+		}
+		("pop", [Reg16(register)]) => {
+			vec![0xC1 | stack_register_pair(*register).ok_or_else(invalid)? << 4]
+		}
+
+		("inc", [Reg8(register)]) => vec![0x04 | register << 3],
+		("dec", [Reg8(register)]) => vec![0x05 | register << 3],
+		("inc", [Reg16(register)]) => {
+			vec![0x03 | register_pair(*register).ok_or_else(invalid)? << 4]
+		}
+		("dec", [Reg16(register)]) => {
+			vec![0x0B | register_pair(*register).ok_or_else(invalid)? << 4]
+		}
+
+		("add", [Reg16(Register16::HL), Reg16(register)]) => {
+			vec![0x09 | register_pair(*register).ok_or_else(invalid)? << 4]
+		}
+		("add", [Reg16(Register16::SP), Value(offset)]) => vec![0xE8, context.signed_byte(offset)?],
+
+		("ldh" | "ld", [MemC, Reg8(A)]) => vec![0xE2],
+		("ldh" | "ld", [Reg8(A), MemC]) => vec![0xF2],
+		("ldh", [MemValue(address), Reg8(A)]) => vec![0xE0, context.high_page(address)?],
+		("ldh", [Reg8(A), MemValue(address)]) => vec![0xF0, context.high_page(address)?],
+
+		("ld", [Reg8(HL_MEM), Reg8(HL_MEM)]) => return Err(invalid()),
+		("ld", [Reg8(dest), Reg8(source)]) => vec![0x40 | dest << 3 | source],
+		("ld", [Reg8(dest), Value(value)]) => vec![0x06 | dest << 3, context.byte(value)?],
+		("ld", [Reg16(Register16::SP), Reg16(Register16::HL)]) => vec![0xF9],
+		("ld", [Reg16(Register16::HL), SpOffset(offset)]) => {
+			vec![0xF8, context.signed_byte(offset)?]
+		}
+		("ld", [Reg16(register), Value(value)]) => {
+			let register = register_pair(*register).ok_or_else(invalid)?;
+			[vec![0x01 | register << 4], context.word(value)?.to_vec()].concat()
+		}
+		("ld", [Mem(Register16::BC), Reg8(A)]) => vec![0x02],
+		("ld", [Mem(Register16::DE), Reg8(A)]) => vec![0x12],
+		("ld", [HlIncrement, Reg8(A)]) => vec![0x22],
+		("ld", [HlDecrement, Reg8(A)]) => vec![0x32],
+		("ld", [Reg8(A), Mem(Register16::BC)]) => vec![0x0A],
+		("ld", [Reg8(A), Mem(Register16::DE)]) => vec![0x1A],
+		("ld", [Reg8(A), HlIncrement]) => vec![0x2A],
+		("ld", [Reg8(A), HlDecrement]) => vec![0x3A],
+		("ld", [MemValue(address), Reg16(Register16::SP)]) => {
+			[vec![0x08], context.word(address)?.to_vec()].concat()
+		}
+		("ld", [MemValue(address), Reg8(A)]) => {
+			[vec![0xEA], context.word(address)?.to_vec()].concat()
+		}
+		("ld", [Reg8(A), MemValue(address)]) => {
+			[vec![0xFA], context.word(address)?.to_vec()].concat()
+		}
+
+		(
+			"nop" | "stop" | "halt" | "di" | "ei" | "rlca" | "rrca" | "rla" | "rra" | "daa" | "cpl"
+			| "scf" | "ccf" | "reti" | "ret" | "jp" | "call" | "jr" | "rst" | "push" | "pop"
+			| "inc" | "dec" | "add" | "ld" | "ldh",
+			_,
+		) => return Err(invalid()),
+		_ => return Err(UnknownMnemonic(mnemonic.to_owned())),
+	};
+
+	Ok(bytes)
+}
+
+fn encode_statement(
+	statement: &Statement,
+	context: &Context,
+) -> Result<Vec<u8>, AssemblerErrorKind> {
+	match statement {
+		Statement::Instruction { mnemonic, operands } => encode(mnemonic, operands, context),
+		Statement::Bytes(items) => {
+			let mut bytes = vec![];
+			for item in items {
+				match item {
+					DataItem::Value(value) => bytes.push(context.byte(value)?),
+					DataItem::Str(string) => bytes.extend_from_slice(string.as_bytes()),
+				}
+			}
+			Ok(bytes)
+		}
+		Statement::Words(values) => {
+			let mut bytes = vec![];
+			for value in values {
+				bytes.extend_from_slice(&context.word(value)?);
+			}
+			Ok(bytes)
+		}
+	}
+}
+
+fn parse_statement(tokens: &[Token], scope: &str) -> Result<Statement, AssemblerErrorKind> {
+	let Some((Token::Ident(mnemonic), operands)) = tokens.split_first() else {
+		return Err(syntax("expected an instruction"));
+	};
+	let mnemonic = mnemonic.to_ascii_lowercase();
+	let operands = split_operands(operands);
+
+	Ok(match mnemonic.as_str() {
+		"db" => Statement::Bytes(
+			operands
+				.into_iter()
+				.map(|operand| match operand {
+					[Token::Str(string)] => Ok(DataItem::Str(string.clone())),
+					operand => Ok(DataItem::Value(parse_expr(operand, scope)?)),
+				})
+				.collect::<Result<_, _>>()?,
+		),
+		"dw" => Statement::Words(
+			operands
+				.into_iter()
+				.map(|operand| parse_expr(operand, scope))
+				.collect::<Result<_, _>>()?,
+		),
+		_ => Statement::Instruction {
+			operands: operands
+				.into_iter()
+				.map(|operand| parse_operand(operand, scope))
+				.collect::<Result<_, _>>()?,
+			mnemonic,
+		},
+	})
+}
+
+/// Assembles `source` into bytes which are placed at `origin`
+pub fn assemble(source: &str, origin: u16) -> Result<Program, AssemblerError> {
+	let mut symbols = HashMap::new();
+	let mut statements = vec![];
+	let mut scope = String::new();
+	let mut address = origin;
+
+	for (index, line) in source.lines().enumerate() {
+		let error = |kind| AssemblerError {
+			line: index + 1,
+			kind,
+		};
+		let tokens = tokenize(line).map_err(error)?;
+		let mut tokens = tokens.as_slice();
+
+		// Labels, the colon is optional for local labels
+		while let [Token::Ident(name), rest @ ..] = tokens {
+			let rest = match rest {
+				[Token::Punct(":" | "::"), rest @ ..] => rest,
+				rest if name.starts_with('.') => rest,
+				_ => break,
+			};
+
+			let label = qualify(name, &scope);
+			if !name.contains('.') {
+				scope = name.clone();
+			}
+			if symbols.insert(label.clone(), address).is_some() {
+				return Err(error(DuplicateLabel(label)));
+			}
+			tokens = rest;
+		}
+
+		if tokens.is_empty() {
+			continue;
+		}
+
+		let statement = parse_statement(tokens, &scope).map_err(error)?;
+		let context = Context {
+			address,
+			symbols: None,
+		};
+		let size = encode_statement(&statement, &context).map_err(error)?.len();
+
+		statements.push((index + 1, address, statement));
+		address = address.wrapping_add(size as u16);
+	}
+
+	let mut bytes = vec![];
+	for (line, address, statement) in &statements {
+		let context = Context {
+			address: *address,
+			symbols: Some(&symbols),
+		};
+		let encoded = encode_statement(statement, &context)
+			.map_err(|kind| AssemblerError { line: *line, kind })?;
+		bytes.extend(encoded);
+	}
+
+	Ok(Program { bytes, symbols })
+}
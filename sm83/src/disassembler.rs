@@ -0,0 +1,214 @@
+// Disassembler over plain bytes, doesn't need a running emulator
+//
+// Decoding reuses `Fetch` on a read only view of the bytes, so it always
+// agrees with what the CPU executes.
+
+use std::{collections::HashMap, fmt::Write};
+
+use crate::{
+	instruction::{Fetch, Instruction},
+	memory_mapper::{MemoryMapper, Source, SourcedMemoryMapper},
+	registers::{Addressable, CPURegister16},
+	values::{ValueRefI8, ValueRefU16, ValueRefU8},
+	CPUState, Condition, SM83,
+};
+
+// Bytes past the end of the slice read as zero, `decode` rejects instructions using them
+struct ByteReader<'a> {
+	bytes: &'a [u8],
+	cpu_state: CPUState,
+}
+
+impl MemoryMapper for ByteReader<'_> {
+	fn read(&self, addr: u16) -> u8 {
+		self.bytes.get(addr as usize).copied().unwrap_or_default()
+	}
+
+	fn write(&mut self, _addr: u16, _value: u8) {}
+}
+
+impl SourcedMemoryMapper for ByteReader<'_> {
+	fn read_from(&self, addr: u16, _source: Source) -> u8 {
+		self.read(addr)
+	}
+
+	fn write_from(&mut self, _addr: u16, _value: u8, _source: Source) {}
+}
+
+impl SM83 for ByteReader<'_> {
+	fn cpu_state(&self) -> &CPUState {
+		&self.cpu_state
+	}
+
+	fn cpu_state_mut(&mut self) -> &mut CPUState {
+		&mut self.cpu_state
+	}
+}
+
+/// Decodes the instruction at the start of `bytes`, along with its length in bytes.
+/// Returns `None` when `bytes` ends before the instruction does.
+pub fn decode(bytes: &[u8]) -> Option<(Instruction, usize)> {
+	let mut reader = ByteReader {
+		bytes: &bytes[..bytes.len().min(4)],
+		cpu_state: CPUState::default(),
+	};
+
+	let instruction = reader.fetch();
+	let length = reader.cpu_state.read(CPURegister16::PC) as usize;
+	(length <= bytes.len()).then_some((instruction, length))
+}
+
+pub struct DisassembledInstruction<'a> {
+	pub address: u16,
+	pub instruction: Instruction,
+	pub bytes: &'a [u8],
+}
+
+/// Disassembles `bytes`, which are mapped starting at `address`.
+/// Bytes of an instruction cut off by the end are returned as `Instruction::ERROR`.
+pub fn disassemble(
+	bytes: &[u8],
+	address: u16,
+) -> impl Iterator<Item = DisassembledInstruction<'_>> + '_ {
+	let mut offset = 0;
+	std::iter::from_fn(move || {
+		let rest = bytes.get(offset..).filter(|rest| !rest.is_empty())?;
+		let (instruction, length) = decode(rest).unwrap_or((Instruction::ERROR(rest[0]), 1));
+
+		let disassembled = DisassembledInstruction {
+			address: address.wrapping_add(offset as u16),
+			instruction,
+			bytes: &rest[..length],
+		};
+		offset += length;
+		Some(disassembled)
+	})
+}
+
+/// Formats instructions as RGBDS compatible assembly
+#[derive(Default)]
+pub struct Formatter {
+	symbols: HashMap<u16, String>,
+	raw_bytes: bool,
+}
+
+impl Formatter {
+	/// Names used for the targets of jumps and calls
+	pub fn with_symbols(mut self, symbols: HashMap<u16, String>) -> Self {
+		self.symbols = symbols;
+		self
+	}
+
+	/// Appends the address and bytes of every instruction as a comment
+	pub fn with_raw_bytes(mut self, raw_bytes: bool) -> Self {
+		self.raw_bytes = raw_bytes;
+		self
+	}
+
+	fn target(&self, address: u16) -> String {
+		match self.symbols.get(&address) {
+			Some(symbol) => symbol.clone(),
+			None => format!("${address:04X}"),
+		}
+	}
+
+	/// Formats a single instruction, `next_address` is the address after it,
+	/// which relative jumps are based on
+	pub fn format_instruction(&self, instruction: &Instruction, next_address: u16) -> String {
+		use Instruction::*;
+
+		let condition = |condition: &Condition| match condition {
+			Condition::Always => String::new(),
+			condition => format!("{condition:?}, "),
+		};
+
+		match instruction {
+			ERROR(byte) => format!("db ${byte:02X}"),
+			LD_8(dest, src) => format!("ld {}, {}", u8_operand(dest), u8_operand(src)),
+			LDH(dest, src) => format!("ldh {}, {}", u8_operand(dest), u8_operand(src)),
+			LD_16(dest, src) => format!("ld {}, {}", u16_operand(dest), u16_operand(src)),
+			INC_8(value) => format!("inc {}", u8_operand(value)),
+			DEC_8(value) => format!("dec {}", u8_operand(value)),
+			INC_16(value) => format!("inc {}", u16_operand(value)),
+			DEC_16(value) => format!("dec {}", u16_operand(value)),
+			ADD_16(dest, src) => format!("add {}, {}", u16_operand(dest), u16_operand(src)),
+			ADD_SIGNED(dest, offset) => format!("add {}, {offset:?}", u16_operand(dest)),
+			LD_HL_SP_DD(ValueRefI8(offset)) if *offset < 0 => {
+				format!("ld hl, sp - ${:02X}", offset.unsigned_abs())
+			}
+			LD_HL_SP_DD(ValueRefI8(offset)) => format!("ld hl, sp + ${offset:02X}"),
+			ALU_OP_8(operation, value) => format!("{operation:?} a, {}", u8_operand(value)),
+			ROT(operation, value) => format!("{operation:?} {}", u8_operand(value)),
+			BIT(bit, value) => format!("bit {bit}, {}", u8_operand(value)),
+			RES(bit, value) => format!("res {bit}, {}", u8_operand(value)),
+			SET(bit, value) => format!("set {bit}, {}", u8_operand(value)),
+
+			JR(cond, ValueRefI8(offset)) => {
+				let target = next_address.wrapping_add_signed(*offset as i16);
+				format!("jr {}{}", condition(cond), self.target(target))
+			}
+			JP(cond, ValueRefU16::Raw(target)) => {
+				format!("jp {}{}", condition(cond), self.target(*target))
+			}
+			JP(cond, target) => format!("jp {}{}", condition(cond), u16_operand(target)),
+			CALL(cond, ValueRefU16::Raw(target)) => {
+				format!("call {}{}", condition(cond), self.target(*target))
+			}
+			CALL(cond, target) => format!("call {}{}", condition(cond), u16_operand(target)),
+			RST(ValueRefU16::Raw(target)) => match self.symbols.get(target) {
+				Some(symbol) => format!("rst {symbol}"),
+				None => format!("rst ${:02X}", *target as u8),
+			},
+
+			// Remaining instructions have no operands needing a different syntax
+			instruction => format!("{instruction:?}"),
+		}
+	}
+
+	/// Formats a line of a listing
+	pub fn format(&self, instruction: &DisassembledInstruction) -> String {
+		let next_address = instruction
+			.address
+			.wrapping_add(instruction.bytes.len() as u16);
+		let mut line = self.format_instruction(&instruction.instruction, next_address);
+
+		if self.raw_bytes {
+			let bytes = instruction
+				.bytes
+				.iter()
+				.map(|byte| format!("{byte:02X}"))
+				.collect::<Vec<_>>()
+				.join(" ");
+			line = format!("{line:<24}; ${:04X}: {bytes}", instruction.address);
+		}
+		line
+	}
+
+	/// Disassembles `bytes` mapped at `address` to a listing,
+	/// symbols within the listing are placed as labels
+	pub fn listing(&self, bytes: &[u8], address: u16) -> String {
+		let mut listing = String::new();
+		for instruction in disassemble(bytes, address) {
+			if let Some(symbol) = self.symbols.get(&instruction.address) {
+				_ = writeln!(listing, "{symbol}:");
+			}
+			_ = writeln!(listing, "\t{}", self.format(&instruction));
+		}
+		listing
+	}
+}
+
+fn u8_operand(value: &ValueRefU8) -> String {
+	match value {
+		ValueRefU8::Mem(address) => format!("[{}]", u16_operand(address)),
+		ValueRefU8::MemOffsetRaw(offset) => format!("[$FF{offset:02X}]"),
+		value => format!("{value:?}"),
+	}
+}
+
+fn u16_operand(value: &ValueRefU16) -> String {
+	match value {
+		ValueRefU16::Mem(address) => format!("[${address:04X}]"),
+		value => format!("{value:?}"),
+	}
+}
@@ -2,13 +2,12 @@
 // https://gb-archive.github.io/salvage/decoding_gbz80_opcodes/Decoding%20Gamboy%20Z80%20Opcodes.html
 
 mod decode_tables;
+mod encode;
 mod execute;
 mod fetch;
 pub use execute::Execute;
 pub use fetch::Fetch;
 
-#[macro_use]
-pub mod mac_instruction;
 pub mod opcode;
 
 use super::Condition;
@@ -20,7 +19,7 @@ use super::{
 };
 
 #[allow(non_camel_case_types)]
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum Instruction {
 	NOP,
 	STOP,
@@ -71,7 +70,7 @@ pub enum Instruction {
 	LD_DEC_HL_A,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 #[allow(clippy::upper_case_acronyms)]
 pub enum ALUOperation {
 	ADD,
@@ -84,7 +83,7 @@ pub enum ALUOperation {
 	CP,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 #[allow(clippy::upper_case_acronyms)]
 pub enum RotShiftOperation {
 	RLC,
@@ -127,12 +126,22 @@ impl Debug for ALUOperation {
 	}
 }
 
+// Jump targets are relative to the start of the instruction, `@` in RGBDS syntax
+fn relative_target(ValueRefI8(displacement): &ValueRefI8) -> String {
+	let offset = *displacement as i16 + 2;
+	if offset >= 0 {
+		format!("@+${offset:02X}")
+	} else {
+		format!("@-${:02X}", offset.unsigned_abs())
+	}
+}
+
 impl Debug for Instruction {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		match self {
 			Self::NOP => write!(f, "nop"),
 			Self::STOP => write!(f, "stop"),
-			Self::ERROR(arg0) => f.debug_tuple("error").field(arg0).finish(),
+			Self::ERROR(arg0) => write!(f, "db ${arg0:02X}"),
 			Self::LD_8(arg0, arg1) => write!(f, "ld {arg0:?}, {arg1:?}"),
 			Self::LDH(arg0, arg1) => write!(f, "ldh {arg0:?}, {arg1:?}"),
 			Self::LD_16(arg0, arg1) => write!(f, "ld {arg0:?}, {arg1:?}"),
@@ -140,8 +149,8 @@ impl Debug for Instruction {
 			Self::INC_16(arg0) => write!(f, "inc {arg0:?}"),
 			Self::DEC_8(arg0) => write!(f, "dec {arg0:?}"),
 			Self::DEC_16(arg0) => write!(f, "dec {arg0:?}"),
-			Self::JR(Condition::Always, arg1) => write!(f, "jr {arg1:?}"),
-			Self::JR(arg0, arg1) => write!(f, "jr {arg0:?}, {arg1:?}"),
+			Self::JR(Condition::Always, arg1) => write!(f, "jr {}", relative_target(arg1)),
+			Self::JR(arg0, arg1) => write!(f, "jr {arg0:?}, {}", relative_target(arg1)),
 			Self::ADD_16(arg0, arg1) => write!(f, "add {arg0:?}, {arg1:?}"),
 			Self::ADD_SIGNED(arg0, arg1) => write!(f, "add {arg0:?}, {arg1:?}"),
 			Self::ALU_OP_8(a0, a1) => write!(f, "{a0:?} a, {a1:?}"),
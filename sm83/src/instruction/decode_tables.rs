@@ -1,11 +1,13 @@
 use super::{
+	opcode::{parse_opcode, Opcode},
 	ALUOperation::{self, *},
 	CPURegister16,
 	CPURegister16::*,
 	CPURegister8::*,
 	Condition,
+	Instruction::{self, *},
 	RotShiftOperation::{self, *},
-	ValueRefU16,
+	ValueRefI8, ValueRefU16,
 	ValueRefU8::{self, *},
 };
 
@@ -34,3 +36,164 @@ pub const DT: DecodeTables = DecodeTables {
 	alu: [ADD, ADC, SUB, SBC, AND, XOR, OR, CP],
 	rot: [RLC, RRC, RL, RR, SLA, SRA, SWAP, SRL],
 };
+
+/// What follows an opcode in the base table
+#[derive(Clone, Copy)]
+pub enum Decoded {
+	Complete(Instruction),
+	// The placeholder operand of the template is filled in with the immediate after the opcode
+	Byte(Instruction),
+	Displacement(Instruction),
+	Word(Instruction),
+	// 0xCB, the next byte is looked up in `CB`
+	Prefix,
+}
+
+pub static BASE: [Decoded; 256] = {
+	let mut table = [Decoded::Complete(NOP); 256];
+	let mut raw = 0;
+	while raw < 256 {
+		table[raw] = decode_base(raw as u8);
+		raw += 1;
+	}
+	table
+};
+
+pub static CB: [Instruction; 256] = {
+	let mut table = [NOP; 256];
+	let mut raw = 0;
+	while raw < 256 {
+		table[raw] = decode_cb(raw as u8);
+		raw += 1;
+	}
+	table
+};
+
+const fn decode_base(raw: u8) -> Decoded {
+	use Decoded::*;
+	use ValueRefU16 as U16;
+
+	let Opcode(x, z, y, p, q) = parse_opcode(raw);
+	match (x, z, y, p, q) {
+		(0, 0, 0, _, _) => Complete(NOP),
+		(0, 0, 1, _, _) => Word(LD_16(U16::Mem(0), U16::Reg(SP))),
+		(0, 0, 2, _, _) => Complete(STOP),
+
+		(0, 0, 3, _, _) => Displacement(JR(Condition::Always, ValueRefI8(0))),
+		(0, 0, _, _, _) => Displacement(JR(DT.cc[y - 4], ValueRefI8(0))),
+
+		(0, 1, _, _, 0) => Word(LD_16(U16::Reg(DT.rp[p]), U16::Raw(0))),
+		(0, 1, _, _, 1) => Complete(ADD_16(U16::Reg(HL), U16::Reg(DT.rp[p]))),
+
+		(0, 2, _, 0, 0) => Complete(LD_8(Mem(U16::Reg(BC)), Reg(A))),
+		(0, 2, _, 1, 0) => Complete(LD_8(Mem(U16::Reg(DE)), Reg(A))),
+		(0, 2, _, 2, 0) => Complete(LD_INC_HL_A),
+		(0, 2, _, 3, 0) => Complete(LD_DEC_HL_A),
+
+		(0, 2, _, 0, 1) => Complete(LD_8(Reg(A), Mem(U16::Reg(BC)))),
+		(0, 2, _, 1, 1) => Complete(LD_8(Reg(A), Mem(U16::Reg(DE)))),
+		(0, 2, _, 2, 1) => Complete(LD_A_INC_HL),
+		(0, 2, _, 3, 1) => Complete(LD_A_DEC_HL),
+
+		(0, 3, _, _, 0) => Complete(INC_16(U16::Reg(DT.rp[p]))),
+		(0, 3, _, _, 1) => Complete(DEC_16(U16::Reg(DT.rp[p]))),
+		(0, 4, _, _, _) => Complete(INC_8(DT.r[y])),
+		(0, 5, _, _, _) => Complete(DEC_8(DT.r[y])),
+		(0, 6, _, _, _) => Byte(LD_8(DT.r[y], Raw(0))),
+
+		(0, 7, 0, _, _) => Complete(RLCA),
+		(0, 7, 1, _, _) => Complete(RRCA),
+		(0, 7, 2, _, _) => Complete(RLA),
+		(0, 7, 3, _, _) => Complete(RRA),
+		(0, 7, 4, _, _) => Complete(DAA),
+		(0, 7, 5, _, _) => Complete(CPL),
+		(0, 7, 6, _, _) => Complete(SCF),
+		(0, 7, _, _, _) => Complete(CCF),
+
+		(1, 6, 6, _, _) => Complete(HALT),
+		(1, _, _, _, _) => Complete(LD_8(DT.r[y], DT.r[z])),
+
+		(2, _, _, _, _) => Complete(ALU_OP_8(DT.alu[y], DT.r[z])),
+
+		(3, 0, 0..=3, _, _) => Complete(RET(DT.cc[y])),
+		(3, 0, 4, _, _) => Byte(LDH(MemOffsetRaw(0), Reg(A))),
+		(3, 0, 5, _, _) => Displacement(ADD_SIGNED(U16::Reg(SP), ValueRefI8(0))),
+		(3, 0, 6, _, _) => Byte(LDH(Reg(A), MemOffsetRaw(0))),
+		(3, 0, _, _, _) => Displacement(LD_HL_SP_DD(ValueRefI8(0))),
+
+		(3, 1, _, 3, 0) => Complete(POP(AF)),
+		(3, 1, _, _, 0) => Complete(POP(DT.rp[p])),
+		(3, 1, _, 0, 1) => Complete(RET(Condition::Always)),
+		(3, 1, _, 1, 1) => Complete(RETI),
+		(3, 1, _, 2, 1) => Complete(JP(Condition::Always, U16::Reg(HL))),
+		(3, 1, _, _, _) => Complete(LD_16(U16::Reg(SP), U16::Reg(HL))),
+
+		(3, 2, 0..=3, _, _) => Word(JP(DT.cc[y], U16::Raw(0))),
+		(3, 2, 4, _, _) => Complete(LDH(MemOffsetReg(C), Reg(A))),
+		(3, 2, 5, _, _) => Word(LD_8(Mem(U16::Raw(0)), Reg(A))),
+		(3, 2, 6, _, _) => Complete(LDH(Reg(A), MemOffsetReg(C))),
+		(3, 2, _, _, _) => Word(LD_8(Reg(A), Mem(U16::Raw(0)))),
+
+		(3, 3, 0, _, _) => Word(JP(Condition::Always, U16::Raw(0))),
+		(3, 3, 1, _, _) => Prefix,
+		(3, 3, 6, _, _) => Complete(DI),
+		(3, 3, 7, _, _) => Complete(EI),
+
+		(3, 4, 0..=3, _, _) => Word(CALL(DT.cc[y], U16::Raw(0))),
+
+		(3, 5, _, 3, 0) => Complete(PUSH(AF)),
+		(3, 5, _, _, 0) => Complete(PUSH(DT.rp[p])),
+		(3, 5, _, 0, 1) => Word(CALL(Condition::Always, U16::Raw(0))),
+
+		(3, 6, _, _, _) => Byte(ALU_OP_8(DT.alu[y], Raw(0))),
+		(3, 7, _, _, _) => Complete(RST(U16::Raw(y as u16 * 8))),
+		(_, _, _, _, _) => Complete(ERROR(raw)),
+	}
+}
+
+const fn decode_cb(raw: u8) -> Instruction {
+	let Opcode(x, z, y, _, _) = parse_opcode(raw);
+	match x {
+		0 => ROT(DT.rot[y], DT.r[z]),
+		1 => BIT(y as u8, DT.r[z]),
+		2 => RES(y as u8, DT.r[z]),
+		_ => SET(y as u8, DT.r[z]),
+	}
+}
+
+impl Instruction {
+	// Fills in the placeholder operands of `Decoded` templates
+
+	pub(super) fn with_byte(self, value: u8) -> Self {
+		match self {
+			LD_8(dest, Raw(_)) => LD_8(dest, Raw(value)),
+			LDH(MemOffsetRaw(_), source) => LDH(MemOffsetRaw(value), source),
+			LDH(dest, MemOffsetRaw(_)) => LDH(dest, MemOffsetRaw(value)),
+			ALU_OP_8(operation, Raw(_)) => ALU_OP_8(operation, Raw(value)),
+			_ => self,
+		}
+	}
+
+	pub(super) fn with_displacement(self, value: i8) -> Self {
+		match self {
+			JR(condition, _) => JR(condition, ValueRefI8(value)),
+			ADD_SIGNED(dest, _) => ADD_SIGNED(dest, ValueRefI8(value)),
+			LD_HL_SP_DD(_) => LD_HL_SP_DD(ValueRefI8(value)),
+			_ => self,
+		}
+	}
+
+	pub(super) fn with_word(self, value: u16) -> Self {
+		use ValueRefU16 as U16;
+
+		match self {
+			LD_16(U16::Mem(_), source) => LD_16(U16::Mem(value), source),
+			LD_16(dest, U16::Raw(_)) => LD_16(dest, U16::Raw(value)),
+			LD_8(Mem(U16::Raw(_)), source) => LD_8(Mem(U16::Raw(value)), source),
+			LD_8(dest, Mem(U16::Raw(_))) => LD_8(dest, Mem(U16::Raw(value))),
+			JP(condition, _) => JP(condition, U16::Raw(value)),
+			CALL(condition, _) => CALL(condition, U16::Raw(value)),
+			_ => self,
+		}
+	}
+}
@@ -0,0 +1,160 @@
+// Encodes instructions back to the bytes `Fetch` decodes them from,
+// the opcode layout mirrors the decode tables
+
+use super::{
+	decode_tables::DT,
+	CPURegister16::{self, *},
+	CPURegister8, Condition, Instruction,
+	Instruction::*,
+	ValueRefI8, ValueRefU16, ValueRefU8,
+};
+
+// Index in `DT.r`
+fn r(value: &ValueRefU8) -> Option<u8> {
+	match value {
+		ValueRefU8::Reg(CPURegister8::F) => None,
+		ValueRefU8::Reg(register) => {
+			DT.r.iter()
+				.position(|r| *r == ValueRefU8::Reg(*register))
+				.map(|index| index as u8)
+		}
+		ValueRefU8::Mem(ValueRefU16::Reg(HL)) => Some(6),
+		_ => None,
+	}
+}
+
+// Index in `DT.rp`
+fn rp(value: &ValueRefU16) -> Option<u8> {
+	match value {
+		ValueRefU16::Reg(register) => rp_register(*register),
+		_ => None,
+	}
+}
+
+fn rp_register(register: CPURegister16) -> Option<u8> {
+	DT.rp
+		.iter()
+		.position(|rp| *rp == register)
+		.map(|index| index as u8)
+}
+
+// Index in the table of push and pop, where AF takes the place of SP
+fn rp2(register: CPURegister16) -> Option<u8> {
+	match register {
+		AF => Some(3),
+		SP => None,
+		register => rp_register(register),
+	}
+}
+
+// Index in `DT.cc`, `None` for unconditional
+fn cc(condition: &Condition) -> Option<u8> {
+	DT.cc
+		.iter()
+		.position(|cc| cc == condition)
+		.map(|index| index as u8)
+}
+
+fn with_word(opcode: u8, value: u16) -> Vec<u8> {
+	let [low, high] = value.to_le_bytes();
+	vec![opcode, low, high]
+}
+
+impl Instruction {
+	/// Encodes the instruction to the bytes it is fetched from.
+	/// Returns `None` for `INT` and operand combinations the CPU has no opcode for.
+	pub fn encode(&self) -> Option<Vec<u8>> {
+		use ValueRefU16 as U16;
+		use ValueRefU8 as U8;
+
+		let bytes = match *self {
+			NOP => vec![0x00],
+			// Followed by a padding byte, like RGBDS emits it
+			STOP => vec![0x10, 0x00],
+			HALT => vec![0x76],
+			DI => vec![0xF3],
+			EI => vec![0xFB],
+			RETI => vec![0xD9],
+			RLCA => vec![0x07],
+			RRCA => vec![0x0F],
+			RLA => vec![0x17],
+			RRA => vec![0x1F],
+			DAA => vec![0x27],
+			CPL => vec![0x2F],
+			SCF => vec![0x37],
+			CCF => vec![0x3F],
+			LD_INC_HL_A => vec![0x22],
+			LD_DEC_HL_A => vec![0x32],
+			LD_A_INC_HL => vec![0x2A],
+			LD_A_DEC_HL => vec![0x3A],
+			ERROR(opcode) => vec![opcode],
+			INT => return None,
+
+			LD_8(U8::Mem(U16::Reg(BC)), U8::Reg(CPURegister8::A)) => vec![0x02],
+			LD_8(U8::Mem(U16::Reg(DE)), U8::Reg(CPURegister8::A)) => vec![0x12],
+			LD_8(U8::Reg(CPURegister8::A), U8::Mem(U16::Reg(BC))) => vec![0x0A],
+			LD_8(U8::Reg(CPURegister8::A), U8::Mem(U16::Reg(DE))) => vec![0x1A],
+			LD_8(U8::Mem(U16::Raw(address)), U8::Reg(CPURegister8::A)) => with_word(0xEA, address),
+			LD_8(U8::Reg(CPURegister8::A), U8::Mem(U16::Raw(address))) => with_word(0xFA, address),
+			LD_8(dest, U8::Raw(value)) => vec![0x06 | r(&dest)? << 3, value],
+			LD_8(dest, source) => match (r(&dest)?, r(&source)?) {
+				(6, 6) => return None,
+				(dest, source) => vec![0x40 | dest << 3 | source],
+			},
+
+			LDH(U8::MemOffsetRaw(offset), U8::Reg(CPURegister8::A)) => vec![0xE0, offset],
+			LDH(U8::Reg(CPURegister8::A), U8::MemOffsetRaw(offset)) => vec![0xF0, offset],
+			LDH(U8::MemOffsetReg(CPURegister8::C), U8::Reg(CPURegister8::A)) => vec![0xE2],
+			LDH(U8::Reg(CPURegister8::A), U8::MemOffsetReg(CPURegister8::C)) => vec![0xF2],
+			LDH(_, _) => return None,
+
+			LD_16(U16::Mem(address), U16::Reg(SP)) => with_word(0x08, address),
+			LD_16(U16::Reg(SP), U16::Reg(HL)) => vec![0xF9],
+			LD_16(dest, U16::Raw(value)) => with_word(0x01 | rp(&dest)? << 4, value),
+			LD_16(_, _) => return None,
+
+			INC_8(value) => vec![0x04 | r(&value)? << 3],
+			DEC_8(value) => vec![0x05 | r(&value)? << 3],
+			INC_16(value) => vec![0x03 | rp(&value)? << 4],
+			DEC_16(value) => vec![0x0B | rp(&value)? << 4],
+
+			ADD_16(U16::Reg(HL), value) => vec![0x09 | rp(&value)? << 4],
+			ADD_16(_, _) => return None,
+			ADD_SIGNED(U16::Reg(SP), ValueRefI8(offset)) => vec![0xE8, offset as u8],
+			ADD_SIGNED(_, _) => return None,
+			LD_HL_SP_DD(ValueRefI8(offset)) => vec![0xF8, offset as u8],
+
+			ALU_OP_8(operation, U8::Raw(value)) => vec![0xC6 | (operation as u8) << 3, value],
+			ALU_OP_8(operation, value) => vec![0x80 | (operation as u8) << 3 | r(&value)?],
+
+			JR(Condition::Always, ValueRefI8(offset)) => vec![0x18, offset as u8],
+			JR(condition, ValueRefI8(offset)) => vec![0x20 | cc(&condition)? << 3, offset as u8],
+
+			JP(Condition::Always, U16::Reg(HL)) => vec![0xE9],
+			JP(Condition::Always, U16::Raw(address)) => with_word(0xC3, address),
+			JP(condition, U16::Raw(address)) => with_word(0xC2 | cc(&condition)? << 3, address),
+			JP(_, _) => return None,
+
+			CALL(Condition::Always, U16::Raw(address)) => with_word(0xCD, address),
+			CALL(condition, U16::Raw(address)) => with_word(0xC4 | cc(&condition)? << 3, address),
+			CALL(_, _) => return None,
+
+			RET(Condition::Always) => vec![0xC9],
+			RET(condition) => vec![0xC0 | cc(&condition)? << 3],
+			RST(U16::Raw(target)) if target & !0x38 == 0 => vec![0xC7 | target as u8],
+			RST(_) => return None,
+
+			PUSH(register) => vec![0xC5 | rp2(register)? << 4],
+			POP(register) => vec![0xC1 | rp2(register)? << 4],
+
+			// CB prefixed
+			ROT(operation, value) => vec![0xCB, (operation as u8) << 3 | r(&value)?],
+			BIT(bit, value) if bit < 8 => vec![0xCB, 0x40 | bit << 3 | r(&value)?],
+			RES(bit, value) if bit < 8 => vec![0xCB, 0x80 | bit << 3 | r(&value)?],
+			SET(bit, value) if bit < 8 => vec![0xCB, 0xC0 | bit << 3 | r(&value)?],
+			BIT(..) | RES(..) | SET(..) => return None,
+		};
+
+		Some(bytes)
+	}
+}
@@ -99,6 +99,7 @@ impl<T: SM83> Execute for T {
 			}
 
 			STOP => cpu.exec_stop(),
+			// Locks up the CPU, see `SM83::step_cpu`
 			ERROR(_) => {}
 
 			JR(condition, ValueRefI8(offset)) => {
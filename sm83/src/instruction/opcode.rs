@@ -1,6 +1,6 @@
 pub struct Opcode(pub usize, pub usize, pub usize, pub usize, pub usize);
 
-pub fn parse_opcode(raw: u8) -> Opcode {
+pub const fn parse_opcode(raw: u8) -> Opcode {
 	let i = raw as usize;
 	let x = (i >> 6) & 0b11;
 	let z = i & 0b111;
@@ -1,5 +1,7 @@
+pub mod assembler;
 mod bits;
 mod cpu;
+pub mod disassembler;
 pub mod instruction;
 pub mod memory_mapper;
 pub mod registers;
@@ -10,7 +12,7 @@ pub use instruction::Instruction;
 use instruction::{Execute, Fetch};
 use memory_mapper::{Source, SourcedMemoryMapper};
 use registers::{Addressable, CPURegister16};
-pub use state::CPUState;
+pub use state::{CPULock, CPUState};
 
 use values::{ValueRefU16, ValueRefU8};
 
@@ -39,6 +41,10 @@ pub trait SM83: SourcedMemoryMapper + Sized {
 			self.cpu_state().read(CPURegister16::PC).into(),
 		));
 
+		if std::mem::take(&mut self.cpu_state_mut().halt_bug) {
+			return value;
+		}
+
 		let next_pc = self.cpu_state().read(CPURegister16::PC).wrapping_add(1);
 		self.cpu_state_mut().write(CPURegister16::PC, next_pc);
 		value
@@ -112,12 +118,13 @@ pub trait SM83: SourcedMemoryMapper + Sized {
 
 	fn write_16(&mut self, value_ref: ValueRefU16, value: u16) {
 		match value_ref {
+			// Low byte first, to the lower address, matching the per cycle bus activity of LD (a16), SP
 			ValueRefU16::Mem(i) => {
-				let [lsb, msb] = u16::to_be_bytes(value);
+				let [lsb, msb] = u16::to_le_bytes(value);
 				self.tick_m_cycles(1);
-				self.write_from(i.wrapping_add(1), lsb, Source::Cpu);
+				self.write_from(i, lsb, Source::Cpu);
 				self.tick_m_cycles(1);
-				self.write_from(i, msb, Source::Cpu);
+				self.write_from(i.wrapping_add(1), msb, Source::Cpu);
 			}
 			ValueRefU16::Reg(reg) => self.cpu_state_mut().write(reg, value),
 			ValueRefU16::Raw(_) => unreachable!(),
@@ -136,6 +143,12 @@ pub trait SM83: SourcedMemoryMapper + Sized {
 
 	fn get_next_instruction_or_interrupt(&mut self) -> Instruction {
 		if self.cpu_state().get_pending_interrupt().is_some() {
+			// Dispatch undoes the PC increment of the opcode fetch. After the HALT bug
+			// there was none, so the handler returns to the HALT itself
+			if std::mem::take(&mut self.cpu_state_mut().halt_bug) {
+				let pc = self.cpu_state().read(CPURegister16::PC).wrapping_sub(1);
+				self.cpu_state_mut().write(CPURegister16::PC, pc);
+			}
 			Instruction::INT
 		} else {
 			self.fetch()
@@ -146,6 +159,12 @@ pub trait SM83: SourcedMemoryMapper + Sized {
 	where
 		Self: Sized,
 	{
+		// Only a reset gets the CPU out of a lock up, not even interrupts
+		if self.cpu_state().locked.is_some() {
+			self.tick_m_cycles(1);
+			return None;
+		}
+
 		if self.cpu_state().halted {
 			if self.cpu_state().interrupt_pending() {
 				self.cpu_state_mut().halted = false;
@@ -155,8 +174,25 @@ pub trait SM83: SourcedMemoryMapper + Sized {
 			}
 		}
 
+		// IME as seen by this instruction, EI only takes effect after the next one
+		let ime = self.cpu_state().ime();
+		// Address of the opcode, PC can't be used after the fetch as the HALT bug may not move it
+		let pc = self.cpu_state().read(CPURegister16::PC);
 		let instruction = self.get_next_instruction_or_interrupt();
 		self.execute(instruction);
+
+		if let Instruction::ERROR(opcode) = instruction {
+			self.cpu_state_mut().locked = Some(CPULock { pc, opcode });
+		}
+
+		// With IME=0 and an interrupt pending HALT does not halt,
+		// instead the next byte is read twice
+		if instruction == Instruction::HALT && !ime && self.cpu_state().interrupt_pending() {
+			let state = self.cpu_state_mut();
+			state.halted = false;
+			state.halt_bug = true;
+		}
+
 		Some(instruction)
 	}
 
@@ -7,7 +7,7 @@ pub trait Addressable<Idx, T> {
 	fn write(&mut self, index: Idx, value: T);
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum CPURegister8 {
 	A,
 	F,
@@ -19,7 +19,7 @@ pub enum CPURegister8 {
 	L,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 #[repr(C, align(2))]
 pub enum CPURegister16 {
 	AF,
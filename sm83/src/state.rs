@@ -1,3 +1,5 @@
+use std::fmt::{self, Display};
+
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -15,6 +17,30 @@ pub struct CPUState {
 	pub interrupt_enable: u8,  // IE
 	pub interrupt_request: u8, // IF
 	ie_next: bool,
+	// HALT was executed with IME=0 and an interrupt pending,
+	// the next opcode fetch does not increment PC
+	#[serde(default)]
+	pub halt_bug: bool,
+	// Set by an illegal opcode, the CPU stays locked until reset
+	#[serde(default)]
+	pub locked: Option<CPULock>,
+}
+
+/// The illegal opcode that locked up the CPU
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CPULock {
+	pub pc: u16,
+	pub opcode: u8,
+}
+
+impl Display for CPULock {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"CPU locked by illegal opcode ${:02X} at ${:04X}",
+			self.opcode, self.pc
+		)
+	}
 }
 
 impl Flags for CPUState {
@@ -0,0 +1,163 @@
+use crate::{
+	assembler::{assemble, AssemblerErrorKind},
+	disassembler::decode,
+};
+
+fn bytes(source: &str) -> Vec<u8> {
+	assemble(source, 0x0100).unwrap().bytes
+}
+
+fn error(source: &str) -> AssemblerErrorKind {
+	assemble(source, 0x0100).err().unwrap().kind
+}
+
+#[test]
+fn instructions() {
+	let source = "
+		ld sp, $FFFE
+		ld a, [hl+]
+		ld [hld], a
+		ldh [$FF40], a
+		ldh a, [c]
+		ld [$ff00+c], a
+		ld [$C000], sp
+		ld hl, sp - 2
+		add sp, -$10
+		cp $90
+		sub a, b
+		bit 7, h
+		swap [hl]
+		push af
+		jp hl
+		ret nc
+		rst $38
+	";
+
+	assert_eq!(
+		bytes(source),
+		[
+			0x31, 0xFE, 0xFF, 0x2A, 0x32, 0xE0, 0x40, 0xF2, 0xE2, 0x08, 0x00, 0xC0, 0xF8, 0xFE,
+			0xE8, 0xF0, 0xFE, 0x90, 0x90, 0xCB, 0x7C, 0xCB, 0x36, 0xF5, 0xE9, 0xD0, 0xFF,
+		]
+	);
+}
+
+#[test]
+fn labels() {
+	let source = "
+	Main:
+		ld b, 4
+	.loop:
+		dec b
+		jr nz, .loop
+		call Function
+	.done
+		jr .done
+	Function::
+		ret
+	";
+
+	let program = assemble(source, 0x0150).unwrap();
+	assert_eq!(
+		program.bytes,
+		[0x06, 0x04, 0x05, 0x20, 0xFD, 0xCD, 0x5A, 0x01, 0x18, 0xFE, 0xC9]
+	);
+	assert_eq!(program.symbols["Main"], 0x0150);
+	assert_eq!(program.symbols["Main.loop"], 0x0152);
+	assert_eq!(program.symbols["Main.done"], 0x0158);
+	assert_eq!(program.symbols["Function"], 0x015A);
+}
+
+#[test]
+fn local_labels_are_scoped() {
+	let source = "
+	First:
+	.loop: jr .loop
+	Second:
+	.loop: jr First.loop
+	";
+
+	assert_eq!(bytes(source), [0x18, 0xFE, 0x18, 0xFC]);
+}
+
+#[test]
+fn data() {
+	let source = r#"
+	Start:
+		db 1, $02, %11, "Hi\n", 'A'
+		dw Start, $1234
+	"#;
+
+	assert_eq!(
+		bytes(source),
+		[0x01, 0x02, 0x03, b'H', b'i', b'\n', b'A', 0x00, 0x01, 0x34, 0x12]
+	);
+}
+
+#[test]
+fn expressions() {
+	let source = "
+	Table:
+		ld a, 1 + 2 * 3
+		ld b, (1 + 2) * 3
+		ld c, 1 << 4 | 3
+		ld d, HIGH(Table + $0280)
+		ld e, LOW(Table) % 7
+		ld hl, @ + 3
+		db ~0 & $0F, -1
+	";
+
+	assert_eq!(
+		bytes(source),
+		[0x3E, 7, 0x06, 9, 0x0E, 0x13, 0x16, 0x03, 0x1E, 0x00, 0x21, 0x0D, 0x01, 0x0F, 0xFF]
+	);
+}
+
+#[test]
+fn errors() {
+	assert_eq!(
+		error("frob a"),
+		AssemblerErrorKind::UnknownMnemonic("frob".to_owned())
+	);
+	assert_eq!(
+		error("ld [hl], [hl]"),
+		AssemblerErrorKind::InvalidOperands("ld".to_owned())
+	);
+	assert_eq!(
+		error("jp Nowhere"),
+		AssemblerErrorKind::UndefinedSymbol("Nowhere".to_owned())
+	);
+	assert_eq!(error("ld a, 256"), AssemblerErrorKind::OutOfRange(256));
+	assert_eq!(error("rst $39"), AssemblerErrorKind::OutOfRange(0x39));
+	assert_eq!(error("db 1 / 0"), AssemblerErrorKind::DivisionByZero);
+	assert_eq!(error("jr @ + 200"), AssemblerErrorKind::OutOfRange(198));
+	assert_eq!(
+		error("A:\nA:"),
+		AssemblerErrorKind::DuplicateLabel("A".to_owned())
+	);
+
+	let error = assemble("nop\n\tld a,", 0).err().unwrap();
+	assert_eq!(error.line, 2);
+}
+
+// Every opcode decodes to an instruction whose `Debug` output assembles back to it
+#[test]
+fn debug_output_round_trip() {
+	let operands = [0x40, 0xFF];
+	let opcodes = (0..=0xFF)
+		.filter(|opcode| *opcode != 0xCB)
+		.map(|opcode| [&[opcode], &operands[..]].concat())
+		.chain((0..=0xFF).map(|opcode| vec![0xCB, opcode]));
+
+	for bytes in opcodes {
+		let (instruction, length) = decode(&bytes).unwrap();
+		let source = format!("{instruction:?}");
+		let assembled = assemble(&source, 0)
+			.unwrap_or_else(|err| panic!("Failed to assemble \"{source}\": {err}"))
+			.bytes;
+
+		let (reassembled, _) = decode(&assembled).unwrap();
+		assert_eq!(source, format!("{reassembled:?}"));
+		assert_eq!(assembled[..length], bytes[..length], "{source}");
+	}
+}
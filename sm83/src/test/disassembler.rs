@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+
+use crate::disassembler::{decode, disassemble, Formatter};
+
+fn format(bytes: &[u8], address: u16) -> Vec<String> {
+	let formatter = Formatter::default();
+	disassemble(bytes, address)
+		.map(|instruction| formatter.format(&instruction))
+		.collect()
+}
+
+#[test]
+fn decode_lengths() {
+	assert_eq!(decode(&[0x00]).unwrap().1, 1);
+	assert_eq!(decode(&[0x3E, 0x12]).unwrap().1, 2);
+	assert_eq!(decode(&[0xC3, 0x50, 0x01]).unwrap().1, 3);
+	assert_eq!(decode(&[0xCB, 0x7C]).unwrap().1, 2);
+	assert_eq!(decode(&[0xFA, 0x00, 0xC0, 0xFF]).unwrap().1, 3);
+}
+
+#[test]
+fn decode_truncated() {
+	assert!(decode(&[]).is_none());
+	assert!(decode(&[0xC3, 0x50]).is_none());
+	assert!(decode(&[0xCB]).is_none());
+}
+
+#[test]
+fn rgbds_syntax() {
+	let bytes = [
+		0x31, 0xFE, 0xFF, // ld sp, $FFFE
+		0xE0, 0x40, // ldh [$FF40], a
+		0xF2, // ldh a, [c]
+		0x08, 0x00, 0xC0, // ld [$C000], sp
+		0x2A, // ld a, [hl+]
+		0xFE, 0x90, // cp a, $90
+		0xCB, 0x46, // bit 0, [hl]
+		0xF8, 0xFE, // ld hl, sp - $02
+		0xE8, 0x04, // add sp, $04
+		0xC7, // rst $00
+		0xD3, // illegal
+	];
+
+	assert_eq!(
+		format(&bytes, 0x0150),
+		[
+			"ld sp, $FFFE",
+			"ldh [$FF40], a",
+			"ldh a, [c]",
+			"ld [$C000], sp",
+			"ld a, [hl+]",
+			"cp a, $90",
+			"bit 0, [hl]",
+			"ld hl, sp - $02",
+			"add sp, $04",
+			"rst $00",
+			"db $D3",
+		]
+	);
+}
+
+#[test]
+fn relative_jumps_use_targets() {
+	let bytes = [
+		0x18, 0xFE, // jr to itself
+		0x20, 0x02, // jr nz, forward
+		0xC3, 0x00, 0x01, // jp $0100
+	];
+
+	assert_eq!(
+		format(&bytes, 0x4000),
+		["jr $4000", "jr nz, $4006", "jp $0100"]
+	);
+}
+
+#[test]
+fn symbols_and_raw_bytes() {
+	let symbols = HashMap::from([(0x0150, "Main".to_owned()), (0x0200, "Update".to_owned())]);
+	let formatter = Formatter::default()
+		.with_symbols(symbols)
+		.with_raw_bytes(true);
+
+	let bytes = [
+		0xCD, 0x00, 0x02, // call Update
+		0x18, 0xFB, // jr Main
+	];
+
+	assert_eq!(
+		formatter.listing(&bytes, 0x0150),
+		concat!(
+			"Main:\n",
+			"\tcall Update             ; $0150: CD 00 02\n",
+			"\tjr Main                 ; $0153: 18 FB\n",
+		)
+	);
+}
+
+#[test]
+fn truncated_instruction_at_end() {
+	assert_eq!(format(&[0x00, 0xC3, 0x50], 0), ["nop", "db $C3", "ld d, b"]);
+}
@@ -0,0 +1,90 @@
+use crate::{
+	assembler::assemble,
+	disassembler::decode,
+	instruction::Instruction::*,
+	registers::{CPURegister16::HL, CPURegister8::*},
+	values::{ValueRefU16, ValueRefU8},
+	Condition,
+};
+
+// Operand bytes following every opcode, covering zero, sign and high page edge cases
+const OPERANDS: [[u8; 2]; 5] = [
+	[0x00, 0x00],
+	[0x34, 0x12],
+	[0x7F, 0x80],
+	[0x80, 0x7F],
+	[0xFF, 0xFF],
+];
+
+fn round_trip(bytes: &[u8]) {
+	let (instruction, length) = decode(bytes).unwrap();
+	let encoded = instruction
+		.encode()
+		.unwrap_or_else(|| panic!("Failed to encode {instruction:?} from {bytes:02X?}"));
+
+	// The padding byte after STOP is not part of the decoded instruction
+	let padding = if instruction == STOP { 1 } else { 0 };
+	assert_eq!(encoded.len(), length + padding, "{instruction:?}");
+	assert_eq!(encoded[..length], bytes[..length], "{instruction:?}");
+	assert_eq!(decode(&encoded).unwrap(), (instruction, length));
+}
+
+#[test]
+fn encode_round_trip_all_opcodes() {
+	for opcode in 0..=0xFF {
+		if opcode == 0xCB {
+			continue;
+		}
+		for operands in OPERANDS {
+			round_trip(&[opcode, operands[0], operands[1]]);
+		}
+	}
+
+	for opcode in 0..=0xFF {
+		round_trip(&[0xCB, opcode]);
+	}
+}
+
+#[test]
+fn encode_operands() {
+	assert_eq!(
+		LD_8(
+			ValueRefU8::Mem(ValueRefU16::Raw(0xC000)),
+			ValueRefU8::Reg(A)
+		)
+		.encode(),
+		Some(vec![0xEA, 0x00, 0xC0])
+	);
+	assert_eq!(
+		LDH(ValueRefU8::MemOffsetRaw(0x44), ValueRefU8::Reg(A)).encode(),
+		Some(vec![0xE0, 0x44])
+	);
+	assert_eq!(RST(ValueRefU16::Raw(0x28)).encode(), Some(vec![0xEF]));
+	assert_eq!(
+		JP(Condition::C, ValueRefU16::Raw(0x0150)).encode(),
+		Some(vec![0xDA, 0x50, 0x01])
+	);
+}
+
+#[test]
+fn encode_invalid_combinations() {
+	assert_eq!(INT.encode(), None);
+	assert_eq!(RST(ValueRefU16::Raw(0x29)).encode(), None);
+	assert_eq!(LD_8(ValueRefU8::Raw(1), ValueRefU8::Raw(2)).encode(), None);
+	assert_eq!(
+		LD_8(
+			ValueRefU8::Mem(ValueRefU16::Reg(HL)),
+			ValueRefU8::Mem(ValueRefU16::Reg(HL))
+		)
+		.encode(),
+		None
+	);
+	assert_eq!(BIT(8, ValueRefU8::Reg(B)).encode(), None);
+}
+
+#[test]
+fn encode_stop_matches_assembler() {
+	let assembled = assemble(&format!("{STOP:?}"), 0).unwrap().bytes;
+	assert_eq!(STOP.encode(), Some(assembled));
+	assert_eq!(STOP.encode(), Some(vec![0x10, 0x00]));
+}
@@ -0,0 +1,123 @@
+use crate::{
+	assembler::assemble,
+	memory_mapper::{MemoryMapper, Source, SourcedMemoryMapper},
+	registers::{Addressable, CPURegister16::*, CPURegister8::*},
+	CPUState, Instruction, Interrupt, SM83,
+};
+
+struct Cpu {
+	memory: Vec<u8>,
+	cpu_state: CPUState,
+}
+
+impl MemoryMapper for Cpu {
+	fn read(&self, addr: u16) -> u8 {
+		self.memory[addr as usize]
+	}
+
+	fn write(&mut self, addr: u16, value: u8) {
+		self.memory[addr as usize] = value
+	}
+}
+
+impl SourcedMemoryMapper for Cpu {
+	fn read_from(&self, addr: u16, _source: Source) -> u8 {
+		self.read(addr)
+	}
+
+	fn write_from(&mut self, addr: u16, value: u8, _source: Source) {
+		self.write(addr, value)
+	}
+}
+
+impl SM83 for Cpu {
+	fn cpu_state(&self) -> &CPUState {
+		&self.cpu_state
+	}
+
+	fn cpu_state_mut(&mut self) -> &mut CPUState {
+		&mut self.cpu_state
+	}
+}
+
+// Runs `source` from $0100 with the VBlank handler at $0040
+fn cpu(source: &str, handler: &str) -> Cpu {
+	let mut cpu = Cpu {
+		memory: vec![0; 0x10000],
+		cpu_state: CPUState::default(),
+	};
+	for (origin, source) in [(0x0100, source), (0x0040, handler)] {
+		let bytes = assemble(source, origin).unwrap().bytes;
+		cpu.memory[origin as usize..][..bytes.len()].copy_from_slice(&bytes);
+	}
+	cpu.cpu_state.write(PC, 0x0100);
+	cpu.cpu_state.write(SP, 0xFFFE);
+	cpu.cpu_state.interrupt_enable = Interrupt::VBlank as u8;
+	cpu
+}
+
+fn stack_top(cpu: &Cpu) -> u16 {
+	let sp = cpu.cpu_state.read(SP);
+	u16::from_le_bytes([cpu.read(sp), cpu.read(sp.wrapping_add(1))])
+}
+
+#[test]
+fn halt_waits_for_interrupt() {
+	let mut cpu = cpu("halt\n inc a", "");
+	cpu.step_cpu();
+	assert!(cpu.cpu_state.halted);
+	assert!(cpu.step_cpu().is_none());
+
+	// Without IME the CPU resumes after the HALT without servicing it
+	cpu.cpu_state.interrupt_request = Interrupt::VBlank as u8;
+	cpu.step_cpu();
+	assert_eq!(cpu.cpu_state.read(A), 1);
+	assert_eq!(cpu.cpu_state.read(PC), 0x0102);
+}
+
+#[test]
+fn halt_with_pending_interrupt_reads_next_byte_twice() {
+	let mut cpu = cpu("halt\n ld a, $14", "");
+	cpu.cpu_state.interrupt_request = Interrupt::VBlank as u8;
+
+	cpu.step_cpu();
+	assert!(!cpu.cpu_state.halted);
+	assert_eq!(cpu.cpu_state.read(PC), 0x0101);
+
+	// Runs as `ld a, $3E` followed by `inc d`
+	cpu.step_cpu();
+	cpu.step_cpu();
+	assert_eq!(cpu.cpu_state.read(A), 0x3E);
+	assert_eq!(cpu.cpu_state.read(D), 1);
+	assert_eq!(cpu.cpu_state.read(PC), 0x0103);
+}
+
+#[test]
+fn halt_bug_before_rst_returns_to_rst() {
+	let mut cpu = cpu("halt\n rst $08", "");
+	cpu.cpu_state.interrupt_request = Interrupt::VBlank as u8;
+
+	cpu.step_cpu();
+	cpu.step_cpu();
+	assert_eq!(cpu.cpu_state.read(PC), 0x0008);
+	assert_eq!(stack_top(&cpu), 0x0101);
+}
+
+#[test]
+fn ei_before_halt_returns_to_halt() {
+	let mut cpu = cpu("ei\n halt\n inc a", "reti");
+	cpu.cpu_state.interrupt_request = Interrupt::VBlank as u8;
+
+	cpu.step_cpu();
+	cpu.step_cpu();
+	assert_eq!(cpu.step_cpu(), Some(Instruction::INT));
+	assert_eq!(cpu.cpu_state.read(PC), 0x0040);
+	assert_eq!(stack_top(&cpu), 0x0101);
+
+	// The HALT runs again, this time without a pending interrupt
+	cpu.step_cpu();
+	cpu.step_cpu();
+	assert!(cpu.cpu_state.halted);
+	assert_eq!(cpu.cpu_state.read(A), 0);
+	assert_eq!(cpu.cpu_state.read(PC), 0x0102);
+}
@@ -1 +1,5 @@
+mod assembler;
+mod disassembler;
+mod encode;
+mod halt_bug;
 mod opcode_tests;
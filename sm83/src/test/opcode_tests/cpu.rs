@@ -1,4 +1,9 @@
-use super::{memory_mapper::FlatMemory, state::TestState};
+use std::cell::RefCell;
+
+use super::{
+	memory_mapper::FlatMemory,
+	state::{BusCycle, TestState},
+};
 use crate::{
 	memory_mapper::{MemoryMapper, SourcedMemoryMapper},
 	registers::{Addressable, CPURegister16::*, CPURegister8::*},
@@ -9,6 +14,19 @@ use crate::{
 pub struct MockCpu {
 	pub memory: FlatMemory,
 	pub cpu_state: CPUState,
+	// Reads happen through `&self`, so the log needs interior mutability
+	pub cycles: RefCell<Vec<BusCycle>>,
+}
+
+impl MockCpu {
+	// Accesses take the place of the idle cycle ticked before them
+	fn record(&self, access: BusCycle) {
+		let mut cycles = self.cycles.borrow_mut();
+		match cycles.last_mut() {
+			Some(cycle @ BusCycle::Idle) => *cycle = access,
+			_ => cycles.push(access),
+		}
+	}
 }
 
 impl MemoryMapper for MockCpu {
@@ -23,10 +41,13 @@ impl MemoryMapper for MockCpu {
 
 impl SourcedMemoryMapper for MockCpu {
 	fn read_from(&self, addr: u16, source: crate::memory_mapper::Source) -> u8 {
-		self.memory.read_from(addr, source)
+		let value = self.memory.read_from(addr, source);
+		self.record(BusCycle::Read(addr, value));
+		value
 	}
 
 	fn write_from(&mut self, addr: u16, value: u8, source: crate::memory_mapper::Source) {
+		self.record(BusCycle::Write(addr, value));
 		self.memory.write_from(addr, value, source)
 	}
 }
@@ -39,12 +60,21 @@ impl SM83 for MockCpu {
 	fn cpu_state_mut(&mut self) -> &mut CPUState {
 		&mut self.cpu_state
 	}
+
+	// Stop waits for a button press, which never comes here
+	fn exec_stop(&mut self) {
+		self.cpu_state.halted = true;
+	}
+
+	fn on_m_cycle(&mut self, m_cycles: u32) {
+		let cycles = self.cycles.get_mut();
+		cycles.extend((0..m_cycles).map(|_| BusCycle::Idle));
+	}
 }
 
 impl From<TestState> for MockCpu {
 	fn from(state: TestState) -> Self {
 		let mut res = Self::default();
-		res.cpu_state.enable_interrupts();
 
 		if state.ime == 1 {
 			res.cpu_state.enable_interrupts();
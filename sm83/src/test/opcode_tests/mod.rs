@@ -5,24 +5,107 @@ mod state;
 use self::cpu::MockCpu;
 use std::fs::{self, read_dir, DirEntry};
 
-use crate::test::opcode_tests::state::{OpcodeTest, TestState};
+use crate::test::opcode_tests::state::{BusCycle, OpcodeTest, TestState};
 use crate::SM83;
 
+#[derive(Default)]
+struct OpcodeSummary {
+	passed: usize,
+	// Registers or memory differ after the instruction
+	state_failures: usize,
+	// Reads, writes or idle cycles differ from the recorded bus activity
+	cycle_failures: usize,
+	first_failure: Option<String>,
+}
+
+impl OpcodeSummary {
+	fn failed(&self) -> usize {
+		self.state_failures.max(self.cycle_failures)
+	}
+}
+
+fn run_test(test: &OpcodeTest, summary: &mut OpcodeSummary) {
+	let mut cpu: MockCpu = test.initial_state.clone().into();
+	cpu.step_cpu();
+
+	// The fixtures keep recording while HALT and STOP wait
+	let expected_cycles: Vec<BusCycle> = test.cycles.iter().map(BusCycle::from).collect();
+	while cpu.cpu_state.halted && cpu.cycles.borrow().len() < expected_cycles.len() {
+		cpu.step_cpu();
+	}
+
+	let cycles = cpu.cycles.take();
+	let end_state: TestState = cpu.into();
+
+	let state_matches = end_state == test.final_state;
+	let cycles_match = cycles == expected_cycles;
+
+	if !state_matches {
+		summary.state_failures += 1;
+	}
+	if !cycles_match {
+		summary.cycle_failures += 1;
+	}
+
+	if state_matches && cycles_match {
+		summary.passed += 1;
+	} else if summary.first_failure.is_none() {
+		let mut message = format!("{}:", test.name);
+		if !state_matches {
+			message += &format!(
+				"\n  state:  {end_state:?}\n  expect: {:?}",
+				test.final_state
+			);
+		}
+		if !cycles_match {
+			message += &format!("\n  cycles: {cycles:?}\n  expect: {expected_cycles:?}");
+		}
+		summary.first_failure = Some(message);
+	}
+}
+
 #[test]
 pub fn run_opcode_tests() {
 	let folder = read_dir("./src/test/opcode_tests/v1").unwrap();
-	let files = folder.flatten().collect::<Vec<DirEntry>>();
+	let mut files = folder.flatten().collect::<Vec<DirEntry>>();
+	files.sort_by_key(|file| file.file_name());
 
+	let mut failed_opcodes = vec![];
 	for file in files {
+		let opcode = file
+			.path()
+			.file_stem()
+			.unwrap()
+			.to_string_lossy()
+			.to_uppercase();
 		let val = fs::read_to_string(file.path()).unwrap();
 		let tests: Vec<OpcodeTest> = serde_json::from_str(&val).unwrap();
-		for test in tests {
-			let mut cpu: MockCpu = test.initial_state.clone().into();
-			cpu.step_cpu();
-			let end_state: TestState = cpu.into();
-			if end_state != test.final_state {
-				println!("FAILED:{:}", test.name);
+
+		let mut summary = OpcodeSummary::default();
+		for test in &tests {
+			run_test(test, &mut summary);
+		}
+
+		println!(
+			"{opcode}: {}/{} passed, {} state failures, {} cycle failures",
+			summary.passed,
+			tests.len(),
+			summary.state_failures,
+			summary.cycle_failures,
+		);
+
+		if summary.failed() > 0 {
+			if let Some(failure) = &summary.first_failure {
+				println!("{failure}");
 			}
+			failed_opcodes.push(opcode);
 		}
 	}
+
+	assert!(
+		failed_opcodes.is_empty(),
+		"{} opcodes failed: {}",
+		failed_opcodes.len(),
+		failed_opcodes.join(", ")
+	);
 }
@@ -1,6 +1,6 @@
 use serde::Deserialize;
 
-#[derive(Deserialize, PartialEq, Eq, Clone)]
+#[derive(Deserialize, PartialEq, Eq, Clone, Debug)]
 pub struct TestState {
 	pub pc: u16,
 	pub sp: u16,
@@ -23,4 +23,23 @@ pub struct OpcodeTest {
 	pub initial_state: TestState,
 	#[serde(alias = "final")]
 	pub final_state: TestState,
+	// Bus activity of every M-cycle: address, value and "r-m", "-wm" or "---" when idle
+	pub cycles: Vec<(u16, u8, String)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BusCycle {
+	Read(u16, u8),
+	Write(u16, u8),
+	Idle,
+}
+
+impl From<&(u16, u8, String)> for BusCycle {
+	fn from((address, value, kind): &(u16, u8, String)) -> Self {
+		match kind.as_str() {
+			"r-m" => Self::Read(*address, *value),
+			"-wm" => Self::Write(*address, *value),
+			_ => Self::Idle,
+		}
+	}
 }
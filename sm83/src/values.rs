@@ -38,7 +38,7 @@ impl From<u16> for ValueRefU16 {
 	}
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum ValueRefU8 {
 	Reg(CPURegister8),
 	Mem(ValueRefU16),
@@ -47,14 +47,14 @@ pub enum ValueRefU8 {
 	MemOffsetReg(CPURegister8),
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum ValueRefU16 {
 	Reg(CPURegister16),
 	Mem(u16),
 	Raw(u16),
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub struct ValueRefI8(pub i8);
 
 impl fmt::Debug for ValueRefU16 {
@@ -92,45 +92,61 @@ impl fmt::Debug for ValueRefI8 {
 	}
 }
 
+// Names of hardware registers, as shown in memory operands
+const HARDWARE_REGISTERS: [(u16, &str); 37] = [
+	(0xFF04, "DIV"),
+	(0xFF05, "TIMA"),
+	(0xFF06, "TMA"),
+	(0xFF07, "TAC"),
+	(0xFF10, "NR10"),
+	(0xFF11, "NR11"),
+	(0xFF12, "NR12"),
+	(0xFF14, "NR14"),
+	(0xFF16, "NR21"),
+	(0xFF17, "NR22"),
+	(0xFF19, "NR24"),
+	(0xFF1A, "NR30"),
+	(0xFF1B, "NR31"),
+	(0xFF1C, "NR32"),
+	(0xFF1E, "NR33"),
+	(0xFF20, "NR41"),
+	(0xFF21, "NR42"),
+	(0xFF22, "NR43"),
+	(0xFF23, "NR44"),
+	(0xFF24, "NR50"),
+	(0xFF25, "NR51"),
+	(0xFF26, "NR52"),
+	(0xFF40, "LCDC"),
+	(0xFF41, "STAT"),
+	(0xFF42, "SCY"),
+	(0xFF43, "SCX"),
+	(0xFF44, "LY"),
+	(0xFF45, "LYC"),
+	(0xFF46, "DMA"),
+	(0xFF47, "BGP"),
+	(0xFF48, "OBP0"),
+	(0xFF49, "OBP1"),
+	(0xFF4A, "WY"),
+	(0xFF4B, "WX"),
+	(0xFF01, "SB"),
+	(0xFF02, "SC"),
+	(0xFF0F, "IF"),
+];
+
 fn format_memref(addr: u16) -> String {
-	match addr {
-		0xFF04 => "DIV".to_owned(),
-		0xFF05 => "TIMA".to_owned(),
-		0xFF06 => "TMA".to_owned(),
-		0xFF07 => "TAC".to_owned(),
-		0xFF10 => "NR10".to_owned(),
-		0xFF11 => "NR11".to_owned(),
-		0xFF12 => "NR12".to_owned(),
-		0xFF14 => "NR14".to_owned(),
-		0xFF16 => "NR21".to_owned(),
-		0xFF17 => "NR22".to_owned(),
-		0xFF19 => "NR24".to_owned(),
-		0xFF1A => "NR30".to_owned(),
-		0xFF1B => "NR31".to_owned(),
-		0xFF1C => "NR32".to_owned(),
-		0xFF1E => "NR33".to_owned(),
-		0xFF20 => "NR41".to_owned(),
-		0xFF21 => "NR42".to_owned(),
-		0xFF22 => "NR43".to_owned(),
-		0xFF23 => "NR44".to_owned(),
-		0xFF24 => "NR50".to_owned(),
-		0xFF25 => "NR51".to_owned(),
-		0xFF26 => "NR52".to_owned(),
-		0xFF40 => "LCDC".to_owned(),
-		0xFF41 => "STAT".to_owned(),
-		0xFF42 => "SCY".to_owned(),
-		0xFF43 => "SCX".to_owned(),
-		0xFF44 => "LY".to_owned(),
-		0xFF45 => "LYC".to_owned(),
-		0xFF46 => "DMA".to_owned(),
-		0xFF47 => "BGP".to_owned(),
-		0xFF48 => "OBP0".to_owned(),
-		0xFF49 => "OBP1".to_owned(),
-		0xFF4A => "WY".to_owned(),
-		0xFF4B => "WX".to_owned(),
-		0xFF01 => "SB".to_owned(),
-		0xFF02 => "SC".to_owned(),
-		0xFF0F => "IF".to_owned(),
-		addr => format!("{addr:04X}"),
+	match HARDWARE_REGISTERS
+		.iter()
+		.find(|(address, _)| *address == addr)
+	{
+		Some((_, name)) => (*name).to_owned(),
+		None => format!("{addr:04X}"),
 	}
 }
+
+/// Address of a hardware register by the name used in memory operands
+pub(crate) fn hardware_register(name: &str) -> Option<u16> {
+	HARDWARE_REGISTERS
+		.iter()
+		.find(|(_, register)| register.eq_ignore_ascii_case(name))
+		.map(|(address, _)| *address)
+}
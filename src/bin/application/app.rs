@@ -5,7 +5,9 @@ use wasm_bindgen::{prelude::wasm_bindgen, Clamped};
 use web_sys::ImageData;
 
 use gameboy::{
-	save_state::{RomSource, SaveState},
+	patch::apply_patch,
+	rewind::Rewind,
+	save_state::{RomSource, SaveError, SaveState},
 	Gameboy,
 };
 
@@ -47,6 +49,9 @@ pub struct Application {
 	input_state: InputState,
 	speed_multiplier: f64,
 	frames: VecDeque<f64>,
+	rewind: Rewind,
+	// Patch applied to the loaded rom, kept in save states
+	rom_patch: Option<Vec<u8>>,
 }
 
 impl Default for Application {
@@ -60,6 +65,8 @@ impl Default for Application {
 			emulator_state,
 			speed_multiplier: 1.0,
 			frames: VecDeque::with_capacity(30),
+			rewind: Rewind::default(),
+			rom_patch: None,
 		}
 	}
 }
@@ -87,6 +94,16 @@ impl Application {
 
 		let controller_state = self.input_state.get_controller_state();
 		self.emulator_state.set_controller_state(&controller_state);
+		self.emulator_state
+			.set_tilt_state(&self.input_state.get_tilt_state());
+
+		// Rewinding steps back one frame per call
+		if self.input_state.is_rewinding() {
+			if let Some(emulator_state) = self.rewind.step_back(&self.emulator_state) {
+				self.emulator_state = emulator_state;
+			}
+			return delta_t;
+		}
 
 		let iters = if self.speed_multiplier > 1.0 {
 			self.speed_multiplier.round() as i32
@@ -94,6 +111,7 @@ impl Application {
 			1
 		};
 
+		let was_locked = self.emulator_state.cpu_state.locked.is_some();
 		for _ in 0..iters {
 			let mut steps = 0;
 			let start_frame = self.emulator_state.ppu.frame;
@@ -102,11 +120,16 @@ impl Application {
 				self.emulator_state.step();
 				if self.emulator_state.ppu.frame != start_frame {
 					self.frames.push_back(performance_now());
+					self.rewind.capture(&self.emulator_state);
 					break;
 				}
 			}
 		}
 
+		if let (false, Some(lock)) = (was_locked, self.emulator_state.cpu_state.locked) {
+			log::error!("{lock}");
+		}
+
 		delta_t
 
 		// if let Some(audio) = &mut self.audio {
@@ -128,6 +151,15 @@ impl Application {
 		// }
 	}
 
+	// Diagnostic for a CPU locked up by an illegal opcode
+	#[wasm_bindgen]
+	pub fn cpu_lock(&self) -> Option<String> {
+		self.emulator_state
+			.cpu_state
+			.locked
+			.map(|lock| lock.to_string())
+	}
+
 	#[wasm_bindgen]
 	pub fn start(&mut self) {
 		self.running_state = RunningState::Playing;
@@ -176,21 +208,86 @@ impl Application {
 		Some(rom_data)
 	}
 
-	pub fn load_rom(&mut self, rom: &[u8], source: Option<String>) {
-		self.emulator_state = Gameboy::default();
+	// Boots a new emulator with the rom, patched if a patch is given
+	fn boot_rom(
+		rom: &[u8],
+		source: Option<String>,
+		patch: Option<&[u8]>,
+	) -> Result<Gameboy, String> {
+		let rom = match patch {
+			Some(patch) => apply_patch(rom, patch).map_err(|err| err.to_string())?,
+			None => rom.to_vec(),
+		};
 
-		self.emulator_state
-			.load_rom(rom, source.map(RomSource::LocalUrl));
+		let mut emulator_state = Gameboy::default();
+		emulator_state
+			.load_rom(&rom, source.map(RomSource::LocalUrl))
+			.map_err(|err| err.to_string())?;
+		Ok(emulator_state)
+	}
+
+	pub fn load_rom(
+		&mut self,
+		rom: &[u8],
+		source: Option<String>,
+		patch: Option<Vec<u8>>,
+	) -> Result<(), String> {
+		self.emulator_state = Self::boot_rom(rom, source, patch.as_deref())?;
+		self.rom_patch = patch;
+		self.rewind.clear();
+		Ok(())
 	}
 
+	// Save state of the running game, along with the patch it was loaded with
+	pub(crate) fn save_state(&self) -> Result<SaveState, SaveError> {
+		let mut save = SaveState::try_from(&self.emulator_state)?;
+		save.rom_patch = self.rom_patch.clone();
+		Ok(save)
+	}
+
+	// The running game is only replaced once the save state is restored
 	pub(crate) fn load_save_state_with_rom(&mut self, rom: &[u8], save: SaveState) {
 		let path = save.rom_source.clone().map(|source| match source {
 			RomSource::LocalUrl(path) => path,
 			RomSource::ExternalUrl(path) => path,
 		});
 
-		self.load_rom(rom, path);
-		self.emulator_state = self.emulator_state.clone().load_save_state(save);
+		let restored = Self::boot_rom(rom, path, save.rom_patch.as_deref()).and_then(|booted| {
+			booted
+				.try_load_save_state(&save)
+				.map_err(|err| format!("{err:?}"))
+		});
+
+		match restored {
+			Ok(emulator_state) => {
+				self.emulator_state = emulator_state;
+				self.rom_patch = save.rom_patch;
+				self.rewind.clear();
+			}
+			Err(err) => log::error!("Failed to load save state: {err}"),
+		}
+	}
+
+	pub fn export_bess(&self) -> Option<Vec<u8>> {
+		self.emulator_state.export_bess().ok()
+	}
+
+	// Imports a save state of another emulator into the loaded rom
+	pub fn import_bess(&mut self, file: &[u8]) -> bool {
+		match self.emulator_state.import_bess(file) {
+			Ok((emulator_state, warnings)) => {
+				for warning in warnings {
+					log::warn!("BESS import: {warning}");
+				}
+				self.emulator_state = emulator_state;
+				self.rewind.clear();
+				true
+			}
+			Err(err) => {
+				log::error!("Failed to import BESS save state: {err:?}");
+				false
+			}
+		}
 	}
 
 	pub fn set_speed(&mut self, multiplier: f64) {
@@ -2,7 +2,7 @@ use gloo::utils::window;
 use wasm_bindgen::JsCast;
 use web_sys::Gamepad;
 
-use gameboy::joypad::JoypadState;
+use gameboy::joypad::{JoypadState, TiltState};
 
 #[derive(Default)]
 pub struct InputState {}
@@ -34,6 +34,40 @@ impl InputState {
 
 		state
 	}
+
+	// Set while the rewind key is held down
+	pub fn is_rewinding(&self) -> bool {
+		window()
+			.get("rewind_held")
+			.and_then(|dom| dom.as_bool())
+			.unwrap_or_default()
+	}
+
+	pub fn get_tilt_state(&self) -> TiltState {
+		if let Some(gp) = self.get_gamepad() {
+			let tilt = gamepad_to_tilt_state(&gp);
+			if tilt.x != 0.0 || tilt.y != 0.0 {
+				return tilt;
+			}
+		}
+
+		window()
+			.get("tilt_state")
+			.and_then(|dom| dom.as_string())
+			.and_then(|json| serde_json::from_str::<TiltState>(&json).ok())
+			.unwrap_or_default()
+	}
+}
+
+// Tilt is driven by the right analog stick
+pub fn gamepad_to_tilt_state(gp: &Gamepad) -> TiltState {
+	let axis: Vec<f64> = gp.axes().iter().map(|v| v.as_f64().unwrap()).collect();
+	let dead_zone = |value: f64| if value.abs() > 0.1 { value as f32 } else { 0.0 };
+
+	TiltState {
+		x: axis.get(2).copied().map(dead_zone).unwrap_or_default(),
+		y: axis.get(3).copied().map(dead_zone).unwrap_or_default(),
+	}
 }
 
 pub fn gamepad_to_controller_state(gp: &Gamepad) -> JoypadState {
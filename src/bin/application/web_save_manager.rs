@@ -1,8 +1,11 @@
 use serde::Deserialize;
-use wasm_bindgen::prelude::wasm_bindgen;
-use web_sys::{window, Storage};
+use wasm_bindgen::{prelude::wasm_bindgen, Clamped};
+use web_sys::{window, ImageData, Storage};
 
-use gameboy::save_state::{RomSource, SaveError, SaveManager, SaveState};
+use gameboy::save_state::{
+	thumbnail::{THUMBNAIL_HEIGHT, THUMBNAIL_WIDTH},
+	RomSource, SaveError, SaveManager, SaveState, SaveStateEntry, SAVE_SLOTS,
+};
 
 use crate::app::{Application, APPLICATION};
 
@@ -42,7 +45,7 @@ impl WebSaveManager {
 }
 
 impl SaveManager for WebSaveManager {
-	fn load_save_state(slot: usize) -> Result<SaveState, SaveError> {
+	fn load_save_state(&self, slot: usize) -> Result<SaveState, SaveError> {
 		use SaveError::*;
 
 		let data = window()
@@ -57,28 +60,31 @@ impl SaveManager for WebSaveManager {
 		serde_json::from_str::<SaveState>(&data).or(Err(Deserialization))
 	}
 
-	fn get_save_states() -> Vec<Option<String>> {
+	fn get_save_states(&self) -> Vec<Option<SaveStateEntry>> {
 		if let Ok(index) = WebSaveManager::get_item("index") {
 			index
 		} else {
-			(0..10).map(|_| None).collect()
+			// Older indices only stored a description, the entries are read from the saves instead
+			(0..SAVE_SLOTS)
+				.map(|slot| self.load_save_state(slot).ok().map(|save| save.info))
+				.collect()
 		}
 	}
 
-	fn save_save_state(state: SaveState, slot: usize) -> Result<(), SaveError> {
+	fn save_save_state(&mut self, state: SaveState, slot: usize) -> Result<(), SaveError> {
 		use SaveError::*;
-		if slot >= 10 {
+		if slot >= SAVE_SLOTS {
 			return Err(IndexOutOfBounds(slot));
 		}
 		let storage = WebSaveManager::get_storage()?;
 
-		let mut current_index = WebSaveManager::get_save_states();
+		let mut current_index = self.get_save_states();
 
-		current_index[slot] = Some(state.info.to_string());
+		current_index[slot] = Some(state.info.clone());
 
 		let serialized_data = serde_json::to_string::<SaveState>(&state).or(Err(Serialization))?;
-		let serialized_index =
-			serde_json::to_string::<Vec<Option<String>>>(&current_index).or(Err(Serialization))?;
+		let serialized_index = serde_json::to_string::<Vec<Option<SaveStateEntry>>>(&current_index)
+			.or(Err(Serialization))?;
 
 		storage
 			.set_item(&slot.to_string(), &serialized_data)
@@ -95,7 +101,7 @@ impl SaveManager for WebSaveManager {
 #[allow(dead_code)]
 #[wasm_bindgen]
 pub async fn load_save_state(slot: usize) {
-	if let Ok(save) = WebSaveManager::load_save_state(slot) {
+	if let Ok(save) = (WebSaveManager {}).load_save_state(slot) {
 		let path = save.rom_source.clone().map(|source| match source {
 			RomSource::LocalUrl(path) => path,
 			RomSource::ExternalUrl(path) => path,
@@ -113,8 +119,35 @@ pub async fn load_save_state(slot: usize) {
 #[wasm_bindgen]
 pub fn save_save_state(slot: usize) {
 	APPLICATION.with_borrow_mut(move |app| {
-		let save = SaveState::try_from(&app.emulator_state).unwrap();
+		let save = app.save_state().unwrap();
 
-		_ = WebSaveManager::save_save_state(save, slot);
+		_ = (WebSaveManager {}).save_save_state(save, slot);
 	});
 }
+
+// Description of a slot for the slot picker, `None` for empty slots
+#[allow(dead_code)]
+#[wasm_bindgen]
+pub fn save_state_label(slot: usize) -> Option<String> {
+	let entry = (WebSaveManager {})
+		.get_save_states()
+		.into_iter()
+		.nth(slot)??;
+	Some(entry.to_string())
+}
+
+#[allow(dead_code)]
+#[wasm_bindgen]
+pub fn save_state_thumbnail(slot: usize) -> Option<ImageData> {
+	let entry = (WebSaveManager {})
+		.get_save_states()
+		.into_iter()
+		.nth(slot)??;
+	let image = entry.thumbnail?.to_rgba();
+	ImageData::new_with_u8_clamped_array_and_sh(
+		Clamped(&image),
+		THUMBNAIL_WIDTH as u32,
+		THUMBNAIL_HEIGHT as u32,
+	)
+	.ok()
+}
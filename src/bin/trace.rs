@@ -6,7 +6,8 @@ use gameboy::Gameboy;
 
 pub fn main() {
 	let mut gb = Gameboy::cgb();
-	gb.load_rom(include_bytes!("../../roms/games/Wario Land 3.gbc"), None);
+	gb.load_rom(include_bytes!("../../roms/games/Wario Land 3.gbc"), None)
+		.unwrap();
 	gb.run_until_boot();
 	use CPURegister8::*;
 
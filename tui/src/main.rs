@@ -1,5 +1,7 @@
 use std::{
+	env, fs,
 	io::{stdout, Write},
+	path::PathBuf,
 	thread::{self},
 	time::{Duration, Instant},
 };
@@ -14,9 +16,72 @@ use crossterm::{
 	execute,
 };
 
-use gameboy::{joypad::JoypadState, Gameboy};
+use gameboy::{
+	cartridge::rtc::RtcClock,
+	joypad::{JoypadState, TiltState},
+	patch::apply_patch,
+	rewind::Rewind,
+	save_state::{
+		file_save_manager::FileSaveManager,
+		thumbnail::{Thumbnail, THUMBNAIL_WIDTH},
+		SaveManager, SaveState,
+	},
+	Gameboy,
+};
+
+struct Args {
+	rom: Vec<u8>,
+	save_directory: PathBuf,
+}
+
+// Usage: gbc-cli [ROM] [--patch PATCH] [--save-dir DIRECTORY]
+fn parse_args() -> Args {
+	let mut rom = None;
+	let mut patch = None;
+	let mut save_directory = None;
+
+	let mut args = env::args().skip(1);
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--patch" => patch = args.next(),
+			"--save-dir" => save_directory = args.next().map(PathBuf::from),
+			_ => rom = Some(arg),
+		}
+	}
+
+	let rom = match rom {
+		Some(path) => fs::read(path).expect("Failed to read rom"),
+		None => {
+			include_bytes!("../../../rust-gbc/roms/games/Super Mario Bros. Deluxe.gbc").to_vec()
+		}
+	};
+
+	let rom = match patch {
+		Some(path) => {
+			let patch = fs::read(path).expect("Failed to read patch");
+			apply_patch(&rom, &patch).expect("Failed to apply patch")
+		}
+		None => rom,
+	};
+
+	Args {
+		rom,
+		save_directory: save_directory.unwrap_or_else(FileSaveManager::default_directory),
+	}
+}
+
+// Scales a save state thumbnail up to the size of the screen
+fn thumbnail_to_screen(thumbnail: &Thumbnail) -> Vec<u8> {
+	let image = thumbnail.to_rgba();
+	(0..144)
+		.flat_map(|y| (0..160).map(move |x| (y / 2 * THUMBNAIL_WIDTH + x / 2) * 4))
+		.flat_map(|index| image[index..index + 4].to_vec())
+		.collect()
+}
 
 fn main() {
+	let args = parse_args();
+
 	let mut stdout = stdout();
 	execute!(
 		stdout,
@@ -29,10 +94,16 @@ fn main() {
 
 	let mut gb = Gameboy::default();
 
-	gb.load_rom(
-		include_bytes!("../../../rust-gbc/roms/games/Super Mario Bros. Deluxe.gbc"),
-		None,
-	);
+	gb.load_rom(&args.rom, None).unwrap();
+	gb.set_rtc_clock(RtcClock::Host);
+
+	let info = &gb.cartridge_state.as_ref().unwrap().info;
+	let mut save_manager = FileSaveManager::new(&args.save_directory, info);
+	save_manager.load_battery(&mut gb).unwrap();
+	// Number keys select the slot used by F5 (save) and F9 (load)
+	let mut save_slot = 0;
+	// Preview of the selected slot, shown for a moment
+	let mut preview: Option<(Vec<u8>, Instant)> = None;
 
 	let config = ImageBuilderConfig {
 		skip_unchanged: true,
@@ -40,6 +111,9 @@ fn main() {
 
 	let mut render_builder = ImageBuilder::new(160, 144, config);
 	let mut controller_state = JoypadState::default();
+	let mut tilt_state = TiltState::default();
+	let mut rewind = Rewind::default();
+	let mut rewinding = false;
 
 	'outer: loop {
 		crossterm::terminal::enable_raw_mode().unwrap();
@@ -69,19 +143,59 @@ fn main() {
 					KeyCode::Tab => controller_state.select = is_down,
 					KeyCode::Char('z') => controller_state.a = is_down,
 					KeyCode::Char('x') => controller_state.b = is_down,
+					KeyCode::Char('j') => tilt_state.x = if is_down { -1.0 } else { 0.0 },
+					KeyCode::Char('l') => tilt_state.x = if is_down { 1.0 } else { 0.0 },
+					KeyCode::Char('i') => tilt_state.y = if is_down { -1.0 } else { 0.0 },
+					KeyCode::Char('k') => tilt_state.y = if is_down { 1.0 } else { 0.0 },
+					// Hold to rewind
+					KeyCode::Backspace => rewinding = is_down,
+					KeyCode::Char(digit @ '0'..='9') if is_down => {
+						save_slot = digit.to_digit(10).unwrap() as usize;
+						preview = save_manager
+							.get_save_states()
+							.swap_remove(save_slot)
+							.and_then(|entry| entry.thumbnail)
+							.map(|thumbnail| (thumbnail_to_screen(&thumbnail), Instant::now()));
+					}
+					KeyCode::F(5) if kind == KeyEventKind::Press => {
+						if let Ok(save) = SaveState::try_from(&gb) {
+							_ = save_manager.save_save_state(save, save_slot);
+						}
+					}
+					KeyCode::F(9) if kind == KeyEventKind::Press => {
+						if let Ok(save) = save_manager.load_save_state(save_slot) {
+							if let Ok(state) = gb.try_load_save_state(&save) {
+								gb = state;
+								rewind.clear();
+							}
+						}
+					}
 					_ => {}
 				}
 			}
 		}
 
 		gb.set_controller_state(&controller_state);
+		gb.set_tilt_state(&tilt_state);
 
-		let start_frame = gb.ppu.frame;
+		if rewinding {
+			if let Some(state) = rewind.step_back(&gb) {
+				gb = state;
+			}
+		} else {
+			let start_frame = gb.ppu.frame;
 
-		while gb.ppu.frame == start_frame {
-			gb.step();
+			while gb.ppu.frame == start_frame {
+				gb.step();
+			}
+			rewind.capture(&gb);
 		}
-		let screen = gb.ppu.lcd.front_buffer();
+		_ = save_manager.flush_battery_periodically(&mut gb);
+
+		let screen = match &preview {
+			Some((image, shown)) if shown.elapsed() < Duration::from_secs(1) => image,
+			_ => gb.ppu.lcd.front_buffer(),
+		};
 		render_builder.draw_img(screen);
 		let output = render_builder.build();
 		let mut stdout = std::io::stdout();
@@ -92,6 +206,11 @@ fn main() {
 		}
 	}
 
+	save_manager.flush_battery(&mut gb).unwrap();
 	crossterm::terminal::disable_raw_mode().unwrap();
 	execute!(stdout, PopKeyboardEnhancementFlags).unwrap();
+
+	if let Some(lock) = gb.cpu_state.locked {
+		eprintln!("{lock}");
+	}
 }